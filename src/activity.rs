@@ -0,0 +1,158 @@
+use crate::model::now_secs;
+use crate::paths;
+use crate::report::{csv_field, format_time};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+
+// 活动的种类
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Created,
+    Renamed,
+    Completed,
+    Reopened, // 取消完成
+    TimerStarted,
+    TimerStopped,
+    Deleted, // 移到回收站
+}
+
+impl EventKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            EventKind::Created => "添加",
+            EventKind::Renamed => "重命名",
+            EventKind::Completed => "完成",
+            EventKind::Reopened => "取消完成",
+            EventKind::TimerStarted => "开始计时",
+            EventKind::TimerStopped => "结束计时",
+            EventKind::Deleted => "删除",
+        }
+    }
+}
+
+// 一条活动记录，删除项目时 todo 为空
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Event {
+    pub at: u64,
+    pub kind: EventKind,
+    pub project: String,
+    pub todo: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>, // 重命名前的标题
+}
+
+impl Event {
+    pub fn new(kind: EventKind, project: &str, todo: &str) -> Self {
+        Self {
+            at: now_secs(),
+            kind,
+            project: project.to_string(),
+            todo: todo.to_string(),
+            detail: None,
+        }
+    }
+
+    // 显示的一行，如 "2026-03-04 10:15 完成 工作 / 写周报"
+    pub fn format(&self) -> String {
+        let target = match self.todo.is_empty() {
+            true => format!("项目「{}」", self.project),
+            false => format!("{} / {}", self.project, self.todo),
+        };
+        match &self.detail {
+            Some(detail) => format!(
+                "{} {} {}（原为「{}」）",
+                format_time(self.at),
+                self.kind.label(),
+                target,
+                detail
+            ),
+            None => format!("{} {} {}", format_time(self.at), self.kind.label(), target),
+        }
+    }
+}
+
+// 活动记录文件放在数据文件旁边，如 data.json 对应 data.activity.jsonl，每行一条记录
+pub fn log_file_path() -> String {
+    let data_file = paths::data_file();
+    let stem = data_file.strip_suffix(".json").unwrap_or(&data_file);
+    format!("{}.activity.jsonl", stem)
+}
+
+// 把记录追加到文件末尾，已有的记录不会改动
+pub fn append(path: &str, events: &[Event]) -> io::Result<()> {
+    if events.is_empty() {
+        return Ok(());
+    }
+    if let Some(parent) = std::path::Path::new(path).parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut lines = String::new();
+    for event in events {
+        lines.push_str(&serde_json::to_string(event)?);
+        lines.push('\n');
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(lines.as_bytes())
+}
+
+// 读取全部记录，从旧到新，文件不存在时为空，跳过写入中途崩溃留下的不完整的行
+pub fn load(path: &str) -> Vec<Event> {
+    fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+// 导出为 CSV：时间、活动、项目、Todo、原标题
+pub fn csv(events: &[Event]) -> String {
+    let mut csv = String::from("time,event,project,todo,detail\n");
+    for event in events {
+        csv.push_str(&format!(
+            "{},{},{},{},{}\n",
+            format_time(event.at),
+            csv_field(event.kind.label()),
+            csv_field(&event.project),
+            csv_field(&event.todo),
+            csv_field(event.detail.as_deref().unwrap_or_default())
+        ));
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_are_appended_and_loaded_in_order() {
+        let dir = std::env::temp_dir().join(format!("s_todo_activity_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir
+            .join("data.activity.jsonl")
+            .to_string_lossy()
+            .into_owned();
+
+        let created = Event::new(EventKind::Created, "工作", "写周报");
+        let renamed = Event {
+            detail: Some("写周报".to_string()),
+            ..Event::new(EventKind::Renamed, "工作", "写月报")
+        };
+        append(&path, std::slice::from_ref(&created)).unwrap();
+        append(&path, std::slice::from_ref(&renamed)).unwrap();
+        // 崩溃时写了一半的行
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"at\": 1, \"ki").unwrap();
+
+        let events = load(&path);
+        assert_eq!(events, vec![created, renamed]);
+        assert!(events[1]
+            .format()
+            .ends_with("重命名 工作 / 写月报（原为「写周报」）"));
+        let csv = csv(&events);
+        assert!(csv.ends_with(",重命名,工作,写月报,写周报\n"));
+        assert_eq!(load(&dir.join("missing").to_string_lossy()), vec![]);
+    }
+}
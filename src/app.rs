@@ -0,0 +1,4626 @@
+use crate::activity::{self, Event, EventKind};
+use crate::config::Config;
+use crate::dates;
+use crate::keys::KeyBindings;
+use crate::message::{Level, Message, MessageLog};
+use crate::model::{
+    arrange_tree, format_duration, fuzzy_score, now_secs, parse_duration, parse_quick_add,
+    parse_tags, project_subtree, purge_trash, today, AppData, DurationUnit, Project, Recurrence,
+    SortMode, Status, Subtask, Template, Templated, Todo, TrashItem, Trashed, DATA_VERSION,
+};
+use crate::palette::{self, Command};
+use crate::paths;
+use crate::pomodoro::{Phase, Pomodoro};
+use crate::report::{self, AgendaGroup, ReportRange};
+use crate::state::UiState;
+use crate::storage::{self, LoadError};
+use crate::text_input::TextInput;
+use crate::theme::{Icons, Theme};
+use chrono::NaiveDate;
+use ratatui::layout::Rect;
+use ratatui::widgets::ListState;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::Instant;
+
+// 项目面板宽度的调整范围和步长（百分比）
+const SPLIT_MIN: u16 = 15;
+const SPLIT_MAX: u16 = 60;
+const SPLIT_STEP: u16 = 5;
+
+// 对已完成的任务开始计时或番茄钟时的提示
+const COMPLETED_TIMER_WARNING: &str = "已完成的任务不能计时";
+
+pub struct App {
+    pub projects: Vec<Project>,
+    pub project_state: ListState,
+    pub todo_state: ListState,
+    pub active_panel: Panel,
+    pub input_mode: InputMode,
+    pub input: TextInput,
+    pub read_only: bool,          // 数据文件版本过新时禁止写回
+    pub message: Option<Message>, // 显示在底部的提示信息
+    pub messages: MessageLog,     // 最近的提示记录
+    pub theme: Theme,
+    pub keys: KeyBindings,
+    pub config: Config,
+    pub description_expanded: bool,          // 描述是否完整显示
+    pub description_scroll: u16,             // 展开描述时的滚动位置
+    pub focus_mode: bool,                    // 隐藏项目面板，Todo 列表占满宽度
+    pub detail_pane: bool,                   // 在 Todo 列表旁边显示选中 todo 的详情
+    pub project_split: Option<u16>, // 项目面板占的宽度（百分比），没有调整过时按终端宽度自动选择
+    pub single_pane: bool,          // 一次只显示当前面板，占满整个区域
+    pub selecting: bool,            // 多选模式
+    pub marked_todos: HashSet<usize>, // 多选模式下选中的 todo 在当前项目 todos 中的下标
+    pub tag_filter: Option<String>, // 只显示带有该标签的 todo
+    pub hide_completed: bool,       // 列表中隐藏已完成的 todo，看板中仍然显示
+    pub search: Option<String>,     // 确认后的搜索关键字（小写），用于跳转到匹配项
+    pub finder_results: Vec<(usize, usize)>, // 全局查找的结果（项目下标, todo 下标），按匹配度排列
+    pub finder_state: ListState,
+    pub palette_results: Vec<Command>, // 命令面板中匹配的命令，按匹配度排列
+    pub palette_state: ListState,
+    pub view: View,
+    pub trash: Vec<TrashItem>,
+    pub archived_projects: Vec<Project>, // 归档的项目，从旧到新排列
+    pub templates: Vec<Template>,
+    pub templates_state: ListState,         // 模板弹窗中选中的模板
+    pub trash_state: ListState,             // 回收站中选中的位置，回收站按从新到旧显示
+    pub subtask_state: ListState,           // 子任务视图中选中的子任务
+    pub picker_state: ListState,            // 项目选择弹窗中选中的项目
+    pub merging_project: bool,              // 项目选择弹窗用于把当前项目合并到选中的项目
+    pub board_column: usize,                // 看板中选中的列，对应 Status::ALL 的下标
+    pub archive_state: ListState,           // 归档视图中选中的位置，归档按从新到旧显示
+    pub archived_projects_state: ListState, // 归档项目视图中选中的位置，从新到旧显示
+    pub session_state: ListState,           // 计时记录视图中选中的记录
+    pub pomodoro: Option<Pomodoro>,
+    pub notifications: Vec<String>,     // 待发送的桌面通知
+    pub reminded_on: Option<NaiveDate>, // 上次发送到期提醒的日期，每天只提醒一次
+    pub interrupted_at: u64,            // 上次退出时的保存时间，用于处理仍在进行的计时
+    pub last_activity: u64,             // 最后一次按键的时间
+    pub idle_since: u64,                // 空闲询问中空闲开始的时间
+    pub report_range: ReportRange,
+    pub report_offset: u64, // 报告往前翻的范围数，0 表示当前的今天或本周
+    pub report_scroll: u16,
+    pub help_scroll: u16,           // 按键帮助的滚动位置
+    pub messages_scroll: u16,       // 消息记录的滚动位置
+    pub unsaved_events: Vec<Event>, // 还没有写入活动记录文件的活动，保存时追加
+    pub activity_log: Vec<Event>,   // 活动记录视图中显示的记录，从新到旧
+    pub activity_scroll: u16,
+    pub today_scroll: u16,                        // 今日回顾的滚动位置
+    pub calendar_day: NaiveDate,                  // 月历中选中的日期
+    pub agenda: Vec<(AgendaGroup, usize, usize)>, // 日程中的 todo（分组, 项目下标, todo 下标）
+    pub agenda_state: ListState,
+    pub workspace: Option<String>, // 当前工作区，默认工作区为 None
+    pub workspace_list: Vec<Option<String>>, // 工作区弹窗中的工作区，第一个是默认工作区
+    pub workspace_state: ListState,
+    pub project_list_area: Rect, // 最后一次绘制的项目列表区域，用于鼠标点击，没有显示时为空
+    pub todo_list_area: Rect,
+    pub last_click: Option<(Instant, usize)>, // 上次点击 Todo 的时间和行，用于识别双击
+    pub count_prefix: Option<usize>,          // 已输入的数字前缀，如 5j 中的 5
+    pub pending_g: bool,                      // 已按下 gg 中的第一个 g
+}
+
+// 当前显示的视图，覆盖视图打开时按键只作用于该视图
+#[derive(PartialEq)]
+pub enum View {
+    Main,
+    Report,   // 按日期分组的时间报告
+    Subtasks, // 选中 todo 的子任务
+    ConfirmDeleteProject,
+    ConfirmDeleteTodos, // 确认删除多选模式下选中的任务
+    Trash,
+    ProjectPicker,    // 选择要把 todo 移到哪个项目
+    Board,            // 当前项目按状态分列的看板
+    Archive,          // 当前项目归档的 todo
+    Stats,            // 各项目耗时和每天耗时的统计图表
+    Sessions,         // 选中 todo 的计时记录
+    PomodoroNotice,   // 番茄钟阶段结束的提示
+    ResumeTimers,     // 启动时询问如何处理上次退出时仍在进行的计时
+    IdlePrompt,       // 空闲后回来时询问是否保留空闲期间的计时
+    CorruptData,      // 数据文件损坏且无法恢复，询问重新开始还是退出
+    Help,             // 所有操作和当前绑定的按键
+    Messages,         // 最近的提示记录
+    Details,          // 选中 todo 的详情
+    Activity,         // 活动记录
+    Today,            // 今日回顾
+    Calendar,         // 月历
+    Agenda,           // 所有项目的日程
+    Zen,              // 全屏只显示正在计时的 todo
+    Workspaces,       // 切换工作区
+    ArchivedProjects, // 归档的项目
+    Templates,        // 选择模板创建项目或 todo
+}
+
+// 上次退出时仍在进行的计时的处理方式
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ResumeChoice {
+    Keep,     // 继续计时，关闭期间的时间也计入
+    Truncate, // 计时记到上次退出为止并结束
+    Discard,  // 丢弃这次计时
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Panel {
+    #[default]
+    Projects,
+    Todos,
+}
+
+#[derive(PartialEq)]
+pub enum InputMode {
+    Normal,
+    AddingProject,
+    AddingTodo,
+    RenamingProject,
+    RenamingTodo,
+    MovingTodos, // 输入目标项目名，移动多选的 todo
+    SettingDueDate,
+    FilteringTag,
+    AddingSubtask,
+    EditingDescription, // 多行输入，Enter 换行
+    EditingSession,     // 修改计时记录的开始和结束时间
+    LoggingTime,        // 补记一段耗时
+    SettingEstimate,    // 预计耗时
+    AddingWorkspace,    // 输入新工作区的名称
+    SettingParent,      // 输入上级项目的名称
+    NamingTemplate,     // 输入保存为模板的名称
+    TaggingTodos,       // 输入要添加到选中任务的标签
+    Searching,          // 输入时实时筛选 todo
+    Finding,            // 在所有项目中模糊查找 todo
+    Commanding,         // 在命令面板中按名称查找命令
+}
+
+impl App {
+    // 从数据文件加载并创建 App
+    pub fn load() -> App {
+        let mut app = match storage::load_with_notice() {
+            Ok((mut app_data, notice)) => {
+                // 没有保存时间的旧数据无法判断关闭了多久，直接结束计时
+                let finished = match app_data.saved_at {
+                    0 => app_data.finish_interrupted_sessions(),
+                    _ => 0,
+                };
+                let mut app = Self::with_projects(app_data.projects);
+                app.trash = app_data.trash;
+                app.archived_projects = app_data.archived_projects;
+                app.templates = app_data.templates;
+                if let Some(notice) = notice {
+                    app.warn(notice);
+                }
+                if finished > 0 {
+                    app.warn(format!(
+                        "已结束 {} 个上次未正常结束的计时，时间记录到最后一次保存",
+                        finished
+                    ));
+                }
+                // 上次退出时仍在计时，询问关闭期间的时间如何处理
+                if app.has_running_timer() {
+                    app.interrupted_at = app_data.saved_at;
+                    app.view = View::ResumeTimers;
+                }
+                app
+            }
+            Err(err) => {
+                let mut app = Self::with_projects(vec![]);
+                app.read_only = true;
+                // 数据文件损坏时让用户选择重新开始或退出
+                if let LoadError::Corrupt(_) = err {
+                    app.view = View::CorruptData;
+                }
+                app.error(err.into_message());
+                app
+            }
+        };
+
+        match Theme::load() {
+            Ok(theme) => app.theme = theme,
+            Err(err) => {
+                app.error(err);
+            }
+        }
+        match KeyBindings::load() {
+            Ok(keys) => app.keys = keys,
+            Err(err) => {
+                app.error(err);
+            }
+        }
+        match Config::load() {
+            Ok(config) => app.config = config,
+            Err(err) => {
+                app.error(err);
+            }
+        }
+        app.restore_ui_state(&UiState::load());
+        app.workspace = paths::workspace();
+
+        if app.config.trash_retention_days > 0 {
+            purge_trash(&mut app.trash, now_secs(), app.config.trash_retention_days);
+        }
+        app
+    }
+
+    // 恢复上次的选中位置，数据在外部被修改过时修正到有效范围
+    pub fn restore_ui_state(&mut self, state: &UiState) {
+        if self.projects.is_empty() {
+            return;
+        }
+
+        let project_idx = state.project.unwrap_or(0).min(self.projects.len() - 1);
+        self.hide_completed = state.hide_completed;
+        self.detail_pane = state.detail_pane;
+        self.project_split = state.project_split.map(|p| p.clamp(SPLIT_MIN, SPLIT_MAX));
+        self.single_pane = state.single_pane;
+        self.project_state.select(Some(project_idx));
+        self.unfold_ancestors(project_idx);
+        self.reset_todo_selection();
+        let todos_len = self.projects[project_idx].todos.len();
+        if let (Some(todo_idx), true) = (state.todo, todos_len > 0) {
+            self.select_todo_index(todo_idx.min(todos_len - 1));
+        }
+        // 上次选中的 todo 已完成并被隐藏时选中第一个
+        if self.todo_state.selected().is_none() {
+            self.reset_todo_selection();
+        }
+        self.active_panel = state.active_panel;
+    }
+
+    // 当前的界面状态，退出时保存
+    pub fn ui_state(&self) -> UiState {
+        UiState {
+            project: self.project_state.selected(),
+            todo: self.selected_todo_index(),
+            active_panel: self.active_panel,
+            hide_completed: self.hide_completed,
+            detail_pane: self.detail_pane,
+            project_split: self.project_split,
+            single_pane: self.single_pane,
+        }
+    }
+
+    // 使用给定的项目列表创建 App，不读取数据文件
+    pub fn with_projects(mut projects: Vec<Project>) -> App {
+        arrange_tree(&mut projects);
+        let mut app = App {
+            projects,
+            project_state: ListState::default(),
+            todo_state: ListState::default(),
+            active_panel: Panel::Projects,
+            input_mode: InputMode::Normal,
+            input: TextInput::default(),
+            read_only: false,
+            message: None,
+            messages: MessageLog::default(),
+            theme: Theme::default(),
+            keys: KeyBindings::default(),
+            config: Config::default(),
+            description_expanded: false,
+            description_scroll: 0,
+            focus_mode: false,
+            detail_pane: false,
+            project_split: None,
+            single_pane: false,
+            selecting: false,
+            marked_todos: HashSet::new(),
+            tag_filter: None,
+            hide_completed: false,
+            search: None,
+            finder_results: vec![],
+            finder_state: ListState::default(),
+            palette_results: vec![],
+            palette_state: ListState::default(),
+            view: View::Main,
+            trash: vec![],
+            archived_projects: vec![],
+            templates: vec![],
+            templates_state: ListState::default(),
+            trash_state: ListState::default(),
+            subtask_state: ListState::default(),
+            picker_state: ListState::default(),
+            merging_project: false,
+            board_column: 0,
+            archive_state: ListState::default(),
+            archived_projects_state: ListState::default(),
+            session_state: ListState::default(),
+            pomodoro: None,
+            notifications: Vec::new(),
+            reminded_on: None,
+            interrupted_at: 0,
+            last_activity: now_secs(),
+            idle_since: 0,
+            report_range: ReportRange::Day,
+            report_offset: 0,
+            report_scroll: 0,
+            help_scroll: 0,
+            messages_scroll: 0,
+            unsaved_events: vec![],
+            activity_log: vec![],
+            activity_scroll: 0,
+            today_scroll: 0,
+            calendar_day: today(),
+            agenda: vec![],
+            agenda_state: ListState::default(),
+            workspace: None,
+            workspace_list: vec![],
+            workspace_state: ListState::default(),
+            project_list_area: Rect::default(),
+            todo_list_area: Rect::default(),
+            last_click: None,
+            count_prefix: None,
+            pending_g: false,
+        };
+
+        if !app.projects.is_empty() {
+            app.project_state.select(Some(0));
+            app.reset_todo_selection();
+        }
+        app
+    }
+
+    // 保存数据，并轮换备份，返回是否写入成功
+    pub fn save_data(&mut self) -> bool {
+        self.save(true)
+    }
+
+    // 计时进行中的定期保存，只为更新保存时间，不轮换备份
+    pub fn save_heartbeat(&mut self) -> bool {
+        self.save(false)
+    }
+
+    // 手动保存，成功时显示确认
+    pub fn save_manually(&mut self) {
+        if self.save_data() {
+            self.info("已保存");
+        }
+    }
+
+    // 写入失败时显示错误，修改保留在内存中，下次保存时重试
+    fn save(&mut self, backup: bool) -> bool {
+        if self.read_only {
+            return false;
+        }
+
+        let app_data = AppData {
+            version: DATA_VERSION,
+            saved_at: now_secs(),
+            projects: self.projects.clone(),
+            trash: self.trash.clone(),
+            archived_projects: self.archived_projects.clone(),
+            templates: self.templates.clone(),
+        };
+
+        match storage::write_data(&app_data, backup) {
+            Ok(()) => {
+                self.flush_events();
+                true
+            }
+            Err(err) => {
+                self.error(format!("保存失败：{}", err));
+                false
+            }
+        }
+    }
+
+    // 数据保存后把新的活动追加到活动记录文件，失败时保留在内存中，下次保存时重试
+    fn flush_events(&mut self) {
+        match activity::append(&activity::log_file_path(), &self.unsaved_events) {
+            Ok(()) => self.unsaved_events.clear(),
+            Err(err) => self.error(format!("写入活动记录失败：{}", err)),
+        }
+    }
+
+    // 记录当前项目中的一次活动
+    fn record(&mut self, kind: EventKind, todo: &str) {
+        let project = self.current_project_name();
+        self.unsaved_events.push(Event::new(kind, &project, todo));
+    }
+
+    fn current_project_name(&self) -> String {
+        self.get_current_project()
+            .map_or_else(String::new, |p| p.name.clone())
+    }
+
+    // 打开活动记录，包括还没有写入文件的活动
+    pub fn open_activity(&mut self) {
+        self.open_activity_from(&activity::log_file_path());
+    }
+
+    // 从给定的活动记录文件打开活动记录
+    fn open_activity_from(&mut self, path: &str) {
+        let mut events = activity::load(path);
+        events.extend(self.unsaved_events.iter().cloned());
+        events.reverse();
+        self.activity_log = events;
+        self.activity_scroll = 0;
+        self.view = View::Activity;
+    }
+
+    // 打开工作区弹窗，列出默认工作区和已有的工作区，选中当前工作区
+    pub fn open_workspaces(&mut self) {
+        if paths::has_data_file() {
+            self.warn("使用 --data-file 时不能切换工作区");
+            return;
+        }
+        self.workspace_list = std::iter::once(None)
+            .chain(paths::workspaces().into_iter().map(Some))
+            .collect();
+        // 还没有保存过的新工作区也列出来
+        if !self.workspace_list.contains(&self.workspace) {
+            self.workspace_list.push(self.workspace.clone());
+        }
+        let current = self
+            .workspace_list
+            .iter()
+            .position(|w| *w == self.workspace);
+        self.workspace_state.select(current);
+        self.view = View::Workspaces;
+    }
+
+    // 在工作区弹窗中移动光标，到头后回到另一端
+    pub fn select_workspace(&mut self, down: bool) {
+        let len = self.workspace_list.len();
+        if len == 0 {
+            return;
+        }
+        let i = self.workspace_state.selected().unwrap_or(0);
+        self.workspace_state.select(Some(if down {
+            (i + 1) % len
+        } else {
+            (i + len - 1) % len
+        }));
+    }
+
+    // 切换到工作区弹窗中选中的工作区
+    pub fn switch_to_selected_workspace(&mut self) {
+        let Some(workspace) = self
+            .workspace_state
+            .selected()
+            .and_then(|i| self.workspace_list.get(i))
+            .cloned()
+        else {
+            return;
+        };
+        self.switch_workspace(workspace);
+    }
+
+    // 保存当前工作区后重新加载另一个工作区的数据、状态和配置，新工作区第一次保存时创建数据文件
+    fn switch_workspace(&mut self, workspace: Option<String>) {
+        if workspace == self.workspace {
+            self.close_view();
+            return;
+        }
+        if let Err(err) = workspace
+            .as_deref()
+            .map_or(Ok(()), paths::check_workspace_name)
+        {
+            self.warn(err);
+            return;
+        }
+        if !self.read_only && !self.save_data() {
+            return;
+        }
+        let _ = self.ui_state().save();
+        if let Err(err) = paths::set_workspace(workspace) {
+            self.warn(err);
+            return;
+        }
+        *self = App::load();
+        let name = self.workspace.as_deref().unwrap_or("默认");
+        self.info(format!("已切换到工作区「{}」", name));
+    }
+
+    // 全屏显示正在计时的 todo，没有计时时不打开
+    pub fn open_zen(&mut self) {
+        if self.running_todo().is_none() {
+            self.warn("没有正在计时的任务");
+            return;
+        }
+        self.view = View::Zen;
+    }
+
+    // 打开日程，列出所有项目中有截止日期的未完成 todo
+    pub fn open_agenda(&mut self) {
+        self.agenda = report::agenda(&self.projects, today());
+        if self.agenda.is_empty() {
+            self.warn("没有设置了截止日期的未完成任务");
+            return;
+        }
+        self.view = View::Agenda;
+        self.agenda_state.select(Some(0));
+    }
+
+    // 在日程中移动光标，到头后回到另一端
+    pub fn select_agenda(&mut self, down: bool) {
+        let len = self.agenda.len();
+        if len == 0 {
+            return;
+        }
+        let i = self.agenda_state.selected().unwrap_or(0);
+        self.agenda_state.select(Some(if down {
+            (i + 1) % len
+        } else {
+            (i + len - 1) % len
+        }));
+    }
+
+    // 跳到日程中选中的 todo
+    pub fn confirm_agenda(&mut self) {
+        let Some(&(_, project_idx, todo_idx)) = self
+            .agenda_state
+            .selected()
+            .and_then(|i| self.agenda.get(i))
+        else {
+            return;
+        };
+        self.view = View::Main;
+        self.reveal_todo(project_idx, todo_idx);
+    }
+
+    // 打开月历，选中今天
+    pub fn open_calendar(&mut self) {
+        self.view = View::Calendar;
+        self.calendar_day = today();
+    }
+
+    // 月历中前后移动若干天，跨月时月历跟着翻页
+    pub fn move_calendar_day(&mut self, days: i64) {
+        self.calendar_day = self
+            .calendar_day
+            .checked_add_signed(chrono::Duration::days(days))
+            .unwrap_or(self.calendar_day);
+    }
+
+    // 切换到上个月或下个月的同一天，没有这一天时取月末
+    pub fn shift_calendar_month(&mut self, earlier: bool) {
+        let months = chrono::Months::new(1);
+        let day = match earlier {
+            true => self.calendar_day.checked_sub_months(months),
+            false => self.calendar_day.checked_add_months(months),
+        };
+        self.calendar_day = day.unwrap_or(self.calendar_day);
+    }
+
+    pub fn open_today(&mut self) {
+        self.view = View::Today;
+        self.today_scroll = 0;
+    }
+
+    pub fn scroll_today(&mut self, down: bool) {
+        self.today_scroll = if down {
+            self.today_scroll.saturating_add(1)
+        } else {
+            self.today_scroll.saturating_sub(1)
+        };
+    }
+
+    pub fn scroll_activity(&mut self, down: bool) {
+        self.activity_scroll = if down {
+            self.activity_scroll.saturating_add(1)
+        } else {
+            self.activity_scroll.saturating_sub(1)
+        };
+    }
+
+    pub fn get_current_project(&self) -> Option<&Project> {
+        self.project_state
+            .selected()
+            .and_then(|i| self.projects.get(i))
+    }
+
+    // 按显示顺序获取当前项目的 todo
+    pub fn get_current_todos(&self) -> Vec<&Todo> {
+        if let Some(project) = self.get_current_project() {
+            self.visible_todo_indices()
+                .into_iter()
+                .map(|i| &project.todos[i])
+                .collect()
+        } else {
+            vec![]
+        }
+    }
+
+    // 当前项目中按显示顺序排列的 todo 下标，设置了标签筛选时只包含带该标签的 todo
+    pub fn visible_todo_indices(&self) -> Vec<usize> {
+        let Some(project) = self.get_current_project() else {
+            return vec![];
+        };
+        let mut indices = project.sorted_indices();
+        if let Some(tag) = &self.tag_filter {
+            indices.retain(|&i| project.todos[i].has_tag(tag));
+        }
+        if self.hide_completed && self.view != View::Board {
+            indices.retain(|&i| !project.todos[i].completed);
+        }
+        // 输入搜索关键字时实时筛选
+        if self.input_mode == InputMode::Searching {
+            let query = self.input.trim().to_lowercase();
+            indices.retain(|&i| project.todos[i].matches(&query));
+        }
+        indices
+    }
+
+    // 当前选中的 todo 在项目 todos 中的下标（todo_state 记录的是显示位置）
+    pub fn selected_todo_index(&self) -> Option<usize> {
+        self.todo_state
+            .selected()
+            .and_then(|i| self.visible_todo_indices().get(i).copied())
+    }
+
+    // 按 todos 中的下标选中 todo
+    fn select_todo_index(&mut self, todo_idx: usize) {
+        let position = self
+            .visible_todo_indices()
+            .iter()
+            .position(|&i| i == todo_idx);
+        self.todo_state.select(position);
+    }
+
+    // 执行修改后重新选中原来的 todo，修改可能让它在排序中换了位置
+    fn with_selection_kept<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        let selected = self.selected_todo_index();
+        let position = self.todo_state.selected();
+        let result = f(self);
+        if let Some(todo_idx) = selected {
+            self.select_todo_index(todo_idx);
+            // todo 不再显示（如隐藏已完成时完成了它）时光标停在原来的位置
+            if self.todo_state.selected().is_none() {
+                self.todo_state.select(position);
+                self.clamp_todo_selection();
+            }
+        }
+        result
+    }
+
+    // 获取当前选中的 todo
+    pub fn get_current_todo(&self) -> Option<&Todo> {
+        self.get_current_project()
+            .zip(self.selected_todo_index())
+            .and_then(|(project, todo_idx)| project.todos.get(todo_idx))
+    }
+
+    // 获取当前选中的 todo（可变引用）
+    pub fn get_current_todo_mut(&mut self) -> Option<&mut Todo> {
+        if let (Some(project_idx), Some(todo_idx)) =
+            (self.project_state.selected(), self.selected_todo_index())
+        {
+            self.projects
+                .get_mut(project_idx)
+                .and_then(|project| project.todos.get_mut(todo_idx))
+        } else {
+            None
+        }
+    }
+
+    // 切换项目后重置 todo 选择：有 todo 时选中第一个，否则清空
+    fn reset_todo_selection(&mut self) {
+        let has_todos = !self.visible_todo_indices().is_empty();
+        self.todo_state
+            .select(if has_todos { Some(0) } else { None });
+    }
+
+    // 删除 todo 后修正选择：光标停在原来的位置，超出末尾时选中最后一个
+    fn clamp_todo_selection(&mut self) {
+        let todos_len = self.visible_todo_indices().len();
+        self.todo_state.select(match self.todo_state.selected() {
+            _ if todos_len == 0 => None,
+            Some(position) => Some(position.min(todos_len - 1)),
+            None => Some(0),
+        });
+    }
+
+    // 在项目和 Todo 面板间切换
+    pub fn switch_panel(&mut self) {
+        // 多选只在 Todo 面板中有效，离开时清空
+        self.exit_select_mode();
+        self.active_panel = match self.active_panel {
+            Panel::Projects => {
+                // 切换到 Todo 面板时，确保有选中项
+                let todos = self.get_current_todos();
+                if !todos.is_empty() && self.todo_state.selected().is_none() {
+                    self.todo_state.select(Some(0));
+                }
+                Panel::Todos
+            }
+            Panel::Todos => {
+                // 切换到项目面板时退出专注模式，否则看不到项目面板
+                self.focus_mode = false;
+                // 切换到项目面板时，确保有选中项
+                if !self.projects.is_empty() && self.project_state.selected().is_none() {
+                    self.project_state.select(Some(0));
+                }
+                Panel::Projects
+            }
+        };
+    }
+
+    // 点击项目列表中的第 position 行，选中该项目并切换到项目面板，返回是否点中了项目
+    pub fn click_project(&mut self, position: usize) -> bool {
+        let Some(&idx) = self.visible_project_indices().get(position) else {
+            return false;
+        };
+        if self.active_panel == Panel::Todos {
+            self.switch_panel();
+        }
+        if self.project_state.selected() != Some(idx) {
+            self.project_state.select(Some(idx));
+            self.reset_todo_selection();
+        }
+        true
+    }
+
+    // 点击 Todo 列表中的第 position 行，选中该 todo 并切换到 Todo 面板，返回是否点中了 todo
+    pub fn click_todo(&mut self, position: usize) -> bool {
+        if position >= self.get_current_todos().len() {
+            return false;
+        }
+        if self.active_panel == Panel::Projects {
+            self.switch_panel();
+        }
+        if self.todo_state.selected() != Some(position) {
+            self.description_scroll = 0;
+            self.todo_state.select(Some(position));
+        }
+        true
+    }
+
+    // 项目列表中显示的项目下标，收起的项目的子项目不显示
+    pub fn visible_project_indices(&self) -> Vec<usize> {
+        let mut indices = Vec::with_capacity(self.projects.len());
+        let mut i = 0;
+        while i < self.projects.len() {
+            indices.push(i);
+            i = match self.projects[i].folded {
+                true => project_subtree(&self.projects, i).end,
+                false => i + 1,
+            };
+        }
+        indices
+    }
+
+    // 在显示的项目中移动光标，到头后回到另一端
+    fn select_project(&mut self, down: bool) {
+        let visible = self.visible_project_indices();
+        // 没有项目时不移动，避免 len() - 1 下溢
+        if visible.is_empty() {
+            return;
+        }
+        let len = visible.len();
+        let position = self
+            .project_state
+            .selected()
+            .and_then(|i| visible.iter().position(|&v| v == i));
+        let i = match position {
+            Some(p) if down => visible[(p + 1) % len],
+            Some(p) => visible[(p + len - 1) % len],
+            None => visible[0],
+        };
+        self.project_state.select(Some(i));
+        self.reset_todo_selection();
+    }
+
+    // 向下移动光标，到底后回到第一项
+    pub fn select_next(&mut self) {
+        self.description_scroll = 0;
+        match self.active_panel {
+            Panel::Projects => self.select_project(true),
+            Panel::Todos => {
+                let len = self.get_current_todos().len();
+                if len > 0 {
+                    let i = match self.todo_state.selected() {
+                        Some(i) => {
+                            if i >= len - 1 {
+                                0
+                            } else {
+                                i + 1
+                            }
+                        }
+                        None => 0,
+                    };
+                    self.todo_state.select(Some(i));
+                }
+            }
+        }
+    }
+
+    // 向上移动光标，到顶后回到最后一项
+    pub fn select_previous(&mut self) {
+        self.description_scroll = 0;
+        match self.active_panel {
+            Panel::Projects => self.select_project(false),
+            Panel::Todos => {
+                let len = self.get_current_todos().len();
+                if len > 0 {
+                    let i = match self.todo_state.selected() {
+                        Some(i) => {
+                            if i == 0 {
+                                len - 1
+                            } else {
+                                i - 1
+                            }
+                        }
+                        None => 0,
+                    };
+                    self.todo_state.select(Some(i));
+                }
+            }
+        }
+    }
+
+    // 在当前面板中把光标移动 steps 行，到头后停住，不回到另一端
+    pub fn move_selection(&mut self, steps: usize, down: bool) {
+        let current = match self.active_panel {
+            Panel::Projects => self.project_state.selected().and_then(|idx| {
+                self.visible_project_indices()
+                    .iter()
+                    .position(|&i| i == idx)
+            }),
+            Panel::Todos => self.todo_state.selected(),
+        }
+        .unwrap_or(0);
+        let position = match down {
+            true => current.saturating_add(steps),
+            false => current.saturating_sub(steps),
+        };
+        self.select_position(position);
+    }
+
+    // 选中当前面板中的第 position 行（从 0 开始），超出时选中最后一行
+    pub fn select_position(&mut self, position: usize) {
+        self.description_scroll = 0;
+        match self.active_panel {
+            Panel::Projects => {
+                let visible = self.visible_project_indices();
+                let Some(&idx) = visible.get(position.min(visible.len().saturating_sub(1))) else {
+                    return;
+                };
+                if self.project_state.selected() != Some(idx) {
+                    self.project_state.select(Some(idx));
+                    self.reset_todo_selection();
+                }
+            }
+            Panel::Todos => {
+                let len = self.get_current_todos().len();
+                if len > 0 {
+                    self.todo_state.select(Some(position.min(len - 1)));
+                }
+            }
+        }
+    }
+
+    // 当前面板显示区域的半页行数，用于 Ctrl+D/Ctrl+U，至少一行
+    pub fn half_page(&self) -> usize {
+        let area = match self.active_panel {
+            Panel::Projects => self.project_list_area,
+            Panel::Todos => self.todo_list_area,
+        };
+        (area.height.saturating_sub(2) as usize / 2).max(1)
+    }
+
+    // 切换当前 todo 的完成状态，返回是否有修改
+    // 完成重复的 todo 时提示安排的下一次日期
+    pub fn toggle_selected_todo(&mut self) -> bool {
+        let today = today();
+        let Some(todo) = self.get_current_todo() else {
+            return false;
+        };
+        let (title, reopening) = (todo.title.clone(), todo.completed);
+        let next = self.with_selection_kept(|app| {
+            app.get_current_todo_mut().map(|todo| {
+                if todo.completed {
+                    todo.set_completed(false);
+                    None
+                } else {
+                    todo.complete(today)
+                }
+            })
+        });
+        let kind = match reopening {
+            true => EventKind::Reopened,
+            false => EventKind::Completed,
+        };
+        self.record(kind, &title);
+        if let Some(Some(next)) = next {
+            self.info(format!("已安排下一次：{}", next.format("%Y-%m-%d")));
+        }
+        next.is_some()
+    }
+
+    // 切换选中 todo 的重复规则，返回是否有修改
+    pub fn cycle_recurrence(&mut self) -> bool {
+        match self.get_current_todo_mut() {
+            Some(todo) => {
+                todo.recurrence = Recurrence::cycle(todo.recurrence);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // 全部完成时全部取消完成，否则全部标记为完成，返回是否有修改
+    pub fn toggle_all_todos(&mut self) -> bool {
+        self.with_selection_kept(Self::toggle_all_todos_in_place)
+    }
+
+    // 设置了标签筛选时只作用于显示出来的 todo
+    fn toggle_all_todos_in_place(&mut self) -> bool {
+        let indices = self.visible_todo_indices();
+        let Some(project) = self
+            .project_state
+            .selected()
+            .and_then(|i| self.projects.get_mut(i))
+        else {
+            return false;
+        };
+        if indices.is_empty() {
+            return false;
+        }
+
+        let all_completed = indices.iter().all(|&i| project.todos[i].completed);
+        let today = today();
+        let mut changed = vec![];
+        for &i in &indices {
+            let todo = &mut project.todos[i];
+            if all_completed {
+                todo.set_completed(false);
+                changed.push(todo.title.clone());
+            } else if !todo.completed {
+                todo.complete(today);
+                changed.push(todo.title.clone());
+            }
+        }
+        let kind = match all_completed {
+            true => EventKind::Reopened,
+            false => EventKind::Completed,
+        };
+        for title in changed {
+            self.record(kind, &title);
+        }
+        true
+    }
+
+    // 结束所有正在进行的计时，返回结束的数量
+    pub fn stop_all_timers(&mut self) -> usize {
+        let mut stopped = 0;
+        for todo in self.projects.iter_mut().flat_map(|p| p.todos.iter_mut()) {
+            if todo.is_working() {
+                todo.end_work();
+                stopped += 1;
+            }
+        }
+        stopped
+    }
+
+    // 处理上次退出时仍在进行的计时，返回是否有修改
+    pub fn resume_timers(&mut self, choice: ResumeChoice) -> bool {
+        let end = self.interrupted_at;
+        for todo in self.projects.iter_mut().flat_map(|p| p.todos.iter_mut()) {
+            if !todo.is_working() {
+                continue;
+            }
+            match choice {
+                ResumeChoice::Keep => {}
+                ResumeChoice::Truncate => todo.end_work_at(end),
+                ResumeChoice::Discard => todo.discard_session(),
+            }
+        }
+        self.info(match choice {
+            ResumeChoice::Keep => "计时继续，关闭期间的时间已计入",
+            ResumeChoice::Truncate => "计时已结束，时间记录到上次退出",
+            ResumeChoice::Discard => "已丢弃上次未结束的计时",
+        });
+        self.close_view();
+        choice != ResumeChoice::Keep
+    }
+
+    // 记录一次按键，计时中空闲超过阈值时弹出询问，返回是否弹出
+    pub fn record_activity(&mut self, now: u64) -> bool {
+        let last = std::mem::replace(&mut self.last_activity, now);
+        let Some(threshold) = self.config.idle_secs() else {
+            return false;
+        };
+        let idle = now.saturating_sub(last) >= threshold
+            && self.has_running_timer()
+            && self.input_mode == InputMode::Normal
+            && self.view != View::ResumeTimers;
+        if idle {
+            self.idle_since = last;
+            self.view = View::IdlePrompt;
+        }
+        idle
+    }
+
+    // 处理空闲期间的计时：丢弃时计时记到空闲开始为止，再从现在重新开始，返回是否有修改
+    pub fn resolve_idle(&mut self, keep: bool) -> bool {
+        self.close_view();
+        if keep {
+            return false;
+        }
+        let idle_since = self.idle_since;
+        for todo in self.projects.iter_mut().flat_map(|p| p.todos.iter_mut()) {
+            if todo.is_working() {
+                todo.end_work_at(idle_since);
+                todo.start_work();
+            }
+        }
+        self.info("已丢弃空闲时间，计时从现在继续");
+        true
+    }
+
+    // 数据文件损坏时重新开始：使用示例数据并允许保存，损坏的文件已另外保存
+    pub fn start_fresh(&mut self) {
+        let app_data = storage::sample_data();
+        self.projects = app_data.projects;
+        self.trash = app_data.trash;
+        self.read_only = false;
+        self.project_state.select(Some(0));
+        self.reset_todo_selection();
+        self.close_view();
+        self.info("已重新开始");
+    }
+
+    // 是否有正在进行的计时
+    pub fn has_running_timer(&self) -> bool {
+        self.projects
+            .iter()
+            .flat_map(|p| p.todos.iter())
+            .any(|todo| todo.is_working())
+    }
+
+    // 在选中的 todo 上开始番茄钟并开始计时，番茄钟进行中时取消，返回是否有修改
+    pub fn toggle_pomodoro(&mut self) -> bool {
+        if self.pomodoro.take().is_some() {
+            self.info("已取消番茄钟");
+            return false;
+        }
+        let started = match self.get_current_todo() {
+            // 和计时一样，已完成的任务不能开始番茄钟
+            Some(todo) if todo.completed => {
+                self.warn(COMPLETED_TIMER_WARNING);
+                return false;
+            }
+            Some(todo) => !todo.is_working(),
+            None => return false,
+        };
+        if started {
+            self.stop_other_timers();
+            if let Some(todo) = self.get_current_todo_mut() {
+                todo.start_work();
+                let title = todo.title.clone();
+                self.record(EventKind::TimerStarted, &title);
+            }
+        }
+        self.pomodoro = Some(Pomodoro::new(now_secs()));
+        started
+    }
+
+    // 推进番茄钟，阶段结束时弹出提示，返回是否需要响铃并保存
+    // 工作结束时停止计时并为计时的 todo 记一个番茄钟，休息结束后番茄钟结束
+    pub fn tick_pomodoro(&mut self, now: u64) -> bool {
+        let running = self.has_running_timer();
+        let (work_secs, break_secs) = self.config.pomodoro_secs();
+        let Some(pomodoro) = &mut self.pomodoro else {
+            return false;
+        };
+        // 工作时手动停止了计时，番茄钟随之结束
+        if pomodoro.phase == Phase::Work && !running {
+            self.pomodoro = None;
+            return false;
+        }
+
+        match pomodoro.advance(now, work_secs, break_secs) {
+            None => return false,
+            Some(Phase::Work) => {
+                let title = self
+                    .projects
+                    .iter_mut()
+                    .flat_map(|p| p.todos.iter_mut())
+                    .find(|todo| todo.is_working())
+                    .map(|todo| {
+                        todo.end_work_at(now);
+                        todo.pomodoros += 1;
+                        todo.title.clone()
+                    })
+                    .unwrap_or_default();
+                self.notify(format!(
+                    "🍅 番茄钟完成：{}，休息 {} 分钟吧",
+                    title,
+                    break_secs / 60
+                ));
+            }
+            Some(Phase::Break) => {
+                self.pomodoro = None;
+                self.notify("☕ 休息结束，开始下一个番茄钟吧".to_string());
+            }
+        }
+        self.view = View::PomodoroNotice;
+        true
+    }
+
+    // 第一个正在计时的 todo 及其所在项目，用于在状态栏显示
+    pub fn running_todo(&self) -> Option<(&Project, &Todo)> {
+        self.projects.iter().find_map(|project| {
+            project
+                .todos
+                .iter()
+                .find(|todo| todo.is_working())
+                .map(|todo| (project, todo))
+        })
+    }
+
+    // 检查正在计时的 todo 是否刚超过提醒阈值，每次计时只提醒一次，返回是否需要提醒
+    pub fn check_timer_alerts(&mut self, now: u64) -> bool {
+        let Some(threshold) = self.config.timer_alert_secs() else {
+            return false;
+        };
+
+        let mut alerted = Vec::new();
+        for todo in self.projects.iter_mut().flat_map(|p| p.todos.iter_mut()) {
+            if !todo.alerted && todo.session_elapsed(now) >= threshold {
+                todo.alerted = true;
+                alerted.push(todo.title.clone());
+            }
+        }
+        for title in &alerted {
+            self.notify(format!(
+                "⏱️ 「{}」已连续计时 {} 分钟",
+                title, self.config.timer_alert_minutes
+            ));
+        }
+        !alerted.is_empty()
+    }
+
+    // 每天第一次检查时，把今天到期和已过期的 todo 汇总成一条通知
+    pub fn check_due_reminders(&mut self, today: NaiveDate) {
+        if self.reminded_on == Some(today) {
+            return;
+        }
+        self.reminded_on = Some(today);
+
+        let due: Vec<&str> = self
+            .projects
+            .iter()
+            .flat_map(|p| &p.todos)
+            .filter(|todo| !todo.completed && todo.due_date.is_some_and(|due| due <= today))
+            .map(|todo| todo.title.as_str())
+            .collect();
+        if !due.is_empty() {
+            let body = format!(
+                "📅 {} 个 Todo 今天到期或已过期：{}",
+                due.len(),
+                due.join("、")
+            );
+            self.notify(body);
+        }
+    }
+
+    // 启用桌面通知时加入待发送队列，由主循环发送
+    fn notify(&mut self, body: String) {
+        if self.config.desktop_notifications {
+            self.notifications.push(body);
+        }
+    }
+
+    // 只允许一个计时时，在开始新的计时前停止所有正在计时的 todo
+    fn stop_other_timers(&mut self) {
+        if !self.config.single_timer {
+            return;
+        }
+        let mut stopped = vec![];
+        for project in &mut self.projects {
+            for todo in project.todos.iter_mut().filter(|todo| todo.is_working()) {
+                todo.end_work();
+                stopped.push(todo.title.clone());
+                let event = Event::new(EventKind::TimerStopped, &project.name, &todo.title);
+                self.unsaved_events.push(event);
+            }
+        }
+        if !stopped.is_empty() {
+            self.info(format!("已停止「{}」的计时", stopped.join("」「")));
+        }
+    }
+
+    // 切换当前 todo 的计时状态
+    pub fn toggle_current_todo_timer(&mut self) -> bool {
+        self.with_selection_kept(|app| {
+            let starting = app
+                .get_current_todo()
+                .is_some_and(|todo| !todo.completed && !todo.is_working());
+            if starting {
+                app.stop_other_timers();
+            }
+            // 只有未完成的任务才能计时
+            if app.get_current_todo().is_some_and(|todo| todo.completed) {
+                app.warn(COMPLETED_TIMER_WARNING);
+                return false;
+            }
+            let Some(todo) = app.get_current_todo_mut() else {
+                return false;
+            };
+            todo.toggle_work();
+            let kind = match todo.is_working() {
+                true => EventKind::TimerStarted,
+                false => EventKind::TimerStopped,
+            };
+            let title = todo.title.clone();
+            app.record(kind, &title);
+            true
+        })
+    }
+
+    // 展开或收起当前 todo 的描述
+    pub fn toggle_description(&mut self) {
+        self.description_expanded = !self.description_expanded;
+        self.description_scroll = 0;
+    }
+
+    // 滚动展开的描述，超出范围的部分在渲染时修正
+    pub fn scroll_description(&mut self, down: bool) {
+        if !self.description_expanded {
+            return;
+        }
+        self.description_scroll = if down {
+            self.description_scroll.saturating_add(1)
+        } else {
+            self.description_scroll.saturating_sub(1)
+        };
+    }
+
+    // 在 Todo 面板中进入或退出多选模式
+    pub fn toggle_select_mode(&mut self) {
+        if self.selecting {
+            self.exit_select_mode();
+        } else if self.active_panel == Panel::Todos {
+            self.selecting = true;
+        }
+    }
+
+    // 退出多选模式并清空选择
+    pub fn exit_select_mode(&mut self) {
+        self.selecting = false;
+        self.marked_todos.clear();
+    }
+
+    // 选中或取消选中光标所在的 todo
+    pub fn toggle_mark(&mut self) {
+        if let Some(todo_idx) = self.selected_todo_index() {
+            if !self.marked_todos.remove(&todo_idx) {
+                self.marked_todos.insert(todo_idx);
+            }
+        }
+    }
+
+    // 取出选中的 todo 下标（从小到大）并退出多选模式，没有选中时提示
+    fn take_marked(&mut self) -> Option<Vec<usize>> {
+        let mut marked: Vec<usize> = self.marked_todos.drain().collect();
+        self.selecting = false;
+        if marked.is_empty() {
+            self.warn("没有选中的任务");
+            return None;
+        }
+        marked.sort_unstable();
+        Some(marked)
+    }
+
+    // 把选中的 todo 全部标记为完成，返回是否有修改
+    pub fn complete_marked(&mut self) -> bool {
+        let Some(project_idx) = self.project_state.selected() else {
+            return false;
+        };
+        let Some(marked) = self.take_marked() else {
+            return false;
+        };
+        let today = today();
+        let changed = self.with_selection_kept(|app| {
+            let mut changed = vec![];
+            for todo_idx in marked {
+                let todo = &mut app.projects[project_idx].todos[todo_idx];
+                if !todo.completed {
+                    todo.complete(today);
+                    changed.push(todo.title.clone());
+                }
+            }
+            changed
+        });
+        for title in changed {
+            self.record(EventKind::Completed, &title);
+        }
+        true
+    }
+
+    // 从当前项目中移除选中的 todo，按原来的顺序返回
+    fn remove_marked(&mut self) -> Option<Vec<Todo>> {
+        let project_idx = self.project_state.selected()?;
+        let marked = self.take_marked()?;
+        let project = &mut self.projects[project_idx];
+        let mut removed: Vec<Todo> = marked
+            .into_iter()
+            .rev()
+            .map(|todo_idx| project.todos.remove(todo_idx))
+            .collect();
+        removed.reverse();
+        self.clamp_todo_selection();
+        Some(removed)
+    }
+
+    // 删除选中的 todo 前确认一次
+    pub fn request_delete_marked(&mut self) {
+        if self.marked_todos.is_empty() {
+            self.warn("没有选中的任务");
+            return;
+        }
+        self.view = View::ConfirmDeleteTodos;
+    }
+
+    // 把选中的 todo 移到回收站，返回是否有修改
+    pub fn delete_marked(&mut self) -> bool {
+        let Some(todos) = self.remove_marked() else {
+            return false;
+        };
+        let project = self.current_project_name();
+        for todo in todos {
+            self.move_to_trash(Trashed::Todo {
+                project: project.clone(),
+                todo,
+            });
+        }
+        true
+    }
+
+    // 放入回收站，正在进行的计时先结束
+    fn move_to_trash(&mut self, mut item: Trashed) {
+        let event = match &item {
+            Trashed::Project(project) => Event::new(EventKind::Deleted, &project.name, ""),
+            Trashed::Todo { project, todo } => Event::new(EventKind::Deleted, project, &todo.title),
+        };
+        self.unsaved_events.push(event);
+        let todos = match &mut item {
+            Trashed::Project(project) => project.todos.iter_mut().collect(),
+            Trashed::Todo { todo, .. } => vec![todo],
+        };
+        for todo in todos {
+            if todo.is_working() {
+                todo.end_work();
+            }
+        }
+        self.trash.push(TrashItem::new(item, now_secs()));
+    }
+
+    // 打开回收站，选中最近删除的一项
+    pub fn open_trash(&mut self) {
+        self.view = View::Trash;
+        self.trash_state
+            .select(if self.trash.is_empty() { None } else { Some(0) });
+    }
+
+    // 回收站选中项在 trash 中的下标
+    fn selected_trash_index(&self) -> Option<usize> {
+        self.trash_state
+            .selected()
+            .filter(|&i| i < self.trash.len())
+            .map(|i| self.trash.len() - 1 - i)
+    }
+
+    // 在回收站中移动光标，到头后回到另一端
+    pub fn select_trash(&mut self, down: bool) {
+        let len = self.trash.len();
+        if len == 0 {
+            return;
+        }
+        let i = match self.trash_state.selected() {
+            Some(i) if down => (i + 1) % len,
+            Some(i) => (i + len - 1) % len,
+            None => 0,
+        };
+        self.trash_state.select(Some(i));
+    }
+
+    // 从回收站取出选中项后修正光标
+    fn take_selected_trash(&mut self) -> Option<TrashItem> {
+        let index = self.selected_trash_index()?;
+        let item = self.trash.remove(index);
+        let len = self.trash.len();
+        let position = self.trash_state.selected().unwrap_or(0);
+        self.trash_state.select(if len == 0 {
+            None
+        } else {
+            Some(position.min(len - 1))
+        });
+        Some(item)
+    }
+
+    // 恢复选中项：项目重名时加上后缀，todo 放回原项目，原项目不存在时重新创建
+    pub fn restore_selected_trash(&mut self) -> bool {
+        let Some(item) = self.take_selected_trash() else {
+            return false;
+        };
+        match item.item {
+            Trashed::Project(mut project) => {
+                while self.project_name_taken(&project.name, None) {
+                    project.name.push_str(" (恢复)");
+                }
+                self.info(format!("已恢复项目「{}」", project.name));
+                self.projects.push(project);
+                self.rearrange_projects();
+            }
+            Trashed::Todo { project, todo } => {
+                let lower = project.to_lowercase();
+                let index = match self
+                    .projects
+                    .iter()
+                    .position(|p| p.name.to_lowercase() == lower)
+                {
+                    Some(index) => index,
+                    None => {
+                        self.projects.push(Project::new(project));
+                        self.projects.len() - 1
+                    }
+                };
+                self.info(format!(
+                    "已恢复「{}」到项目「{}」",
+                    todo.title, self.projects[index].name
+                ));
+                self.projects[index].todos.push(todo);
+            }
+        }
+
+        if self.project_state.selected().is_none() {
+            self.project_state.select(Some(0));
+        }
+        if self.todo_state.selected().is_none() {
+            self.reset_todo_selection();
+        }
+        true
+    }
+
+    // 永久删除回收站中的选中项
+    pub fn purge_selected_trash(&mut self) -> bool {
+        self.take_selected_trash().is_some()
+    }
+
+    // 输入要添加到选中任务的标签
+    pub fn start_tagging_marked(&mut self) {
+        if self.marked_todos.is_empty() {
+            self.warn("没有选中的任务");
+            return;
+        }
+        self.input_mode = InputMode::TaggingTodos;
+        self.input.clear();
+    }
+
+    // 给选中的 todo 添加输入的标签，可以用空格分隔多个，# 可以省略，返回是否有修改
+    fn tag_marked(&mut self, input: &str) -> bool {
+        let tags: Vec<&str> = input
+            .split_whitespace()
+            .map(|word| word.trim_start_matches('#'))
+            .filter(|tag| !tag.is_empty())
+            .collect();
+        if tags.is_empty() {
+            self.warn("标签不能为空");
+            return false;
+        }
+        let Some(project_idx) = self.project_state.selected() else {
+            return false;
+        };
+        let Some(marked) = self.take_marked() else {
+            return false;
+        };
+        self.input.clear();
+        self.input_mode = InputMode::Normal;
+        for &todo_idx in &marked {
+            let todo = &mut self.projects[project_idx].todos[todo_idx];
+            for tag in &tags {
+                if !todo.has_tag(tag) {
+                    todo.tags.push(tag.to_string());
+                }
+            }
+        }
+        self.info(format!("已给 {} 个任务添加标签", marked.len()));
+        true
+    }
+
+    // 进入移动模式，输入目标项目名
+    pub fn start_moving(&mut self) {
+        if self.marked_todos.is_empty() {
+            self.warn("没有选中的任务");
+            return;
+        }
+        self.input_mode = InputMode::MovingTodos;
+        self.input.clear();
+    }
+
+    // 把选中的 todo 移到指定项目（不区分大小写）末尾，项目不存在时提示并停留在输入模式
+    fn move_marked_to(&mut self, name: &str) -> bool {
+        let lower = name.to_lowercase();
+        let Some(target) = self
+            .projects
+            .iter()
+            .position(|project| project.name.to_lowercase() == lower)
+        else {
+            self.warn(format!("没有名为「{}」的项目", name));
+            return false;
+        };
+        if Some(target) == self.project_state.selected() {
+            self.warn("选中的任务已在该项目中");
+            return false;
+        }
+
+        self.input.clear();
+        self.input_mode = InputMode::Normal;
+        self.move_todos_to(target)
+    }
+
+    // 打开项目选择弹窗，把选中的 todo（多选模式下为选中的所有 todo）移到其他项目
+    pub fn open_project_picker(&mut self) {
+        if self.projects.len() < 2 {
+            self.warn("没有其他项目可以移动到");
+            return;
+        }
+        if self.selecting && self.marked_todos.is_empty() {
+            self.warn("没有选中的任务");
+            return;
+        }
+        if !self.selecting && self.selected_todo_index().is_none() {
+            return;
+        }
+        self.merging_project = false;
+        self.view = View::ProjectPicker;
+        let first_other = usize::from(self.project_state.selected() == Some(0));
+        self.picker_state.select(Some(first_other));
+    }
+
+    // 打开项目选择弹窗，把当前项目合并到选中的项目
+    pub fn open_merge_picker(&mut self) {
+        if self.projects.len() < 2 {
+            self.warn("没有其他项目可以合并到");
+            return;
+        }
+        let Some(selected) = self.project_state.selected() else {
+            return;
+        };
+        self.merging_project = true;
+        self.view = View::ProjectPicker;
+        self.picker_state.select(Some(usize::from(selected == 0)));
+    }
+
+    // 在项目选择弹窗中移动光标，到头后回到另一端
+    pub fn select_picker(&mut self, down: bool) {
+        let len = self.projects.len();
+        if len == 0 {
+            return;
+        }
+        let i = match self.picker_state.selected() {
+            Some(i) if down => (i + 1) % len,
+            Some(i) => (i + len - 1) % len,
+            None => 0,
+        };
+        self.picker_state.select(Some(i));
+    }
+
+    // 移到项目选择弹窗中选中的项目，返回是否有修改
+    pub fn move_to_picked_project(&mut self) -> bool {
+        let Some(target) = self
+            .picker_state
+            .selected()
+            .filter(|&i| i < self.projects.len())
+        else {
+            return false;
+        };
+        if Some(target) == self.project_state.selected() {
+            match self.merging_project {
+                true => self.warn("不能合并到项目自身"),
+                false => self.warn("选中的任务已在该项目中"),
+            }
+            return false;
+        }
+        // 合并到自己的子项目中会让子项目之间互为上级
+        let inside = |source| project_subtree(&self.projects, source).contains(&target);
+        if self.merging_project && self.project_state.selected().is_some_and(inside) {
+            self.warn("不能合并到它自己的子项目中");
+            return false;
+        }
+        self.view = View::Main;
+        match self.merging_project {
+            true => self.merge_project_into(target),
+            false => self.move_todos_to(target),
+        }
+    }
+
+    // 把当前项目的 todo 和归档连同计时记录移到目标项目末尾，然后删除当前项目
+    // 当前项目的子项目移到目标项目下，目标项目不能是当前项目的子项目
+    fn merge_project_into(&mut self, target: usize) -> bool {
+        let Some(source) = self
+            .project_state
+            .selected()
+            .filter(|&i| i < self.projects.len())
+        else {
+            return false;
+        };
+        let project = self.projects.remove(source);
+        let target = if target > source { target - 1 } else { target };
+        let target_name = self.projects[target].name.clone();
+        for child in self.projects.iter_mut() {
+            if child.is_child_of(&project.name) {
+                child.parent = Some(target_name.clone());
+            }
+        }
+        self.info(format!(
+            "已把项目「{}」的 {} 个任务合并到「{}」",
+            project.name,
+            project.todos.len(),
+            target_name
+        ));
+        self.projects[target].todos.extend(project.todos);
+        self.projects[target].archived.extend(project.archived);
+        self.project_state.select(Some(target));
+        self.rearrange_projects();
+        self.reset_todo_selection();
+        true
+    }
+
+    // 把选中的 todo 移到目标项目末尾，计时记录一起移动
+    fn move_todos_to(&mut self, target: usize) -> bool {
+        let todos = if self.selecting {
+            self.remove_marked()
+        } else {
+            self.remove_selected_todo().map(|todo| vec![todo])
+        };
+        let Some(todos) = todos else {
+            return false;
+        };
+        self.info(format!(
+            "已移动 {} 个任务到「{}」",
+            todos.len(),
+            self.projects[target].name
+        ));
+        self.projects[target].todos.extend(todos);
+        true
+    }
+
+    // 把选中的 todo 和下方（down 为 false 时上方）可见的 todo 交换位置，返回是否有修改
+    // 只在默认顺序下有效，其他排序方式下位置由排序决定
+    pub fn move_selected_todo(&mut self, down: bool) -> bool {
+        let (Some(project_idx), Some(position)) =
+            (self.project_state.selected(), self.todo_state.selected())
+        else {
+            return false;
+        };
+        if self.projects[project_idx].sort_mode != SortMode::Original {
+            self.warn("只能在默认顺序下调整任务位置，按排序键切换");
+            return false;
+        }
+
+        let visible = self.visible_todo_indices();
+        let target = if down {
+            position + 1
+        } else if position > 0 {
+            position - 1
+        } else {
+            return false;
+        };
+        let (Some(&from), Some(&to)) = (visible.get(position), visible.get(target)) else {
+            return false;
+        };
+        self.projects[project_idx].todos.swap(from, to);
+        // 多选的标记跟着 todo 移动
+        if self.marked_todos.contains(&from) != self.marked_todos.contains(&to) {
+            for i in [from, to] {
+                if !self.marked_todos.remove(&i) {
+                    self.marked_todos.insert(i);
+                }
+            }
+        }
+        self.todo_state.select(Some(target));
+        true
+    }
+
+    // 当前使用的图标
+    pub fn icons(&self) -> &'static Icons {
+        self.config.icons()
+    }
+
+    // 切换 emoji 和 ASCII 图标，只在本次运行中有效
+    pub fn toggle_ascii(&mut self) {
+        self.config.ascii = !self.config.ascii;
+    }
+
+    // 切换是否隐藏已完成的 todo，保持选中同一个 todo，选中的 todo 被隐藏时停在原来的位置
+    pub fn toggle_hide_completed(&mut self) {
+        self.with_selection_kept(|app| app.hide_completed = !app.hide_completed);
+    }
+
+    // 隐藏的已完成 todo 数量，没有隐藏时为 0
+    pub fn hidden_completed_count(&self) -> usize {
+        match (self.hide_completed, self.get_current_project()) {
+            (true, Some(project)) => project.todos.iter().filter(|t| t.completed).count(),
+            _ => 0,
+        }
+    }
+
+    // 切换专注模式，进入时焦点移到 Todo 面板
+    pub fn toggle_focus_mode(&mut self) {
+        self.focus_mode = !self.focus_mode;
+        if self.focus_mode && self.active_panel == Panel::Projects {
+            self.switch_panel();
+        }
+    }
+
+    // 调整项目面板的宽度，每次 5%，没有调整过时从 30% 开始
+    pub fn resize_projects(&mut self, wider: bool) {
+        let current = self.project_split.unwrap_or(30);
+        let split = match wider {
+            true => current.saturating_add(SPLIT_STEP),
+            false => current.saturating_sub(SPLIT_STEP),
+        }
+        .clamp(SPLIT_MIN, SPLIT_MAX);
+        self.project_split = Some(split);
+        self.info(format!("项目面板宽度 {}%", split));
+    }
+
+    // 切换单面板模式，窄屏时两个面板上下排列太挤，可以一次只看一个
+    pub fn toggle_single_pane(&mut self) {
+        self.single_pane = !self.single_pane;
+        self.info(match self.single_pane {
+            true => "单面板模式：Enter 进入项目，Esc 或 Backspace 返回项目列表",
+            false => "已退出单面板模式",
+        });
+    }
+
+    // 显示或隐藏详情面板
+    pub fn toggle_detail_pane(&mut self) {
+        self.detail_pane = !self.detail_pane;
+    }
+
+    // 折叠除选中项目以外的所有项目，其他项目都已折叠时全部展开
+    pub fn collapse_other_projects(&mut self) {
+        let Some(selected) = self.project_state.selected() else {
+            return;
+        };
+        let others_collapsed = self
+            .projects
+            .iter()
+            .enumerate()
+            .all(|(i, p)| i == selected || p.collapsed);
+        for (i, project) in self.projects.iter_mut().enumerate() {
+            project.collapsed = !others_collapsed && i != selected;
+        }
+    }
+
+    // 归档选中的项目，项目从列表中移除但保留在数据文件中，子项目移到它的上级下
+    pub fn archive_selected_project(&mut self) -> bool {
+        let Some(idx) = self
+            .project_state
+            .selected()
+            .filter(|&i| i < self.projects.len())
+        else {
+            return false;
+        };
+        if self.projects[idx].todos.iter().any(|t| t.is_working()) {
+            self.warn("项目中有正在计时的任务，请先停止计时");
+            return false;
+        }
+        let project = self.projects.remove(idx);
+        for child in &mut self.projects {
+            if child.is_child_of(&project.name) {
+                child.parent = project.parent.clone();
+            }
+        }
+        self.info(format!("已归档项目「{}」", project.name));
+        self.archived_projects.push(project);
+        self.project_state.select(match self.projects.len() {
+            0 => None,
+            len => Some(idx.min(len - 1)),
+        });
+        self.reset_todo_selection();
+        true
+    }
+
+    // 打开归档的项目列表
+    pub fn open_archived_projects(&mut self) {
+        let empty = self.archived_projects.is_empty();
+        self.view = View::ArchivedProjects;
+        self.archived_projects_state
+            .select(if empty { None } else { Some(0) });
+    }
+
+    // 在归档的项目中移动光标，到头后回到另一端
+    pub fn select_archived_project(&mut self, down: bool) {
+        let len = self.archived_projects.len();
+        if len == 0 {
+            return;
+        }
+        let i = match self.archived_projects_state.selected() {
+            Some(i) if down => (i + 1) % len,
+            Some(i) => (i + len - 1) % len,
+            None => 0,
+        };
+        self.archived_projects_state.select(Some(i));
+    }
+
+    // 归档的项目中选中的项目在 archived_projects 中的下标，列表从新到旧显示
+    fn selected_archived_project(&self) -> Option<usize> {
+        let len = self.archived_projects.len();
+        self.archived_projects_state
+            .selected()
+            .filter(|&i| i < len)
+            .map(|i| len - 1 - i)
+    }
+
+    // 把选中的归档项目放回项目列表并选中它，重名时加上后缀，返回是否有修改
+    pub fn unarchive_selected_project(&mut self) -> bool {
+        let Some(index) = self.selected_archived_project() else {
+            return false;
+        };
+        let mut project = self.archived_projects.remove(index);
+        let len = self.archived_projects.len();
+        if let Some(i) = self.archived_projects_state.selected() {
+            self.archived_projects_state
+                .select((len > 0).then(|| i.min(len - 1)));
+        }
+        while self.project_name_taken(&project.name, None) {
+            project.name.push_str(" (恢复)");
+        }
+        self.info(format!("已取消归档项目「{}」", project.name));
+        self.projects.push(project);
+        self.project_state.select(Some(self.projects.len() - 1));
+        self.rearrange_projects();
+        self.reset_todo_selection();
+        true
+    }
+
+    // 把选中的归档项目导出为 Markdown
+    pub fn export_archived_project(&mut self) {
+        if let Some(index) = self.selected_archived_project() {
+            let project = &self.archived_projects[index];
+            let (path, markdown) = markdown_file(project, self.config.duration_unit);
+            self.write_export(&path, &markdown);
+        }
+    }
+
+    // 收起（fold 为 false 时展开）选中项目的子项目，返回是否有修改
+    // 没有子项目的项目收起时选中上级项目
+    pub fn fold_selected_project(&mut self, fold: bool) -> bool {
+        let Some(idx) = self
+            .project_state
+            .selected()
+            .filter(|&i| i < self.projects.len())
+        else {
+            return false;
+        };
+        if project_subtree(&self.projects, idx).len() > 1 {
+            let changed = self.projects[idx].folded != fold;
+            self.projects[idx].folded = fold;
+            return changed;
+        }
+        if fold {
+            let parent = (0..idx)
+                .rev()
+                .find(|&i| self.projects[idx].is_child_of(&self.projects[i].name));
+            if let Some(parent) = parent {
+                self.project_state.select(Some(parent));
+                self.reset_todo_selection();
+            }
+        }
+        false
+    }
+
+    // 展开项目的所有上级，使它在项目列表中显示
+    fn unfold_ancestors(&mut self, idx: usize) {
+        for i in 0..idx {
+            if project_subtree(&self.projects, i).contains(&idx) {
+                self.projects[i].folded = false;
+            }
+        }
+    }
+
+    // 修改上级关系后重新排列项目树，保持选中原来的项目
+    fn rearrange_projects(&mut self) {
+        let selected = self.get_current_project().map(|p| p.name.clone());
+        arrange_tree(&mut self.projects);
+        if let Some(idx) =
+            selected.and_then(|name| self.projects.iter().position(|p| p.name == name))
+        {
+            self.project_state.select(Some(idx));
+            self.unfold_ancestors(idx);
+        }
+    }
+
+    // 输入选中项目的上级项目，输入框预填当前的上级
+    pub fn start_setting_parent(&mut self) {
+        if let Some(parent) = self.get_current_project().map(|p| p.parent.clone()) {
+            self.input_mode = InputMode::SettingParent;
+            self.input.set(parent.unwrap_or_default());
+        }
+    }
+
+    // 把选中的项目移到输入的项目下，输入为空时移到顶层，返回是否有修改
+    fn set_parent(&mut self, input: &str) -> bool {
+        let Some(idx) = self
+            .project_state
+            .selected()
+            .filter(|&i| i < self.projects.len())
+        else {
+            return false;
+        };
+        let parent = match input {
+            "" => None,
+            name => {
+                let lower = name.to_lowercase();
+                let Some(parent) = self
+                    .projects
+                    .iter()
+                    .position(|p| p.name.to_lowercase() == lower)
+                else {
+                    self.warn(format!("没有名为「{}」的项目", name));
+                    return false;
+                };
+                if project_subtree(&self.projects, idx).contains(&parent) {
+                    self.warn("不能把项目移到它自己或它的子项目下");
+                    return false;
+                }
+                Some(self.projects[parent].name.clone())
+            }
+        };
+        self.input.clear();
+        self.input_mode = InputMode::Normal;
+        self.projects[idx].parent = parent;
+        self.rearrange_projects();
+        true
+    }
+
+    // 归档当前项目中已完成的 todo，返回是否有修改
+    pub fn archive_completed(&mut self) -> bool {
+        let Some(project_idx) = self.project_state.selected() else {
+            return false;
+        };
+        self.exit_select_mode();
+        let count = self.with_selection_kept(|app| app.projects[project_idx].archive_completed());
+        if count == 0 {
+            self.warn("没有已完成的任务可以归档");
+            return false;
+        }
+        self.clamp_todo_selection();
+        self.info(format!("已归档 {} 个已完成的任务", count));
+        true
+    }
+
+    // 打开当前项目的归档
+    pub fn open_archive(&mut self) {
+        let Some(project) = self.get_current_project() else {
+            return;
+        };
+        let empty = project.archived.is_empty();
+        self.view = View::Archive;
+        self.archive_state
+            .select(if empty { None } else { Some(0) });
+    }
+
+    // 在归档中移动光标，到头后回到另一端
+    pub fn select_archived(&mut self, down: bool) {
+        let len = self.get_current_project().map_or(0, |p| p.archived.len());
+        if len == 0 {
+            return;
+        }
+        let i = match self.archive_state.selected() {
+            Some(i) if down => (i + 1) % len,
+            Some(i) => (i + len - 1) % len,
+            None => 0,
+        };
+        self.archive_state.select(Some(i));
+    }
+
+    // 把归档中选中的 todo 放回列表末尾，返回是否有修改
+    pub fn unarchive_selected(&mut self) -> bool {
+        let Some(project_idx) = self.project_state.selected() else {
+            return false;
+        };
+        let archived = &mut self.projects[project_idx].archived;
+        let Some(index) = self
+            .archive_state
+            .selected()
+            .filter(|&i| i < archived.len())
+            .map(|i| archived.len() - 1 - i)
+        else {
+            return false;
+        };
+        let todo = archived.remove(index);
+        let len = archived.len();
+        self.archive_state
+            .select(match self.archive_state.selected() {
+                _ if len == 0 => None,
+                Some(i) => Some(i.min(len - 1)),
+                None => None,
+            });
+        self.info(format!("已取消归档「{}」", todo.title));
+        self.projects[project_idx].todos.push(todo);
+        if self.todo_state.selected().is_none() {
+            self.reset_todo_selection();
+        }
+        true
+    }
+
+    // 打开当前项目的看板，选中选中 todo 所在的列
+    pub fn open_board(&mut self) {
+        if self.get_current_project().is_none() {
+            return;
+        }
+        self.exit_select_mode();
+        // 看板中显示已完成的 todo，切换视图时保持选中同一个 todo
+        self.with_selection_kept(|app| app.view = View::Board);
+        self.board_column = self.get_current_todo().map_or(0, |todo| {
+            Status::ALL
+                .iter()
+                .position(|&s| s == todo.status())
+                .unwrap_or(0)
+        });
+    }
+
+    // 看板某一列中的 todo 下标，顺序和筛选与列表一致
+    pub fn board_cards(&self, column: usize) -> Vec<usize> {
+        let Some(project) = self.get_current_project() else {
+            return vec![];
+        };
+        self.visible_todo_indices()
+            .into_iter()
+            .filter(|&i| project.todos[i].status() == Status::ALL[column])
+            .collect()
+    }
+
+    // 选中的 todo 在看板选中列中的位置，不在该列时返回 None
+    pub fn board_selected(&self) -> Option<usize> {
+        let todo_idx = self.selected_todo_index()?;
+        self.board_cards(self.board_column)
+            .iter()
+            .position(|&i| i == todo_idx)
+    }
+
+    // 在看板的列中上下移动，到头后回到另一端
+    pub fn select_board_card(&mut self, down: bool) {
+        let cards = self.board_cards(self.board_column);
+        let len = cards.len();
+        if len == 0 {
+            return;
+        }
+        let i = match self.board_selected() {
+            Some(i) if down => (i + 1) % len,
+            Some(i) => (i + len - 1) % len,
+            None => 0,
+        };
+        self.select_todo_index(cards[i]);
+    }
+
+    // 切换看板中选中的列，选中该列的第一个 todo
+    pub fn switch_board_column(&mut self, right: bool) {
+        let len = Status::ALL.len();
+        self.board_column = if right {
+            (self.board_column + 1) % len
+        } else {
+            (self.board_column + len - 1) % len
+        };
+        if let Some(&first) = self.board_cards(self.board_column).first() {
+            self.select_todo_index(first);
+        }
+    }
+
+    // 把选中的卡片移到左边（right 为 true 时右边）的列，返回是否有修改
+    pub fn move_board_card(&mut self, right: bool) -> bool {
+        if self.board_selected().is_none() {
+            return false;
+        }
+        let target = if right {
+            self.board_column + 1
+        } else if self.board_column > 0 {
+            self.board_column - 1
+        } else {
+            return false;
+        };
+        let Some(&status) = Status::ALL.get(target) else {
+            return false;
+        };
+
+        let today = today();
+        let (title, was_done) = match self.get_current_todo() {
+            Some(todo) => (todo.title.clone(), todo.completed),
+            None => return false,
+        };
+        let next = self.with_selection_kept(|app| {
+            app.get_current_todo_mut()
+                .and_then(|todo| todo.set_status(status, today))
+        });
+        if status == Status::Done {
+            self.record(EventKind::Completed, &title);
+        } else if was_done {
+            self.record(EventKind::Reopened, &title);
+        }
+        match next {
+            // 重复的 todo 完成后回到待办，光标跟着留在待办列
+            Some(next) => {
+                self.board_column = 0;
+                self.info(format!("已安排下一次：{}", next.format("%Y-%m-%d")));
+            }
+            None => self.board_column = target,
+        }
+        true
+    }
+
+    // 把当前项目导出为当前目录下的 "<项目名>.md"
+    pub fn export_project_markdown(&mut self) {
+        if let Some(project) = self.get_current_project() {
+            let (path, markdown) = markdown_file(project, self.config.duration_unit);
+            self.write_export(&path, &markdown);
+        }
+    }
+
+    // 把导出的内容写到当前目录的文件中，显示结果
+    fn write_export(&mut self, path: &str, content: &str) {
+        match write_new_file(path, content) {
+            Ok(path) => self.info(format!("已导出到 {}", path)),
+            Err(err) => self.error(format!("导出失败：{}", err)),
+        }
+    }
+
+    // 打开时间报告
+    pub fn open_report(&mut self) {
+        self.view = View::Report;
+        self.report_offset = 0;
+        self.report_scroll = 0;
+    }
+
+    // 打开选中 todo 的子任务视图
+    pub fn open_subtasks(&mut self) {
+        if let Some(todo) = self.get_current_todo() {
+            let has_subtasks = !todo.subtasks.is_empty();
+            self.view = View::Subtasks;
+            self.subtask_state
+                .select(if has_subtasks { Some(0) } else { None });
+        }
+    }
+
+    // 当前 todo 的子任务（可变引用）
+    fn current_subtasks_mut(&mut self) -> Option<&mut Vec<Subtask>> {
+        self.get_current_todo_mut().map(|todo| &mut todo.subtasks)
+    }
+
+    // 在子任务间移动光标，到头后回到另一端
+    pub fn select_subtask(&mut self, down: bool) {
+        let len = self.current_subtasks_mut().map_or(0, |s| s.len());
+        if len == 0 {
+            return;
+        }
+        let i = match self.subtask_state.selected() {
+            Some(i) if down => (i + 1) % len,
+            Some(i) => (i + len - 1) % len,
+            None => 0,
+        };
+        self.subtask_state.select(Some(i));
+    }
+
+    // 切换选中子任务的完成状态，返回是否有修改
+    pub fn toggle_selected_subtask(&mut self) -> bool {
+        let selected = self.subtask_state.selected();
+        match (self.current_subtasks_mut(), selected) {
+            (Some(subtasks), Some(i)) if i < subtasks.len() => {
+                subtasks[i].completed = !subtasks[i].completed;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // 向当前 todo 添加子任务并选中它
+    pub fn add_subtask(&mut self, title: String) -> bool {
+        let Some(subtasks) = self.current_subtasks_mut() else {
+            return false;
+        };
+        subtasks.push(Subtask {
+            title,
+            completed: false,
+        });
+        let last = subtasks.len() - 1;
+        self.subtask_state.select(Some(last));
+        true
+    }
+
+    // 删除选中的子任务，返回是否有修改
+    pub fn delete_selected_subtask(&mut self) -> bool {
+        let selected = self.subtask_state.selected();
+        let Some(subtasks) = self.current_subtasks_mut() else {
+            return false;
+        };
+        let Some(i) = selected.filter(|&i| i < subtasks.len()) else {
+            return false;
+        };
+        subtasks.remove(i);
+        let len = subtasks.len();
+        self.subtask_state
+            .select(if len == 0 { None } else { Some(i.min(len - 1)) });
+        true
+    }
+
+    // 打开选中 todo 的计时记录
+    pub fn open_sessions(&mut self) {
+        if let Some(todo) = self.get_current_todo() {
+            let len = todo.sessions.len();
+            self.view = View::Sessions;
+            self.session_state.select(len.checked_sub(1));
+        }
+    }
+
+    // 在计时记录间移动光标，到头后回到另一端
+    pub fn select_session(&mut self, down: bool) {
+        let len = self.get_current_todo().map_or(0, |t| t.sessions.len());
+        if len == 0 {
+            return;
+        }
+        let i = match self.session_state.selected() {
+            Some(i) if down => (i + 1) % len,
+            Some(i) => (i + len - 1) % len,
+            None => 0,
+        };
+        self.session_state.select(Some(i));
+    }
+
+    // 选中的计时记录在 sessions 中的下标
+    fn selected_session_index(&self) -> Option<usize> {
+        let len = self.get_current_todo()?.sessions.len();
+        self.session_state.selected().filter(|&i| i < len)
+    }
+
+    // 删除选中的计时记录，耗时随之减少，返回是否有修改
+    pub fn delete_selected_session(&mut self) -> bool {
+        let Some(i) = self.selected_session_index() else {
+            return false;
+        };
+        let Some(todo) = self.get_current_todo_mut() else {
+            return false;
+        };
+        todo.sessions.remove(i);
+        let len = todo.sessions.len();
+        self.session_state
+            .select(if len == 0 { None } else { Some(i.min(len - 1)) });
+        true
+    }
+
+    // 修改选中的计时记录，输入框预填当前的时间段
+    pub fn start_editing_session(&mut self) {
+        let Some(i) = self.selected_session_index() else {
+            return;
+        };
+        if let Some(session) = self.get_current_todo().map(|t| t.sessions[i]) {
+            self.input_mode = InputMode::EditingSession;
+            self.input
+                .set(report::format_session(session.start, session.end));
+        }
+    }
+
+    // 为选中的 todo 补记耗时
+    pub fn start_logging_time(&mut self) {
+        if self.get_current_todo().is_some() {
+            self.input_mode = InputMode::LoggingTime;
+            self.input.clear();
+        }
+    }
+
+    fn log_time(&mut self, input: &str) -> bool {
+        let (start, end) = match report::parse_time_entry(input, now_secs()) {
+            Ok(range) => range,
+            Err(err) => {
+                self.warn(err);
+                return false;
+            }
+        };
+        self.input.clear();
+        self.input_mode = InputMode::Normal;
+        let unit = self.config.duration_unit;
+        let logged = self.with_selection_kept(|app| {
+            let todo = app.get_current_todo_mut()?;
+            todo.log_session(start, end);
+            Some(todo.sessions.iter().rposition(|s| s.start == start))
+        });
+        let Some(position) = logged else {
+            return false;
+        };
+        // 在计时记录视图中补记时选中新记录
+        if self.view == View::Sessions {
+            self.session_state.select(position);
+        }
+        self.info(format!(
+            "已补记 {}（{}）",
+            format_duration(end - start, unit),
+            report::format_session(start, end)
+        ));
+        true
+    }
+
+    fn set_session(&mut self, input: &str) -> bool {
+        let (start, end) = match report::parse_session(input) {
+            Ok(range) => range,
+            Err(err) => {
+                self.warn(err);
+                return false;
+            }
+        };
+        let Some(i) = self.selected_session_index() else {
+            return false;
+        };
+        self.input.clear();
+        self.input_mode = InputMode::Normal;
+        let Some(todo) = self.get_current_todo_mut() else {
+            return false;
+        };
+        // 修改后按开始时间重新排列，光标跟着这条记录
+        todo.sessions.remove(i);
+        todo.log_session(start, end);
+        let position = todo
+            .sessions
+            .iter()
+            .position(|s| s.start == start && s.end == end);
+        self.session_state.select(position);
+        true
+    }
+
+    // 关闭覆盖视图，回到主界面
+    pub fn close_view(&mut self) {
+        self.with_selection_kept(|app| app.view = View::Main);
+    }
+
+    // 切换报告的时间范围
+    pub fn cycle_report_range(&mut self) {
+        self.report_range = self.report_range.next();
+        self.report_offset = 0;
+        self.report_scroll = 0;
+    }
+
+    // 报告切换到前一个或后一个日期范围，不能翻到未来
+    pub fn shift_report_period(&mut self, earlier: bool) {
+        if self.report_range == ReportRange::All {
+            return;
+        }
+        self.report_offset = if earlier {
+            self.report_offset + 1
+        } else {
+            self.report_offset.saturating_sub(1)
+        };
+        self.report_scroll = 0;
+    }
+
+    // 滚动报告，超出范围的部分在渲染时修正
+    pub fn scroll_report(&mut self, down: bool) {
+        self.report_scroll = if down {
+            self.report_scroll.saturating_add(1)
+        } else {
+            self.report_scroll.saturating_sub(1)
+        };
+    }
+
+    pub fn open_help(&mut self) {
+        self.view = View::Help;
+        self.help_scroll = 0;
+    }
+
+    pub fn scroll_help(&mut self, down: bool) {
+        self.help_scroll = if down {
+            self.help_scroll.saturating_add(1)
+        } else {
+            self.help_scroll.saturating_sub(1)
+        };
+    }
+
+    // 查看选中 todo 的详情
+    pub fn open_details(&mut self) {
+        if self.get_current_todo().is_some() {
+            self.view = View::Details;
+        }
+    }
+
+    pub fn open_messages(&mut self) {
+        self.view = View::Messages;
+        self.messages_scroll = 0;
+    }
+
+    pub fn scroll_messages(&mut self, down: bool) {
+        self.messages_scroll = if down {
+            self.messages_scroll.saturating_add(1)
+        } else {
+            self.messages_scroll.saturating_sub(1)
+        };
+    }
+
+    // 在底部显示提示并记入消息记录，已经显示着更严重的提示时只记录
+    pub fn show_message(&mut self, level: Level, text: impl Into<String>) {
+        let message = Message {
+            level,
+            text: text.into(),
+            at: now_secs(),
+        };
+        self.messages.push(message.clone());
+        if self
+            .message
+            .as_ref()
+            .is_none_or(|shown| shown.level <= level)
+        {
+            self.message = Some(message);
+        }
+    }
+
+    pub fn info(&mut self, text: impl Into<String>) {
+        self.show_message(Level::Info, text);
+    }
+
+    pub fn warn(&mut self, text: impl Into<String>) {
+        self.show_message(Level::Warning, text);
+    }
+
+    pub fn error(&mut self, text: impl Into<String>) {
+        self.show_message(Level::Error, text);
+    }
+
+    // 切换当前项目的排序方式，保持选中同一个 todo
+    pub fn cycle_sort_mode(&mut self) -> bool {
+        match self.get_current_project() {
+            Some(project) => self.set_sort_mode(project.sort_mode.next()),
+            None => false,
+        }
+    }
+
+    // 设置当前项目的排序方式，返回是否有修改
+    pub fn set_sort_mode(&mut self, mode: SortMode) -> bool {
+        self.with_selection_kept(|app| {
+            match app
+                .project_state
+                .selected()
+                .and_then(|i| app.projects.get_mut(i))
+            {
+                Some(project) => {
+                    project.sort_mode = mode;
+                    true
+                }
+                None => false,
+            }
+        })
+    }
+
+    // 添加项目并自动选中
+    pub fn add_project(&mut self, name: String) {
+        self.projects.push(Project {
+            sort_mode: self.config.default_sort,
+            ..Project::new(name)
+        });
+        let new_index = self.projects.len() - 1;
+        self.project_state.select(Some(new_index));
+        // 清空 todo 选择，因为新项目没有 todo
+        self.todo_state.select(None);
+    }
+
+    // 向当前项目添加 todo 并自动选中，返回是否添加成功
+    // 输入中的 !优先级、#标签、due:日期 和 est:时长 会被拆出来保存到对应的字段
+    pub fn add_todo(&mut self, input: String) -> bool {
+        let parsed = parse_quick_add(&input, today());
+        match self
+            .project_state
+            .selected()
+            .and_then(|i| self.projects.get_mut(i))
+        {
+            Some(project) => {
+                let todo = parsed.into_todo();
+                let event = Event::new(EventKind::Created, &project.name, &todo.title);
+                project.todos.push(todo);
+                let new_todo_index = project.todos.len() - 1;
+                self.unsaved_events.push(event);
+                self.select_todo_index(new_todo_index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // 重命名当前项目，子项目跟着改为新的名称
+    pub fn rename_selected_project(&mut self, name: String) -> bool {
+        let Some(old) = self.get_current_project().map(|p| p.name.clone()) else {
+            return false;
+        };
+        for project in &mut self.projects {
+            if project.is_child_of(&old) {
+                project.parent = Some(name.clone());
+            }
+        }
+        if let Some(project) = self
+            .project_state
+            .selected()
+            .and_then(|i| self.projects.get_mut(i))
+        {
+            project.name = name;
+        }
+        true
+    }
+
+    // 重命名当前 todo
+    pub fn rename_selected_todo(&mut self, input: String) -> bool {
+        let (title, tags) = parse_tags(&input);
+        let Some(old) = self.get_current_todo().map(|t| t.title.clone()) else {
+            return false;
+        };
+        if old != title {
+            let event = Event {
+                detail: Some(old),
+                ..Event::new(EventKind::Renamed, &self.current_project_name(), &title)
+            };
+            self.unsaved_events.push(event);
+        }
+        self.with_selection_kept(|app| match app.get_current_todo_mut() {
+            Some(todo) => {
+                todo.title = title;
+                todo.tags = tags;
+                true
+            }
+            None => false,
+        })
+    }
+
+    // 删除项目前确认，项目中没有 todo 时直接删除，返回是否有修改
+    pub fn request_delete_project(&mut self) -> bool {
+        match self.get_current_project() {
+            Some(project) if project.todos.is_empty() => self.delete_selected_project(),
+            Some(_) => {
+                self.view = View::ConfirmDeleteProject;
+                false
+            }
+            None => false,
+        }
+    }
+
+    // 删除当前项目并修正选中位置
+    pub fn delete_selected_project(&mut self) -> bool {
+        match self.project_state.selected() {
+            Some(idx) if idx < self.projects.len() => {
+                // 子项目移到被删除项目的上级下
+                let project = self.projects.remove(idx);
+                for child in &mut self.projects {
+                    if child.is_child_of(&project.name) {
+                        child.parent = project.parent.clone();
+                    }
+                }
+                self.move_to_trash(Trashed::Project(project));
+                if self.projects.is_empty() {
+                    self.project_state.select(None);
+                } else if idx >= self.projects.len() {
+                    self.project_state.select(Some(self.projects.len() - 1));
+                }
+                self.reset_todo_selection();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // 删除当前 todo 并修正选中位置
+    pub fn delete_selected_todo(&mut self) -> bool {
+        let Some(todo) = self.remove_selected_todo() else {
+            return false;
+        };
+        let project = self.current_project_name();
+        self.move_to_trash(Trashed::Todo { project, todo });
+        true
+    }
+
+    // 从当前项目中移除选中的 todo 并修正光标
+    fn remove_selected_todo(&mut self) -> Option<Todo> {
+        let project_idx = self.project_state.selected()?;
+        let todo_idx = self.selected_todo_index()?;
+        let todo = self.projects[project_idx].todos.remove(todo_idx);
+        self.clamp_todo_selection();
+        Some(todo)
+    }
+
+    // 复制选中的 todo 或项目，名称后面加上 (copy)，副本放在原来的后面并选中，返回是否有修改
+    pub fn duplicate_selected(&mut self) -> bool {
+        match self.active_panel {
+            Panel::Projects => {
+                let Some(idx) = self
+                    .project_state
+                    .selected()
+                    .filter(|&i| i < self.projects.len())
+                else {
+                    return false;
+                };
+                let mut name = format!("{} (copy)", self.projects[idx].name);
+                while self.project_name_taken(&name, None) {
+                    name.push_str(" (copy)");
+                }
+                let copy = self.projects[idx].duplicate(name);
+                let position = project_subtree(&self.projects, idx).end;
+                self.info(format!("已复制为项目「{}」", copy.name));
+                self.projects.insert(position, copy);
+                self.project_state.select(Some(position));
+                self.reset_todo_selection();
+                true
+            }
+            Panel::Todos => {
+                let Some(todo_idx) = self.selected_todo_index() else {
+                    return false;
+                };
+                let Some(project) = self
+                    .project_state
+                    .selected()
+                    .and_then(|i| self.projects.get_mut(i))
+                else {
+                    return false;
+                };
+                let mut copy = project.todos[todo_idx].duplicate();
+                copy.title.push_str(" (copy)");
+                let event = Event::new(EventKind::Created, &project.name, &copy.title);
+                project.todos.insert(todo_idx + 1, copy);
+                self.unsaved_events.push(event);
+                // 插入后多选中排在后面的 todo 下标后移
+                self.marked_todos = self
+                    .marked_todos
+                    .iter()
+                    .map(|&i| if i > todo_idx { i + 1 } else { i })
+                    .collect();
+                self.select_todo_index(todo_idx + 1);
+                true
+            }
+        }
+    }
+
+    // 把选中的项目或 todo 保存为模板，输入框预填它的名称
+    pub fn start_saving_template(&mut self) {
+        let name = match self.active_panel {
+            Panel::Projects => self.get_current_project().map(|p| p.name.clone()),
+            Panel::Todos => self.get_current_todo().map(|t| t.title.clone()),
+        };
+        if let Some(name) = name {
+            self.input_mode = InputMode::NamingTemplate;
+            self.input.set(name);
+        }
+    }
+
+    // 保存模板，同名（不区分大小写）的模板会被替换，返回是否有修改
+    fn save_template(&mut self, name: String) -> bool {
+        let template = match self.active_panel {
+            Panel::Projects => self
+                .get_current_project()
+                .map(|p| Template::from_project(name, p)),
+            Panel::Todos => self
+                .get_current_todo()
+                .map(|t| Template::from_todo(name, t)),
+        };
+        let Some(template) = template else {
+            return false;
+        };
+        let lower = template.name.to_lowercase();
+        match self
+            .templates
+            .iter()
+            .position(|t| t.name.to_lowercase() == lower)
+        {
+            Some(i) => {
+                self.info(format!("已更新模板「{}」", template.name));
+                self.templates[i] = template;
+            }
+            None => {
+                self.info(format!("已保存模板「{}」", template.name));
+                self.templates.push(template);
+            }
+        }
+        true
+    }
+
+    // 打开模板列表
+    pub fn open_templates(&mut self) {
+        let empty = self.templates.is_empty();
+        self.view = View::Templates;
+        self.templates_state
+            .select(if empty { None } else { Some(0) });
+    }
+
+    // 在模板列表中移动光标，到头后回到另一端
+    pub fn select_template(&mut self, down: bool) {
+        let len = self.templates.len();
+        if len == 0 {
+            return;
+        }
+        let i = match self.templates_state.selected() {
+            Some(i) if down => (i + 1) % len,
+            Some(i) => (i + len - 1) % len,
+            None => 0,
+        };
+        self.templates_state.select(Some(i));
+    }
+
+    // 用选中的模板创建项目或在当前项目中添加 todo，并选中新建的内容，返回是否有修改
+    // 项目重名时在名称后面加上编号
+    pub fn instantiate_selected_template(&mut self) -> bool {
+        let Some(template) = self
+            .templates_state
+            .selected()
+            .and_then(|i| self.templates.get(i))
+        else {
+            return false;
+        };
+        match &template.item {
+            Templated::Project(project) => {
+                let mut name = project.name.clone();
+                let mut n = 2;
+                while self.project_name_taken(&name, None) {
+                    name = format!("{} {}", project.name, n);
+                    n += 1;
+                }
+                let project = Project {
+                    parent: None,
+                    ..project.duplicate(name)
+                };
+                self.view = View::Main;
+                self.info(format!("已从模板创建项目「{}」", project.name));
+                self.projects.push(project);
+                self.project_state.select(Some(self.projects.len() - 1));
+                self.active_panel = Panel::Projects;
+                self.reset_todo_selection();
+                true
+            }
+            Templated::Todo(todo) => {
+                let todo = todo.duplicate();
+                let Some(project_idx) = self
+                    .project_state
+                    .selected()
+                    .filter(|&i| i < self.projects.len())
+                else {
+                    self.warn("请先选择要添加到的项目");
+                    return false;
+                };
+                let project = &mut self.projects[project_idx];
+                self.unsaved_events.push(Event::new(
+                    EventKind::Created,
+                    &project.name,
+                    &todo.title,
+                ));
+                project.todos.push(todo);
+                let todo_idx = project.todos.len() - 1;
+                self.view = View::Main;
+                self.reveal_todo(project_idx, todo_idx);
+                true
+            }
+        }
+    }
+
+    // 删除选中的模板，返回是否有修改
+    pub fn delete_selected_template(&mut self) -> bool {
+        let Some(i) = self
+            .templates_state
+            .selected()
+            .filter(|&i| i < self.templates.len())
+        else {
+            return false;
+        };
+        let template = self.templates.remove(i);
+        let len = self.templates.len();
+        self.templates_state
+            .select((len > 0).then(|| i.min(len - 1)));
+        self.info(format!("已删除模板「{}」", template.name));
+        true
+    }
+
+    // 进入添加模式
+    pub fn start_adding(&mut self) {
+        self.input_mode = match self.active_panel {
+            Panel::Projects => InputMode::AddingProject,
+            Panel::Todos => InputMode::AddingTodo,
+        };
+        self.input.clear();
+    }
+
+    // 设置当前 todo 的截止日期，输入框预填已有的日期
+    pub fn start_setting_due_date(&mut self) {
+        if let Some(due_date) = self.get_current_todo_mut().map(|t| t.due_date) {
+            self.input_mode = InputMode::SettingDueDate;
+            self.input
+                .set(due_date.map_or_else(String::new, |d| d.format("%Y-%m-%d").to_string()));
+        }
+    }
+
+    // 设置当前 todo 的预计耗时，输入框预填已有的预计耗时
+    pub fn start_setting_estimate(&mut self) {
+        if let Some(estimate) = self.get_current_todo().map(|t| t.estimate) {
+            self.input_mode = InputMode::SettingEstimate;
+            self.input.set(estimate.map_or_else(String::new, |secs| {
+                format_duration(secs, DurationUnit::Hour)
+            }));
+        }
+    }
+
+    // 设置预计耗时，可以像显示的那样带空格，如 "1h 30m"
+    // 输入为空时清除，无法识别时提示并停留在输入模式
+    fn set_estimate(&mut self, input: &str) -> bool {
+        let estimate = if input.is_empty() {
+            None
+        } else {
+            match parse_duration(&input.split_whitespace().collect::<String>()) {
+                Some(secs) => Some(secs),
+                None => {
+                    self.warn("无法识别的时长，可以输入 2h、1h30m、45m 等");
+                    return false;
+                }
+            }
+        };
+
+        self.input.clear();
+        self.input_mode = InputMode::Normal;
+        self.with_selection_kept(|app| match app.get_current_todo_mut() {
+            Some(todo) => {
+                todo.estimate = estimate;
+                true
+            }
+            None => false,
+        })
+    }
+
+    // 编辑当前 todo 的描述，输入框预填已有的描述
+    pub fn start_editing_description(&mut self) {
+        if let Some(description) = self.get_current_todo().map(|t| t.description.clone()) {
+            self.input_mode = InputMode::EditingDescription;
+            self.input.set(description);
+        }
+    }
+
+    fn set_description(&mut self) -> bool {
+        let description = self.input.take();
+        self.input_mode = InputMode::Normal;
+        self.set_current_description(&description)
+    }
+
+    // 保存当前 todo 的描述，去掉末尾的空白，为空时清除描述，返回是否有修改
+    pub fn set_current_description(&mut self, description: &str) -> bool {
+        self.description_scroll = 0;
+        match self.get_current_todo_mut() {
+            Some(todo) => {
+                todo.description = description.trim_end().to_string();
+                true
+            }
+            None => false,
+        }
+    }
+
+    // 设置截止日期，支持 tomorrow、next monday 等写法和界面的日期格式
+    // 输入为空时清除，无法识别时提示并停留在输入模式
+    fn set_due_date(&mut self, input: &str) -> bool {
+        let due_date = if input.is_empty() {
+            None
+        } else {
+            let today = today();
+            let parsed = dates::parse_date(input, today)
+                .or_else(|| dates::parse_with_format(input, &self.config.date_format, today));
+            match parsed {
+                Some(date) => Some(date),
+                None => {
+                    self.warn(format!("无法识别的日期，{}", dates::HINT));
+                    return false;
+                }
+            }
+        };
+
+        self.input.clear();
+        self.input_mode = InputMode::Normal;
+        self.with_selection_kept(|app| match app.get_current_todo_mut() {
+            Some(todo) => {
+                todo.due_date = due_date;
+                true
+            }
+            None => false,
+        })
+    }
+
+    // 开始搜索当前项目的 todo
+    pub fn start_searching(&mut self) {
+        self.input_mode = InputMode::Searching;
+        self.input.clear();
+    }
+
+    // 输入内容变化后调用，搜索时列表随输入变化，选中第一个结果
+    pub fn input_changed(&mut self) {
+        match self.input_mode {
+            InputMode::Searching => self.reset_todo_selection(),
+            InputMode::Finding => self.update_finder(),
+            InputMode::Commanding => self.update_palette(),
+            _ => {}
+        }
+    }
+
+    // 打开全局查找，在所有项目中模糊匹配 todo 标题
+    pub fn start_finding(&mut self) {
+        self.input_mode = InputMode::Finding;
+        self.input.clear();
+        self.update_finder();
+    }
+
+    // 按输入重新匹配，结果按分数从高到低排列，选中第一个
+    fn update_finder(&mut self) {
+        let query = self.input.as_str();
+        let mut results: Vec<(i64, usize, usize)> = self
+            .projects
+            .iter()
+            .enumerate()
+            .flat_map(|(p, project)| {
+                project
+                    .todos
+                    .iter()
+                    .enumerate()
+                    .filter_map(move |(t, todo)| {
+                        fuzzy_score(query, &todo.title).map(|score| (score, p, t))
+                    })
+            })
+            .collect();
+        results.sort_by_key(|&(score, _, _)| std::cmp::Reverse(score));
+        self.finder_results = results.into_iter().map(|(_, p, t)| (p, t)).collect();
+        self.finder_state
+            .select((!self.finder_results.is_empty()).then_some(0));
+    }
+
+    // 在查找结果或命令面板中上下移动，到头后回到另一端
+    pub fn select_finder(&mut self, down: bool) {
+        let (len, state) = match self.input_mode {
+            InputMode::Finding => (self.finder_results.len(), &mut self.finder_state),
+            InputMode::Commanding => (self.palette_results.len(), &mut self.palette_state),
+            _ => return,
+        };
+        if len == 0 {
+            return;
+        }
+        let i = state.selected().unwrap_or(0);
+        state.select(Some(if down {
+            (i + 1) % len
+        } else {
+            (i + len - 1) % len
+        }));
+    }
+
+    // 打开命令面板，列出所有命令
+    pub fn start_command_palette(&mut self) {
+        self.input_mode = InputMode::Commanding;
+        self.input.clear();
+        self.update_palette();
+    }
+
+    fn update_palette(&mut self) {
+        self.palette_results = palette::search(&self.input);
+        self.palette_state
+            .select((!self.palette_results.is_empty()).then_some(0));
+    }
+
+    // 关闭命令面板，返回选中的命令
+    pub fn take_palette_command(&mut self) -> Option<Command> {
+        self.input.clear();
+        self.input_mode = InputMode::Normal;
+        self.palette_state
+            .selected()
+            .and_then(|i| self.palette_results.get(i))
+            .copied()
+    }
+
+    // 执行没有对应按键操作的命令，返回是否有修改
+    pub fn run_command(&mut self, command: Command) -> bool {
+        match command {
+            Command::Action(_) => false,
+            Command::Sort(mode) => self.set_sort_mode(mode),
+            Command::ExportCsv => {
+                let csv = report::sessions_csv(&self.projects);
+                self.write_export("sessions.csv", &csv);
+                false
+            }
+        }
+    }
+
+    // 跳到查找结果中选中的 todo
+    fn confirm_finder(&mut self) {
+        self.input.clear();
+        self.input_mode = InputMode::Normal;
+        let Some(&(project_idx, todo_idx)) = self
+            .finder_state
+            .selected()
+            .and_then(|i| self.finder_results.get(i))
+        else {
+            return;
+        };
+        self.reveal_todo(project_idx, todo_idx);
+    }
+
+    // 选中某个项目中的 todo，必要时切换项目、展开项目并取消会隐藏它的标签筛选
+    fn reveal_todo(&mut self, project_idx: usize, todo_idx: usize) {
+        self.exit_select_mode();
+        self.description_scroll = 0;
+        self.project_state.select(Some(project_idx));
+        self.projects[project_idx].collapsed = false;
+        self.unfold_ancestors(project_idx);
+        if let Some(tag) = &self.tag_filter {
+            if !self.projects[project_idx].todos[todo_idx].has_tag(tag) {
+                self.tag_filter = None;
+            }
+        }
+        self.select_todo_index(todo_idx);
+        self.active_panel = Panel::Todos;
+    }
+
+    // 取消输入，取消搜索时恢复完整列表
+    pub fn cancel_input(&mut self) {
+        let searching = self.input_mode == InputMode::Searching;
+        let selected = self.selected_todo_index();
+        self.input_mode = InputMode::Normal;
+        if searching {
+            match selected {
+                Some(todo_idx) => self.select_todo_index(todo_idx),
+                None => self.reset_todo_selection(),
+            }
+        }
+    }
+
+    // 确认搜索：记住关键字并选中光标所在的匹配项，之后可以在匹配项间跳转
+    fn confirm_search(&mut self, query: &str) {
+        let selected = self.selected_todo_index();
+        self.input.clear();
+        self.input_mode = InputMode::Normal;
+        self.search = (!query.is_empty()).then(|| query.to_lowercase());
+        match selected {
+            Some(todo_idx) => self.select_todo_index(todo_idx),
+            None => {
+                self.reset_todo_selection();
+                if self.search.is_some() {
+                    self.warn(format!("没有匹配「{}」的任务", query));
+                }
+            }
+        }
+    }
+
+    // 跳到下一个（forward 为 false 时上一个）匹配搜索关键字的 todo，到头后回到另一端
+    pub fn jump_to_match(&mut self, forward: bool) {
+        let Some(query) = &self.search else {
+            return;
+        };
+        let todos = self.get_current_todos();
+        let matches: Vec<usize> = (0..todos.len())
+            .filter(|&i| todos[i].matches(query))
+            .collect();
+        if matches.is_empty() {
+            self.warn(format!("没有匹配「{}」的任务", query));
+            return;
+        }
+
+        let current = self.todo_state.selected();
+        let next = if forward {
+            matches
+                .iter()
+                .find(|&&i| current.is_none_or(|c| i > c))
+                .unwrap_or(&matches[0])
+        } else {
+            matches
+                .iter()
+                .rev()
+                .find(|&&i| current.is_none_or(|c| i < c))
+                .unwrap_or(&matches[matches.len() - 1])
+        };
+        self.todo_state.select(Some(*next));
+    }
+
+    // 输入要筛选的标签，输入框预填当前的筛选
+    pub fn start_filtering_tag(&mut self) {
+        self.input_mode = InputMode::FilteringTag;
+        self.input.set(self.tag_filter.clone().unwrap_or_default());
+    }
+
+    // 按标签筛选 todo，输入为空时取消筛选
+    fn set_tag_filter(&mut self, input: &str) {
+        let tag = input.trim_start_matches('#');
+        self.tag_filter = (!tag.is_empty()).then(|| tag.to_string());
+        self.input.clear();
+        self.input_mode = InputMode::Normal;
+        self.reset_todo_selection();
+    }
+
+    // 进入重命名模式，输入框预填当前名称
+    pub fn start_renaming(&mut self) {
+        match self.active_panel {
+            Panel::Projects => {
+                if let Some(name) = self.get_current_project().map(|p| p.name.clone()) {
+                    self.input_mode = InputMode::RenamingProject;
+                    self.input.set(name);
+                }
+            }
+            Panel::Todos => {
+                if let Some(title) = self.get_current_todo_mut().map(|t| t.title_with_tags()) {
+                    self.input_mode = InputMode::RenamingTodo;
+                    self.input.set(title);
+                }
+            }
+        }
+    }
+
+    // 是否已有同名项目（不区分大小写，包括归档的项目），exclude 为要排除的项目下标
+    pub fn project_name_taken(&self, name: &str, exclude: Option<usize>) -> bool {
+        let name = name.to_lowercase();
+        self.projects
+            .iter()
+            .enumerate()
+            .any(|(i, project)| Some(i) != exclude && project.name.to_lowercase() == name)
+            || self
+                .archived_projects
+                .iter()
+                .any(|project| project.name.to_lowercase() == name)
+    }
+
+    // 提交输入框内容，根据输入模式添加、重命名或移动，返回是否有修改
+    // 输入为空或只有空白时提示并停留在输入模式
+    pub fn submit_input(&mut self) -> bool {
+        if self.input_mode == InputMode::EditingDescription {
+            return self.set_description();
+        }
+        let input = self.input.trim().to_string();
+        if self.input_mode == InputMode::SettingDueDate {
+            return self.set_due_date(&input);
+        }
+        if self.input_mode == InputMode::EditingSession {
+            return self.set_session(&input);
+        }
+        if self.input_mode == InputMode::LoggingTime {
+            return self.log_time(&input);
+        }
+        if self.input_mode == InputMode::SettingEstimate {
+            return self.set_estimate(&input);
+        }
+        if self.input_mode == InputMode::Searching {
+            self.confirm_search(&input);
+            return false;
+        }
+        if self.input_mode == InputMode::Finding {
+            self.confirm_finder();
+            return false;
+        }
+        if self.input_mode == InputMode::FilteringTag {
+            self.set_tag_filter(&input);
+            return false;
+        }
+        if self.input_mode == InputMode::SettingParent {
+            return self.set_parent(&input);
+        }
+        if self.input_mode == InputMode::TaggingTodos {
+            return self.tag_marked(&input);
+        }
+        if self.input_mode == InputMode::AddingWorkspace {
+            self.input.clear();
+            self.input_mode = InputMode::Normal;
+            self.switch_workspace(Some(input));
+            return false;
+        }
+        // todo 的标题去掉标签等标记后也不能为空
+        let untitled = match self.input_mode {
+            InputMode::AddingTodo => parse_quick_add(&input, today()).title.is_empty(),
+            InputMode::RenamingTodo => parse_tags(&input).0.is_empty(),
+            _ => false,
+        };
+        if input.is_empty() || untitled {
+            self.warn("名称不能为空");
+            return false;
+        }
+        if self.input_mode == InputMode::MovingTodos {
+            return self.move_marked_to(&input);
+        }
+        if self.input_mode == InputMode::NamingTemplate {
+            self.input.clear();
+            self.input_mode = InputMode::Normal;
+            return self.save_template(input);
+        }
+
+        // 项目名不区分大小写地保持唯一，重命名时排除项目自身
+        let exclude = match self.input_mode {
+            InputMode::AddingProject => Some(None),
+            InputMode::RenamingProject => Some(self.project_state.selected()),
+            _ => None,
+        };
+        if let Some(exclude) = exclude {
+            if self.project_name_taken(&input, exclude) {
+                self.warn(format!("已存在同名项目「{}」", input));
+                return false;
+            }
+        }
+
+        self.input.clear();
+        let changed = match self.input_mode {
+            InputMode::AddingProject => {
+                self.add_project(input);
+                true
+            }
+            InputMode::AddingTodo => self.add_todo(input),
+            InputMode::AddingSubtask => self.add_subtask(input),
+            InputMode::RenamingProject => self.rename_selected_project(input),
+            InputMode::RenamingTodo => self.rename_selected_todo(input),
+            InputMode::Normal
+            | InputMode::MovingTodos
+            | InputMode::SettingDueDate
+            | InputMode::FilteringTag
+            | InputMode::EditingDescription
+            | InputMode::EditingSession
+            | InputMode::LoggingTime
+            | InputMode::SettingEstimate
+            | InputMode::AddingWorkspace
+            | InputMode::SettingParent
+            | InputMode::NamingTemplate
+            | InputMode::TaggingTodos
+            | InputMode::Searching
+            | InputMode::Finding
+            | InputMode::Commanding => false,
+        };
+        self.input_mode = InputMode::Normal;
+        changed
+    }
+}
+
+// 导出的文件名和内容，文件放在当前目录，以项目名命名
+fn markdown_file(project: &Project, unit: DurationUnit) -> (String, String) {
+    let path = format!("{}.md", project.name.replace(['/', '\\'], "_"));
+    (path, report::project_markdown(project, unit))
+}
+
+// 写入新文件，不覆盖已有的文件：同名文件已存在时依次尝试 "名称 (2).md"、"名称 (3).md"，返回实际写入的路径
+fn write_new_file(path: &str, content: &str) -> std::io::Result<String> {
+    let (stem, extension) = match path.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, format!(".{}", extension)),
+        _ => (path, String::new()),
+    };
+    let mut candidate = path.to_string();
+    let mut n = 1;
+    loop {
+        let file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&candidate);
+        match file {
+            Ok(mut file) => {
+                std::io::Write::write_all(&mut file, content.as_bytes())?;
+                return Ok(candidate);
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => {
+                n += 1;
+                candidate = format!("{} ({}){}", stem, n, extension);
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(name: &str, todos: &[&str]) -> Project {
+        Project {
+            todos: todos.iter().map(|t| Todo::new(t.to_string())).collect(),
+            ..Project::new(name.to_string())
+        }
+    }
+
+    fn sample_app() -> App {
+        App::with_projects(vec![
+            project("a", &["a1", "a2", "a3"]),
+            project("b", &[]),
+            project("c", &["c1"]),
+        ])
+    }
+
+    #[test]
+    fn completed_todos_can_be_hidden() {
+        let mut app = sample_app();
+        app.switch_panel();
+        app.toggle_hide_completed();
+        app.select_next();
+        assert!(app.toggle_selected_todo());
+        // 完成的 a2 被隐藏，光标停在原来的位置
+        assert_eq!(app.get_current_todo().unwrap().title, "a3");
+        assert_eq!(app.visible_todo_indices(), vec![0, 2]);
+        assert_eq!(app.hidden_completed_count(), 1);
+
+        // 看板中仍然显示已完成的 todo，关闭后保持选中
+        app.open_board();
+        assert_eq!(app.board_cards(2), vec![1]);
+        app.close_view();
+        assert_eq!(app.get_current_todo().unwrap().title, "a3");
+
+        app.toggle_hide_completed();
+        assert_eq!(app.hidden_completed_count(), 0);
+        assert_eq!(app.get_current_todo().unwrap().title, "a3");
+        assert_eq!(app.visible_todo_indices().len(), 3);
+    }
+
+    #[test]
+    fn changes_are_recorded_as_activity() {
+        let mut app = sample_app();
+        app.switch_panel();
+        app.start_adding();
+        app.input.set("a4 #tag");
+        assert!(app.submit_input());
+        app.start_renaming();
+        app.input.set("a5");
+        assert!(app.submit_input());
+        app.toggle_current_todo_timer();
+        app.toggle_selected_todo();
+        app.toggle_selected_todo();
+        app.delete_selected_todo();
+
+        let kinds: Vec<EventKind> = app.unsaved_events.iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                EventKind::Created,
+                EventKind::Renamed,
+                EventKind::TimerStarted,
+                EventKind::Completed,
+                EventKind::Reopened,
+                EventKind::Deleted,
+            ]
+        );
+        let renamed = &app.unsaved_events[1];
+        assert_eq!(
+            (renamed.project.as_str(), renamed.todo.as_str()),
+            ("a", "a5")
+        );
+        assert_eq!(renamed.detail.as_deref(), Some("a4"));
+
+        // 文件中的旧记录排在还没有写入的记录之后
+        let dir = std::env::temp_dir().join(format!("s_todo_app_activity_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir
+            .join("data.activity.jsonl")
+            .to_string_lossy()
+            .into_owned();
+        let old = Event::new(EventKind::Created, "old", "old todo");
+        activity::append(&path, std::slice::from_ref(&old)).unwrap();
+        app.open_activity_from(&path);
+        assert!(app.view == View::Activity);
+        assert_eq!(app.activity_log.len(), 7);
+        assert_eq!(app.activity_log[0].kind, EventKind::Deleted);
+        assert_eq!(app.activity_log[6], old);
+        let _ = std::fs::remove_dir_all(&dir);
+
+        // 批量完成只记录原来未完成的 todo
+        app.unsaved_events.clear();
+        app.projects[0].todos[0].completed = true;
+        app.selecting = true;
+        app.marked_todos.extend([0, 1]);
+        assert!(app.complete_marked());
+        let completed: Vec<(EventKind, &str)> = app
+            .unsaved_events
+            .iter()
+            .map(|e| (e.kind, e.todo.as_str()))
+            .collect();
+        assert_eq!(completed, [(EventKind::Completed, "a2")]);
+    }
+
+    #[test]
+    fn deleting_last_todo_selects_new_last() {
+        let mut app = sample_app();
+        app.todo_state.select(Some(2));
+        assert!(app.delete_selected_todo());
+        assert_eq!(app.todo_state.selected(), Some(1));
+        assert!(app.delete_selected_todo());
+        assert!(app.delete_selected_todo());
+        assert_eq!(app.todo_state.selected(), None);
+        assert!(!app.delete_selected_todo());
+    }
+
+    #[test]
+    fn deleting_middle_todo_keeps_index() {
+        let mut app = sample_app();
+        app.todo_state.select(Some(1));
+        app.delete_selected_todo();
+        assert_eq!(app.todo_state.selected(), Some(1));
+        assert_eq!(app.get_current_todos()[0].title, "a1");
+        assert_eq!(app.get_current_todos()[1].title, "a3");
+    }
+
+    #[test]
+    fn deleting_project_resets_todo_selection() {
+        let mut app = sample_app();
+        app.todo_state.select(Some(2));
+        // 删除 a 后选中 b，b 没有 todo
+        app.delete_selected_project();
+        assert_eq!(app.project_state.selected(), Some(0));
+        assert_eq!(app.get_current_project().unwrap().name, "b");
+        assert_eq!(app.todo_state.selected(), None);
+    }
+
+    #[test]
+    fn deleting_last_project_selects_previous() {
+        let mut app = sample_app();
+        app.project_state.select(Some(2));
+        app.delete_selected_project();
+        assert_eq!(app.project_state.selected(), Some(1));
+        app.delete_selected_project();
+        app.delete_selected_project();
+        assert_eq!(app.project_state.selected(), None);
+        assert_eq!(app.todo_state.selected(), None);
+        assert!(!app.delete_selected_project());
+    }
+
+    #[test]
+    fn empty_projects_do_not_panic() {
+        let mut app = sample_app();
+        while app.delete_selected_project() {}
+        assert!(app.projects.is_empty());
+
+        app.select_next();
+        app.select_previous();
+        assert_eq!(app.project_state.selected(), None);
+
+        app.switch_panel();
+        app.select_next();
+        app.select_previous();
+        assert!(!app.toggle_selected_todo());
+        assert!(!app.toggle_current_todo_timer());
+        assert!(!app.delete_selected_todo());
+
+        app.start_adding();
+        app.input.set("x");
+        assert!(!app.submit_input());
+
+        app.switch_panel();
+        app.start_adding();
+        app.input.set("p");
+        assert!(app.submit_input());
+        assert_eq!(app.project_state.selected(), Some(0));
+    }
+
+    #[test]
+    fn navigation_wraps_around() {
+        let mut app = sample_app();
+        app.select_previous();
+        assert_eq!(app.project_state.selected(), Some(2));
+        app.select_next();
+        assert_eq!(app.project_state.selected(), Some(0));
+
+        app.switch_panel();
+        app.select_previous();
+        assert_eq!(app.todo_state.selected(), Some(2));
+        app.select_next();
+        assert_eq!(app.todo_state.selected(), Some(0));
+    }
+
+    #[test]
+    fn moving_to_empty_project_clears_todo_selection() {
+        let mut app = sample_app();
+        app.select_next();
+        assert_eq!(app.todo_state.selected(), None);
+        app.switch_panel();
+        assert!(!app.toggle_selected_todo());
+        app.start_renaming();
+        assert!(app.input_mode == InputMode::Normal);
+    }
+
+    #[test]
+    fn completing_todo_stops_timer() {
+        let mut app = sample_app();
+        app.switch_panel();
+        assert!(app.toggle_current_todo_timer());
+        assert!(app.get_current_todos()[0].is_working());
+        app.toggle_selected_todo();
+        let todo = app.get_current_todos()[0];
+        assert!(todo.completed);
+        assert!(!todo.is_working());
+        // 已完成的任务不能计时
+        assert!(!app.toggle_current_todo_timer());
+    }
+
+    #[test]
+    fn toggle_all_completes_then_uncompletes() {
+        let mut app = sample_app();
+        app.switch_panel();
+        app.toggle_current_todo_timer();
+        app.toggle_selected_todo();
+        app.select_next();
+        app.toggle_current_todo_timer();
+
+        assert!(app.toggle_all_todos());
+        let todos = app.get_current_todos();
+        assert!(todos.iter().all(|t| t.completed && !t.is_working()));
+
+        assert!(app.toggle_all_todos());
+        assert!(app.get_current_todos().iter().all(|t| !t.completed));
+
+        // 空项目不做修改
+        app.switch_panel();
+        app.select_next();
+        assert!(!app.toggle_all_todos());
+    }
+
+    #[test]
+    fn stop_all_timers_across_projects() {
+        let mut app = sample_app();
+        app.projects[0].todos[0].start_work();
+        app.projects[2].todos[0].start_work();
+        assert_eq!(app.stop_all_timers(), 2);
+        assert!(app.projects[0].todos[0].end_time.is_some());
+        assert!(!app.projects[2].todos[0].is_working());
+        assert_eq!(app.stop_all_timers(), 0);
+    }
+
+    #[test]
+    fn sorting_keeps_selected_todo() {
+        let mut app = App::with_projects(vec![project("p", &["b", "c", "a"])]);
+        app.switch_panel();
+        app.select_next();
+        assert_eq!(app.selected_todo_index(), Some(1));
+
+        // 按标题排序后 "c" 显示在最后
+        assert!(app.cycle_sort_mode());
+        assert_eq!(app.todo_state.selected(), Some(2));
+        assert_eq!(app.selected_todo_index(), Some(1));
+
+        // 未完成优先时，完成的 todo 移到最后并保持选中
+        app.cycle_sort_mode();
+        app.select_previous();
+        assert_eq!(app.get_current_todo_mut().unwrap().title, "b");
+        app.toggle_selected_todo();
+        assert_eq!(app.todo_state.selected(), Some(2));
+        assert_eq!(app.get_current_todo_mut().unwrap().title, "b");
+
+        // 删除按显示位置修正选中
+        app.delete_selected_todo();
+        assert_eq!(app.todo_state.selected(), Some(1));
+        assert_eq!(app.get_current_todo_mut().unwrap().title, "a");
+    }
+
+    #[test]
+    fn timer_alert_fires_once_per_session() {
+        let mut app = sample_app();
+        app.config.timer_alert_minutes = 1;
+        app.projects[0].todos[0].start_time = Some(1_000);
+
+        assert!(!app.check_timer_alerts(1_059));
+        assert!(app.check_timer_alerts(1_060));
+        assert!(!app.check_timer_alerts(1_200));
+
+        // 重新开始计时后可以再次提醒
+        app.projects[0].todos[0].end_work_at(1_200);
+        app.projects[0].todos[0].start_work();
+        assert!(!app.projects[0].todos[0].alerted);
+
+        app.config.timer_alert_minutes = 0;
+        assert!(!app.check_timer_alerts(u64::MAX));
+        assert_eq!(app.notifications.len(), 1);
+    }
+
+    #[test]
+    fn interrupted_timers_can_be_kept_truncated_or_discarded() {
+        let mut app = sample_app();
+        app.interrupted_at = 1_600;
+        app.view = View::ResumeTimers;
+        app.projects[0].todos[0].start_time = Some(1_000);
+        app.projects[2].todos[0].start_time = Some(1_200);
+        assert!(!app.resume_timers(ResumeChoice::Keep));
+        assert!(app.view == View::Main);
+        assert!(app.projects[0].todos[0].is_working());
+
+        assert!(app.resume_timers(ResumeChoice::Truncate));
+        assert_eq!(app.projects[0].todos[0].total_duration(), 600);
+        assert_eq!(app.projects[2].todos[0].total_duration(), 400);
+
+        app.projects[0].todos[0].start_time = Some(2_000);
+        app.projects[0].todos[0].end_time = None;
+        assert!(app.resume_timers(ResumeChoice::Discard));
+        assert!(!app.has_running_timer());
+        assert_eq!(app.projects[0].todos[0].sessions.len(), 1);
+    }
+
+    #[test]
+    fn idle_time_can_be_discarded() {
+        let mut app = sample_app();
+        app.config.idle_minutes = 10;
+        app.last_activity = 1_000;
+        assert!(!app.record_activity(5_000));
+
+        app.projects[0].todos[0].start_time = Some(5_000);
+        assert!(!app.record_activity(5_599));
+        assert!(app.record_activity(6_200));
+        assert!(app.view == View::IdlePrompt);
+        assert!(!app.resolve_idle(true));
+        assert!(app.view == View::Main);
+
+        assert!(app.record_activity(7_000));
+        assert!(app.resolve_idle(false));
+        let todo = &app.projects[0].todos[0];
+        assert_eq!(todo.total_duration(), 1_200);
+        assert!(todo.is_working());
+    }
+
+    #[test]
+    fn starting_a_timer_stops_the_others() {
+        let mut app = sample_app();
+        app.switch_panel();
+        app.projects[2].todos[0].start_work();
+        assert!(app.toggle_current_todo_timer());
+        assert!(app.projects[0].todos[0].is_working());
+        assert!(!app.projects[2].todos[0].is_working());
+        assert_eq!(app.projects[2].todos[0].sessions.len(), 1);
+
+        // 关闭后允许多个计时同时进行
+        app.config.single_timer = false;
+        app.projects[2].todos[0].start_work();
+        app.select_next();
+        assert!(app.toggle_current_todo_timer());
+        assert!(app.projects[0].todos[0].is_working());
+        assert!(app.projects[2].todos[0].is_working());
+    }
+
+    #[test]
+    fn due_reminders_are_sent_once_a_day() {
+        let mut app = sample_app();
+        let day = |d| NaiveDate::from_ymd_opt(2026, 3, d).unwrap();
+        app.projects[0].todos[0].due_date = Some(day(2));
+        app.projects[2].todos[0].due_date = Some(day(3));
+
+        app.check_due_reminders(day(1));
+        assert!(app.notifications.is_empty());
+        app.check_due_reminders(day(2));
+        app.check_due_reminders(day(2));
+        assert_eq!(app.notifications.len(), 1);
+
+        // 关闭桌面通知后不再加入队列
+        app.notifications.clear();
+        app.config.desktop_notifications = false;
+        app.check_due_reminders(day(3));
+        assert!(app.notifications.is_empty());
+    }
+
+    #[test]
+    fn duplicate_project_names_are_rejected() {
+        let mut app = sample_app();
+        app.start_adding();
+        app.input.set("A");
+        assert!(!app.submit_input());
+        assert!(app.input_mode == InputMode::AddingProject);
+        assert_eq!(app.projects.len(), 3);
+
+        // 重命名为自己的名字（改变大小写）是允许的
+        app.input_mode = InputMode::Normal;
+        app.start_renaming();
+        app.input.set("A");
+        assert!(app.submit_input());
+        assert_eq!(app.projects[0].name, "A");
+
+        app.start_renaming();
+        app.input.set("b");
+        assert!(!app.submit_input());
+        assert!(app.input_mode == InputMode::RenamingProject);
+    }
+
+    #[test]
+    fn submit_input_adds_and_renames() {
+        let mut app = sample_app();
+        app.start_adding();
+        app.input.set("d");
+        assert!(app.submit_input());
+        assert_eq!(app.project_state.selected(), Some(3));
+        assert_eq!(app.todo_state.selected(), None);
+
+        app.switch_panel();
+        app.start_adding();
+        app.input.set("d1");
+        assert!(app.submit_input());
+        assert_eq!(app.todo_state.selected(), Some(0));
+
+        app.start_renaming();
+        assert_eq!(app.input, "d1");
+        app.input.set("d2");
+        app.submit_input();
+        assert_eq!(app.get_current_todos()[0].title, "d2");
+        assert!(app.input_mode == InputMode::Normal);
+
+        // 空白输入不做修改，停留在输入模式
+        app.start_adding();
+        app.input.set("   ");
+        assert!(!app.submit_input());
+        assert!(app.input_mode == InputMode::AddingTodo);
+        assert!(app.message.is_some());
+
+        // 首尾空白会被去掉
+        app.input.set("  d3 ");
+        assert!(app.submit_input());
+        assert_eq!(app.get_current_todos()[1].title, "d3");
+    }
+
+    #[test]
+    fn collapse_others_toggles_and_focus_mode_leaves_on_switch() {
+        let mut app = sample_app();
+        app.project_state.select(Some(1));
+        app.collapse_other_projects();
+        let collapsed: Vec<bool> = app.projects.iter().map(|p| p.collapsed).collect();
+        assert_eq!(collapsed, [true, false, true]);
+        app.collapse_other_projects();
+        assert!(app.projects.iter().all(|p| !p.collapsed));
+
+        app.toggle_focus_mode();
+        assert!(app.focus_mode);
+        assert!(app.active_panel == Panel::Todos);
+        // 切回项目面板时自动退出专注模式
+        app.switch_panel();
+        assert!(!app.focus_mode);
+        assert!(app.active_panel == Panel::Projects);
+    }
+
+    #[test]
+    fn marked_todos_are_completed_deleted_or_moved_together() {
+        let mut app = sample_app();
+        app.switch_panel();
+        app.toggle_select_mode();
+        assert!(app.selecting);
+        app.toggle_mark();
+        app.select_next();
+        app.select_next();
+        app.toggle_mark();
+        assert!(app.complete_marked());
+        assert!(!app.selecting && app.marked_todos.is_empty());
+        let completed: Vec<bool> = app
+            .get_current_todos()
+            .iter()
+            .map(|t| t.completed)
+            .collect();
+        assert_eq!(completed, [true, false, true]);
+
+        // 没有选中时不做修改
+        app.toggle_select_mode();
+        app.request_delete_marked();
+        assert!(app.view == View::Main);
+        assert!(!app.delete_marked());
+        assert!(app.message.is_some());
+
+        app.toggle_select_mode();
+        app.toggle_mark();
+        app.select_next();
+        app.toggle_mark();
+        app.start_tagging_marked();
+        assert!(app.input_mode == InputMode::TaggingTodos);
+        app.input.set("#");
+        assert!(!app.submit_input());
+        app.input.set("#urgent Work");
+        assert!(app.submit_input());
+        assert!(!app.selecting && app.marked_todos.is_empty());
+        let tags: Vec<usize> = app
+            .get_current_todos()
+            .iter()
+            .map(|t| t.tags.len())
+            .collect();
+        assert_eq!(tags, [2, 0, 2]);
+
+        app.toggle_select_mode();
+        app.todo_state.select(Some(0));
+        app.toggle_mark();
+        app.todo_state.select(Some(2));
+        app.toggle_mark();
+        app.start_moving();
+        assert!(app.input_mode == InputMode::MovingTodos);
+        app.input.set("nope");
+        assert!(!app.submit_input());
+        assert!(app.input_mode == InputMode::MovingTodos);
+        app.input.set("C");
+        assert!(app.submit_input());
+        assert!(app.input_mode == InputMode::Normal);
+        let titles: Vec<&str> = app.projects[2]
+            .todos
+            .iter()
+            .map(|t| t.title.as_str())
+            .collect();
+        assert_eq!(titles, ["c1", "a1", "a3"]);
+        assert_eq!(app.get_current_todos()[0].title, "a2");
+        assert_eq!(app.todo_state.selected(), Some(0));
+
+        // 离开 Todo 面板时清空选择
+        app.toggle_select_mode();
+        app.toggle_mark();
+        app.switch_panel();
+        assert!(!app.selecting && app.marked_todos.is_empty());
+    }
+
+    #[test]
+    fn ui_state_is_restored_within_bounds() {
+        let mut app = sample_app();
+        app.project_state.select(Some(2));
+        app.reset_todo_selection();
+        app.switch_panel();
+        let state = app.ui_state();
+
+        let mut restored = sample_app();
+        restored.restore_ui_state(&state);
+        assert_eq!(restored.project_state.selected(), Some(2));
+        assert_eq!(restored.todo_state.selected(), Some(0));
+        assert!(restored.active_panel == Panel::Todos);
+
+        // 项目或 todo 在外部被删除时选中最后一个
+        let state = UiState {
+            project: Some(9),
+            todo: Some(9),
+            active_panel: Panel::Projects,
+            hide_completed: false,
+            detail_pane: true,
+            project_split: Some(90),
+            single_pane: true,
+        };
+        let mut restored = App::with_projects(vec![project("a", &["a1", "a2"])]);
+        restored.restore_ui_state(&state);
+        assert!(restored.detail_pane);
+        assert_eq!(restored.project_split, Some(60));
+        assert!(restored.single_pane);
+        assert_eq!(restored.project_state.selected(), Some(0));
+        assert_eq!(restored.todo_state.selected(), Some(1));
+
+        let mut empty = App::with_projects(vec![]);
+        empty.restore_ui_state(&state);
+        assert_eq!(empty.project_state.selected(), None);
+    }
+
+    #[test]
+    fn project_panel_is_resized_within_bounds() {
+        let mut app = sample_app();
+        app.resize_projects(true);
+        assert_eq!(app.project_split, Some(35));
+        for _ in 0..10 {
+            app.resize_projects(false);
+        }
+        assert_eq!(app.project_split, Some(15));
+        for _ in 0..20 {
+            app.resize_projects(true);
+        }
+        assert_eq!(app.project_split, Some(60));
+    }
+
+    #[test]
+    fn due_date_is_set_validated_and_cleared() {
+        let mut app = sample_app();
+        app.switch_panel();
+        app.start_setting_due_date();
+        assert!(app.input_mode == InputMode::SettingDueDate);
+        assert!(app.input.is_empty());
+
+        app.input.set("someday");
+        assert!(!app.submit_input());
+        assert!(app.input_mode == InputMode::SettingDueDate);
+        app.input.set("2026-03-04");
+        assert!(app.submit_input());
+        assert_eq!(
+            app.get_current_todos()[0].due_date,
+            NaiveDate::from_ymd_opt(2026, 3, 4)
+        );
+
+        app.start_setting_due_date();
+        assert_eq!(app.input, "2026-03-04");
+        app.input.clear();
+        assert!(app.submit_input());
+        assert_eq!(app.get_current_todos()[0].due_date, None);
+    }
+
+    #[test]
+    fn estimate_is_set_and_prefilled() {
+        let mut app = sample_app();
+        app.switch_panel();
+        app.start_setting_estimate();
+        assert!(app.input_mode == InputMode::SettingEstimate);
+        app.input.set("soon");
+        assert!(!app.submit_input());
+        app.input.set("1h 30m");
+        assert!(app.submit_input());
+        assert_eq!(app.get_current_todos()[0].estimate, Some(5400));
+
+        app.start_setting_estimate();
+        assert_eq!(app.input, "1h 30m");
+        app.input.clear();
+        assert!(app.submit_input());
+        assert_eq!(app.get_current_todos()[0].estimate, None);
+    }
+
+    #[test]
+    fn tag_filter_narrows_current_project() {
+        let mut app = sample_app();
+        app.switch_panel();
+        app.start_adding();
+        app.input.set("buy milk #Errand #home");
+        assert!(app.submit_input());
+        let added = app.get_current_todos()[3];
+        assert_eq!(added.title, "buy milk");
+        assert_eq!(added.tags, ["Errand", "home"]);
+
+        // 只有标签没有标题时不添加
+        app.start_adding();
+        app.input.set("#errand");
+        assert!(!app.submit_input());
+        app.input_mode = InputMode::Normal;
+
+        app.start_filtering_tag();
+        app.input.set("#errand");
+        app.submit_input();
+        assert_eq!(app.tag_filter.as_deref(), Some("errand"));
+        assert_eq!(app.get_current_todos().len(), 1);
+        assert_eq!(app.todo_state.selected(), Some(0));
+        assert_eq!(app.selected_todo_index(), Some(3));
+
+        app.start_renaming();
+        assert_eq!(app.input, "buy milk #Errand #home");
+
+        // 删除筛选结果中的最后一个后列表为空
+        assert!(app.delete_selected_todo());
+        assert_eq!(app.todo_state.selected(), None);
+
+        app.start_filtering_tag();
+        app.input.clear();
+        app.submit_input();
+        assert_eq!(app.tag_filter, None);
+        assert_eq!(app.get_current_todos().len(), 3);
+    }
+
+    #[test]
+    fn subtasks_are_added_toggled_and_deleted() {
+        let mut app = sample_app();
+        app.switch_panel();
+        app.select_next();
+        app.open_subtasks();
+        assert!(app.view == View::Subtasks);
+        assert_eq!(app.subtask_state.selected(), None);
+        assert!(!app.toggle_selected_subtask());
+
+        app.input_mode = InputMode::AddingSubtask;
+        app.input.set("s1");
+        assert!(app.submit_input());
+        app.input_mode = InputMode::AddingSubtask;
+        app.input.set("s2");
+        assert!(app.submit_input());
+        assert_eq!(app.subtask_state.selected(), Some(1));
+
+        app.select_subtask(true);
+        assert!(app.toggle_selected_subtask());
+        let todo = app.get_current_todo().unwrap();
+        assert_eq!(todo.title, "a2");
+        assert_eq!(todo.subtask_progress(), Some((1, 2)));
+
+        app.select_subtask(false);
+        assert!(app.delete_selected_subtask());
+        assert_eq!(app.subtask_state.selected(), Some(0));
+        assert_eq!(
+            app.get_current_todo().unwrap().subtask_progress(),
+            Some((1, 1))
+        );
+        assert_eq!(app.get_current_todos()[0].subtask_progress(), None);
+    }
+
+    #[test]
+    fn description_is_edited_over_multiple_lines() {
+        let mut app = sample_app();
+        app.switch_panel();
+        app.start_editing_description();
+        assert!(app.input_mode == InputMode::EditingDescription);
+        app.input.set("line 1\nline 2\n\n");
+        assert!(app.submit_input());
+        assert_eq!(
+            app.get_current_todo().unwrap().description,
+            "line 1\nline 2"
+        );
+
+        app.start_editing_description();
+        assert_eq!(app.input, "line 1\nline 2");
+        app.input.clear();
+        assert!(app.submit_input());
+        assert!(app.get_current_todo().unwrap().description.is_empty());
+    }
+
+    #[test]
+    fn deleting_project_with_todos_needs_confirmation() {
+        let mut app = sample_app();
+        assert!(!app.request_delete_project());
+        assert!(app.view == View::ConfirmDeleteProject);
+        assert_eq!(app.projects.len(), 3);
+
+        // 空项目直接删除
+        app.close_view();
+        app.select_next();
+        assert!(app.request_delete_project());
+        assert!(app.view == View::Main);
+        assert_eq!(app.projects.len(), 2);
+    }
+
+    #[test]
+    fn deleted_items_can_be_restored_from_trash() {
+        let mut app = sample_app();
+        app.switch_panel();
+        app.toggle_current_todo_timer();
+        assert!(app.delete_selected_todo());
+        app.switch_panel();
+        assert!(app.delete_selected_project());
+        assert_eq!(app.trash.len(), 2);
+        // 删除时结束计时
+        assert!(!app.has_running_timer());
+
+        // 回收站从新到旧显示，先恢复项目，再恢复 todo 到原项目
+        app.open_trash();
+        assert!(app.restore_selected_trash());
+        assert_eq!(app.projects.last().unwrap().name, "a");
+        assert!(app.restore_selected_trash());
+        assert_eq!(app.trash_state.selected(), None);
+        let titles: Vec<&str> = app.projects[2]
+            .todos
+            .iter()
+            .map(|t| t.title.as_str())
+            .collect();
+        assert_eq!(titles, ["a2", "a3", "a1"]);
+
+        // 原项目不存在时重新创建，重名项目加后缀
+        app.trash.push(TrashItem::new(
+            Trashed::Todo {
+                project: "gone".to_string(),
+                todo: Todo::new("g1".to_string()),
+            },
+            0,
+        ));
+        app.trash
+            .push(TrashItem::new(Trashed::Project(project("b", &[])), 0));
+        app.open_trash();
+        assert!(app.restore_selected_trash());
+        assert_eq!(app.projects.last().unwrap().name, "b (恢复)");
+        assert!(app.restore_selected_trash());
+        assert_eq!(app.projects.last().unwrap().name, "gone");
+        assert!(!app.purge_selected_trash());
+    }
+
+    #[test]
+    fn search_filters_live_and_jumps_between_matches() {
+        let mut app =
+            App::with_projects(vec![project("p", &["milk", "bread", "Milkshake", "eggs"])]);
+        app.projects[0].todos[3].description = "buy with milk".to_string();
+        app.switch_panel();
+        app.start_searching();
+        app.input.set("MIL");
+        app.input_changed();
+        assert_eq!(app.get_current_todos().len(), 3);
+        app.select_next();
+        assert_eq!(app.selected_todo_index(), Some(2));
+
+        // 确认后恢复完整列表，光标停在原来的匹配项上
+        app.submit_input();
+        assert_eq!(app.get_current_todos().len(), 4);
+        assert_eq!(app.todo_state.selected(), Some(2));
+        app.jump_to_match(true);
+        assert_eq!(app.todo_state.selected(), Some(3));
+        app.jump_to_match(true);
+        assert_eq!(app.todo_state.selected(), Some(0));
+        app.jump_to_match(false);
+        assert_eq!(app.todo_state.selected(), Some(3));
+
+        // 取消搜索时保留光标所在的 todo
+        app.start_searching();
+        app.input.set("bread");
+        app.input_changed();
+        app.cancel_input();
+        assert_eq!(app.todo_state.selected(), Some(1));
+    }
+
+    #[test]
+    fn finder_jumps_to_todo_in_another_project() {
+        let mut app = App::with_projects(vec![
+            project("work", &["write report", "prepare slides"]),
+            project("home", &["buy milk", "repair bike"]),
+        ]);
+        app.projects[1].collapsed = true;
+        app.tag_filter = Some("errand".to_string());
+        app.start_finding();
+        assert_eq!(app.finder_results.len(), 4);
+
+        app.input.set("rep");
+        app.input_changed();
+        assert_eq!(app.finder_results[0], (1, 1));
+        assert_eq!(app.finder_results.len(), 3);
+        app.select_finder(false);
+        app.select_finder(true);
+
+        assert!(!app.submit_input());
+        assert!(app.input_mode == InputMode::Normal);
+        assert_eq!(app.project_state.selected(), Some(1));
+        assert_eq!(app.active_panel, Panel::Todos);
+        assert!(!app.projects[1].collapsed);
+        assert_eq!(app.tag_filter, None);
+        assert_eq!(app.get_current_todo().unwrap().title, "repair bike");
+    }
+
+    #[test]
+    fn projects_nest_fold_and_follow_renames() {
+        let mut app = App::with_projects(vec![
+            project("work", &[]),
+            project("home", &[]),
+            project("meetings", &["standup"]),
+        ]);
+        // 把 meetings 移到 work 下
+        app.project_state.select(Some(2));
+        app.start_setting_parent();
+        assert_eq!(app.input, "");
+        app.input.set("Work");
+        assert!(app.submit_input());
+        let names: Vec<&str> = app.projects.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["work", "meetings", "home"]);
+        assert_eq!(app.get_current_project().unwrap().name, "meetings");
+
+        // 不能移到自己的子项目下
+        app.project_state.select(Some(0));
+        app.start_setting_parent();
+        app.input.set("meetings");
+        assert!(!app.submit_input());
+        assert!(app.input_mode == InputMode::SettingParent);
+        app.cancel_input();
+
+        // 收起后跳过子项目
+        assert!(app.fold_selected_project(true));
+        assert_eq!(app.visible_project_indices(), [0, 2]);
+        app.select_next();
+        assert_eq!(app.project_state.selected(), Some(2));
+        app.select_next();
+        assert_eq!(app.project_state.selected(), Some(0));
+        assert!(app.fold_selected_project(false));
+
+        // 子项目上收起时选中上级
+        app.project_state.select(Some(1));
+        assert!(!app.fold_selected_project(true));
+        assert_eq!(app.project_state.selected(), Some(0));
+
+        app.rename_selected_project("job".to_string());
+        assert_eq!(app.projects[1].parent.as_deref(), Some("job"));
+        app.delete_selected_project();
+        assert_eq!(app.projects[0].name, "meetings");
+        assert_eq!(app.projects[0].parent, None);
+    }
+
+    #[test]
+    fn todos_and_projects_are_duplicated_without_time() {
+        let mut app = App::with_projects(vec![
+            project("work", &["report", "slides"]),
+            project("home", &[]),
+        ]);
+        let todo = &mut app.projects[0].todos[0];
+        todo.log_session(100, 200);
+        todo.completed = true;
+        todo.tags = vec!["weekly".to_string()];
+        todo.subtasks = vec![Subtask {
+            title: "draft".to_string(),
+            completed: true,
+        }];
+
+        app.switch_panel();
+        assert!(app.duplicate_selected());
+        let titles: Vec<&str> = app.projects[0]
+            .todos
+            .iter()
+            .map(|t| t.title.as_str())
+            .collect();
+        assert_eq!(titles, ["report", "report (copy)", "slides"]);
+        let copy = app.get_current_todo().unwrap();
+        assert_eq!(copy.title, "report (copy)");
+        assert_eq!(copy.total_duration(), 0);
+        assert!(!copy.completed);
+        assert_eq!(copy.tags, ["weekly"]);
+        assert!(!copy.subtasks[0].completed);
+
+        app.switch_panel();
+        assert!(app.duplicate_selected());
+        assert!(app.duplicate_selected());
+        let names: Vec<&str> = app.projects.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["work", "work (copy)", "work (copy) (copy)", "home"]);
+        assert_eq!(app.projects[1].todos.len(), 3);
+        assert!(app.projects[1]
+            .todos
+            .iter()
+            .all(|t| t.total_duration() == 0));
+        assert_eq!(app.project_state.selected(), Some(2));
+    }
+
+    #[test]
+    fn templates_are_saved_and_instantiated() {
+        let mut app = App::with_projects(vec![
+            project("release", &["tag", "publish"]),
+            project("work", &["deploy"]),
+        ]);
+        app.projects[0].todos[0].log_session(100, 200);
+        app.projects[0].todos[0].completed = true;
+
+        app.start_saving_template();
+        assert_eq!(app.input, "release");
+        assert!(app.submit_input());
+        app.select_next();
+        app.switch_panel();
+        app.projects[1].todos[0].subtasks = vec![Subtask {
+            title: "check logs".to_string(),
+            completed: true,
+        }];
+        app.start_saving_template();
+        app.input.set("checklist");
+        assert!(app.submit_input());
+        assert_eq!(app.templates.len(), 2);
+
+        // 同名模板被替换
+        app.start_saving_template();
+        app.input.set("Checklist");
+        assert!(app.submit_input());
+        assert_eq!(app.templates.len(), 2);
+        assert_eq!(app.templates[1].name, "Checklist");
+
+        app.open_templates();
+        assert!(app.instantiate_selected_template());
+        assert!(app.view == View::Main);
+        let names: Vec<&str> = app.projects.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["release", "work", "release 2"]);
+        let copy = &app.projects[2];
+        assert_eq!(copy.todos.len(), 2);
+        assert!(copy
+            .todos
+            .iter()
+            .all(|t| !t.completed && t.total_duration() == 0));
+
+        app.open_templates();
+        app.select_template(true);
+        assert!(app.instantiate_selected_template());
+        assert_eq!(app.active_panel, Panel::Todos);
+        let todo = app.get_current_todo().unwrap();
+        assert_eq!(todo.title, "deploy");
+        assert!(!todo.subtasks[0].completed);
+        assert_eq!(app.projects[2].todos.len(), 3);
+
+        app.open_templates();
+        assert!(app.delete_selected_template());
+        assert_eq!(app.templates.len(), 1);
+        assert_eq!(app.templates_state.selected(), Some(0));
+    }
+
+    #[test]
+    fn merged_project_moves_todos_with_their_timers() {
+        let mut app = App::with_projects(vec![
+            project("inbox", &["a"]),
+            project("work", &["report", "slides"]),
+            project("meetings", &[]),
+        ]);
+        app.projects[2].parent = Some("work".to_string());
+        app.projects[1].todos[0].log_session(100, 200);
+        app.projects[1].todos[1].start_time = Some(now_secs());
+        app.projects[1].archived.push(Todo::new("old".to_string()));
+
+        app.project_state.select(Some(1));
+        app.open_merge_picker();
+        assert!(app.view == View::ProjectPicker);
+        assert_eq!(app.picker_state.selected(), Some(0));
+        app.select_picker(true);
+        assert!(!app.move_to_picked_project());
+
+        app.select_picker(false);
+        assert!(app.move_to_picked_project());
+        let names: Vec<&str> = app.projects.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["inbox", "meetings"]);
+        assert_eq!(app.projects[1].parent.as_deref(), Some("inbox"));
+        let inbox = &app.projects[0];
+        assert_eq!(inbox.todos.len(), 3);
+        assert_eq!(inbox.todos[1].total_duration(), 100);
+        assert!(inbox.todos[2].is_working());
+        assert_eq!(inbox.archived.len(), 1);
+        assert_eq!(app.project_state.selected(), Some(0));
+    }
+
+    #[test]
+    fn project_cannot_be_merged_into_its_own_subtree() {
+        let mut app = App::with_projects(vec![
+            project("s", &["a"]),
+            project("a", &[]),
+            project("t", &[]),
+        ]);
+        app.projects[1].parent = Some("s".to_string());
+        app.projects[2].parent = Some("a".to_string());
+        app.project_state.select(Some(0));
+        app.open_merge_picker();
+        for target in [1, 2] {
+            app.picker_state.select(Some(target));
+            assert!(!app.move_to_picked_project());
+        }
+        assert_eq!(app.projects.len(), 3);
+        assert_eq!(app.projects[1].parent.as_deref(), Some("s"));
+        assert_eq!(app.projects[2].parent.as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn projects_are_archived_and_restored() {
+        let mut app = App::with_projects(vec![
+            project("work", &["report"]),
+            project("old", &["a", "b"]),
+            project("child", &[]),
+        ]);
+        app.projects[2].parent = Some("old".to_string());
+        app.projects[0].todos[0].start_time = Some(now_secs());
+        assert!(!app.archive_selected_project());
+        assert_eq!(app.projects.len(), 3);
+
+        app.project_state.select(Some(1));
+        assert!(app.archive_selected_project());
+        let names: Vec<&str> = app.projects.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["work", "child"]);
+        assert_eq!(app.projects[1].parent, None);
+        assert_eq!(app.archived_projects[0].todos.len(), 2);
+
+        // 归档项目的名称仍然被占用
+        app.start_adding();
+        app.input.set("OLD");
+        assert!(!app.submit_input());
+        assert_eq!(app.projects.len(), 2);
+        app.cancel_input();
+
+        app.add_project("old".to_string());
+        app.open_archived_projects();
+        assert!(app.view == View::ArchivedProjects);
+        assert!(app.unarchive_selected_project());
+        assert!(app.archived_projects.is_empty());
+        assert_eq!(app.archived_projects_state.selected(), None);
+        assert_eq!(app.get_current_project().unwrap().name, "old (恢复)");
+        assert!(!app.unarchive_selected_project());
+    }
+
+    #[test]
+    fn agenda_jumps_to_the_owning_project() {
+        let mut app = App::with_projects(vec![
+            project("work", &["write report"]),
+            project("home", &["buy milk", "repair bike"]),
+        ]);
+        app.open_agenda();
+        assert!(app.view == View::Main);
+        assert!(app.messages.iter().any(|m| m.level == Level::Warning));
+
+        app.projects[0].todos[0].due_date = Some(today() + chrono::Days::new(30));
+        app.projects[1].todos[1].due_date = Some(today());
+        app.projects[1].collapsed = true;
+        app.open_agenda();
+        assert!(app.view == View::Agenda);
+        assert_eq!(app.agenda[0], (AgendaGroup::Today, 1, 1));
+        app.select_agenda(true);
+        app.select_agenda(true);
+
+        app.confirm_agenda();
+        assert!(app.view == View::Main);
+        assert_eq!(app.project_state.selected(), Some(1));
+        assert_eq!(app.active_panel, Panel::Todos);
+        assert!(!app.projects[1].collapsed);
+        assert_eq!(app.get_current_todo().unwrap().title, "repair bike");
+    }
+
+    #[test]
+    fn todos_are_reordered_in_default_sort_mode() {
+        let mut app = App::with_projects(vec![project("p", &["a", "b", "c"])]);
+        app.switch_panel();
+        assert!(!app.move_selected_todo(false));
+        assert!(app.move_selected_todo(true));
+        assert!(app.move_selected_todo(true));
+        assert!(!app.move_selected_todo(true));
+        let titles: Vec<_> = app.projects[0].todos.iter().map(|t| &t.title).collect();
+        assert_eq!(titles, ["b", "c", "a"]);
+        assert_eq!(app.get_current_todo().unwrap().title, "a");
+
+        app.cycle_sort_mode();
+        assert!(!app.move_selected_todo(false));
+        assert!(app.message.is_some());
+    }
+
+    #[test]
+    fn todo_is_moved_to_picked_project_with_its_history() {
+        let mut app = App::with_projects(vec![project("a", &["x", "y"]), project("b", &[])]);
+        app.projects[0].todos[1].log_session(0, 120);
+        app.switch_panel();
+        app.select_next();
+        app.open_project_picker();
+        assert!(app.view == View::ProjectPicker);
+        assert_eq!(app.picker_state.selected(), Some(1));
+
+        // 当前项目不能作为目标
+        app.select_picker(true);
+        assert!(!app.move_to_picked_project());
+        assert!(app.view == View::ProjectPicker);
+
+        app.select_picker(false);
+        assert!(app.move_to_picked_project());
+        assert!(app.view == View::Main);
+        assert_eq!(app.projects[0].todos.len(), 1);
+        assert_eq!(app.projects[1].todos[0].title, "y");
+        assert_eq!(app.projects[1].todos[0].total_duration(), 120);
+        assert_eq!(app.todo_state.selected(), Some(0));
+    }
+
+    #[test]
+    fn board_moves_cards_between_status_columns() {
+        let mut app = App::with_projects(vec![project("p", &["a", "b", "c"])]);
+        app.projects[0].todos[2].completed = true;
+        app.open_board();
+        assert!(app.view == View::Board);
+        assert_eq!(app.board_cards(0), [0, 1]);
+        assert_eq!(app.board_cards(2), [2]);
+
+        app.select_board_card(true);
+        assert!(app.move_board_card(true));
+        assert_eq!(app.board_column, 1);
+        assert_eq!(app.board_cards(1), [1]);
+        assert_eq!(app.board_selected(), Some(0));
+        assert!(app.projects[0].todos[1].doing);
+
+        assert!(app.move_board_card(true));
+        assert!(app.projects[0].todos[1].completed);
+        assert!(!app.move_board_card(true));
+
+        // 切换到空列时没有选中的卡片
+        app.switch_board_column(false);
+        assert_eq!(app.board_selected(), None);
+        assert!(!app.move_board_card(false));
+        app.switch_board_column(false);
+        assert_eq!(app.get_current_todo().unwrap().title, "a");
+    }
+
+    #[test]
+    fn completed_todos_are_archived_and_restored() {
+        let mut app = App::with_projects(vec![project("p", &["a", "b", "c"])]);
+        app.switch_panel();
+        assert!(!app.archive_completed());
+        app.projects[0].todos[0].completed = true;
+        app.projects[0].todos[0].log_session(0, 60);
+        app.projects[0].todos[2].completed = true;
+        app.select_next();
+
+        assert!(app.archive_completed());
+        assert_eq!(app.projects[0].todos.len(), 1);
+        assert_eq!(app.projects[0].archived.len(), 2);
+        assert_eq!(app.get_current_todo().unwrap().title, "b");
+
+        // 归档从新到旧显示，第二项是最早归档的 a
+        app.open_archive();
+        app.select_archived(true);
+        assert!(app.unarchive_selected());
+        assert_eq!(app.projects[0].todos[1].title, "a");
+        assert_eq!(app.projects[0].todos[1].total_duration(), 60);
+        assert_eq!(app.archive_state.selected(), Some(0));
+    }
+
+    #[test]
+    fn sessions_are_edited_and_deleted() {
+        let mut app = App::with_projects(vec![project("p", &["a"])]);
+        app.switch_panel();
+        let todo = app.get_current_todo_mut().unwrap();
+        todo.log_session(1_000, 1_600);
+        todo.log_session(5_000, 5_060);
+        app.open_sessions();
+        assert_eq!(app.session_state.selected(), Some(1));
+
+        app.start_editing_session();
+        assert!(app.input_mode == InputMode::EditingSession);
+        app.input.set("bad");
+        assert!(!app.submit_input());
+        assert!(app.message.is_some());
+
+        // 改到第一条记录之前，光标跟着移动
+        app.input.set(report::format_session(0, 120));
+        assert!(app.submit_input());
+        assert_eq!(app.session_state.selected(), Some(0));
+        assert_eq!(app.get_current_todo().unwrap().total_duration(), 720);
+
+        assert!(app.delete_selected_session());
+        assert_eq!(app.get_current_todo().unwrap().total_duration(), 600);
+        assert!(app.delete_selected_session());
+        assert!(!app.delete_selected_session());
+        assert_eq!(app.session_state.selected(), None);
+    }
+
+    #[test]
+    fn time_is_logged_manually() {
+        let mut app = App::with_projects(vec![project("p", &["a"])]);
+        app.switch_panel();
+        app.start_logging_time();
+        app.input.set("forever");
+        assert!(!app.submit_input());
+        assert!(app.input_mode == InputMode::LoggingTime);
+
+        app.input.set("45m yesterday");
+        assert!(app.submit_input());
+        assert!(app.input_mode == InputMode::Normal);
+        let todo = app.get_current_todo().unwrap();
+        assert_eq!(todo.sessions.len(), 1);
+        assert_eq!(todo.total_duration(), 2700);
+    }
+
+    #[test]
+    fn pomodoro_times_the_todo_and_counts_completed_cycles() {
+        let mut app = App::with_projects(vec![project("p", &["a"])]);
+        app.switch_panel();
+        assert!(app.toggle_pomodoro());
+        assert!(app.get_current_todo().unwrap().is_working());
+
+        let (work_secs, break_secs) = app.config.pomodoro_secs();
+        let start = app.pomodoro.as_ref().unwrap().started;
+        assert!(!app.tick_pomodoro(start + 10));
+        assert!(app.tick_pomodoro(start + work_secs));
+        assert!(app.view == View::PomodoroNotice);
+        let todo = app.get_current_todo().unwrap();
+        assert!(!todo.is_working());
+        assert_eq!(todo.pomodoros, 1);
+
+        // 休息时没有计时也不会结束番茄钟
+        app.close_view();
+        assert!(!app.tick_pomodoro(start + work_secs + 1));
+        assert!(app.tick_pomodoro(start + work_secs + break_secs));
+        assert!(app.pomodoro.is_none());
+
+        // 工作时手动停止计时会结束番茄钟
+        app.toggle_pomodoro();
+        app.toggle_current_todo_timer();
+        assert!(!app.tick_pomodoro(now_secs()));
+        assert!(app.pomodoro.is_none());
+        let started = app.unsaved_events.iter();
+        let started = started.filter(|e| e.kind == EventKind::TimerStarted);
+        assert_eq!(started.count(), 2);
+
+        // 已完成的任务不能开始番茄钟
+        app.get_current_todo_mut().unwrap().completed = true;
+        assert!(!app.toggle_pomodoro());
+        assert!(app.pomodoro.is_none());
+        assert!(!app.get_current_todo().unwrap().is_working());
+        assert_eq!(app.message.as_ref().unwrap().text, COMPLETED_TIMER_WARNING);
+    }
+
+    #[test]
+    fn exports_never_overwrite_existing_files() {
+        let dir = std::env::temp_dir().join(format!("s_todo_export_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("README.md").to_string_lossy().into_owned();
+        std::fs::write(&path, "keep").unwrap();
+
+        let second = write_new_file(&path, "a").unwrap();
+        let third = write_new_file(&path, "b").unwrap();
+        assert!(second.ends_with("README (2).md"));
+        assert!(third.ends_with("README (3).md"));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "keep");
+        assert_eq!(std::fs::read_to_string(&third).unwrap(), "b");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn messages_are_logged_and_errors_stay_visible() {
+        let mut app = App::with_projects(vec![project("p", &["a"])]);
+        app.error("保存失败：磁盘已满");
+        app.info("已导出到 p.md");
+
+        // 较轻的提示不会盖住错误，但都会记录下来
+        let shown = app.message.as_ref().unwrap();
+        assert_eq!(shown.level, Level::Error);
+        assert_eq!(shown.text, "保存失败：磁盘已满");
+        let log: Vec<&str> = app.messages.iter().map(|m| m.text.as_str()).collect();
+        assert_eq!(log, vec!["已导出到 p.md", "保存失败：磁盘已满"]);
+
+        app.message = None;
+        app.info("已保存");
+        assert_eq!(app.message.as_ref().unwrap().level, Level::Info);
+    }
+
+    #[test]
+    fn calendar_moves_between_days_and_months() {
+        let mut app = App::with_projects(vec![Project::new("inbox".to_string())]);
+        app.open_calendar();
+        assert!(app.view == View::Calendar);
+        assert_eq!(app.calendar_day, today());
+
+        app.calendar_day = NaiveDate::from_ymd_opt(2026, 1, 31).unwrap();
+        app.shift_calendar_month(false);
+        assert_eq!(
+            app.calendar_day,
+            NaiveDate::from_ymd_opt(2026, 2, 28).unwrap()
+        );
+        app.move_calendar_day(1);
+        assert_eq!(
+            app.calendar_day,
+            NaiveDate::from_ymd_opt(2026, 3, 1).unwrap()
+        );
+        app.move_calendar_day(-7);
+        assert_eq!(
+            app.calendar_day,
+            NaiveDate::from_ymd_opt(2026, 2, 22).unwrap()
+        );
+        app.shift_calendar_month(true);
+        assert_eq!(
+            app.calendar_day,
+            NaiveDate::from_ymd_opt(2026, 1, 22).unwrap()
+        );
+    }
+}
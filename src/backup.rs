@@ -0,0 +1,117 @@
+use crate::storage;
+use chrono::{DateTime, Local};
+use std::{fs, io, path::Path, time::SystemTime};
+
+// 保留的备份数量
+pub const MAX_BACKUPS: usize = 5;
+
+// 一份备份文件
+pub struct Backup {
+    pub index: usize, // 1 为最新的备份
+    pub path: String,
+    pub modified: Option<SystemTime>,
+}
+
+impl Backup {
+    // 格式化备份时间
+    pub fn format_time(&self) -> String {
+        self.modified.map_or_else(
+            || "未知时间".to_string(),
+            |time| {
+                DateTime::<Local>::from(time)
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string()
+            },
+        )
+    }
+}
+
+fn backup_path(data_file: &str, index: usize) -> String {
+    format!("{}.bak.{}", data_file, index)
+}
+
+// 保存前轮换备份：data.json.bak.1 为上一次保存的内容，最多保留 MAX_BACKUPS 份
+pub fn rotate(data_file: &str) -> io::Result<()> {
+    if !Path::new(data_file).exists() {
+        return Ok(());
+    }
+
+    for index in (1..MAX_BACKUPS).rev() {
+        let from = backup_path(data_file, index);
+        if Path::new(&from).exists() {
+            fs::rename(&from, backup_path(data_file, index + 1))?;
+        }
+    }
+    fs::copy(data_file, backup_path(data_file, 1))?;
+    Ok(())
+}
+
+// 列出现有的备份，从新到旧排列
+pub fn list(data_file: &str) -> Vec<Backup> {
+    (1..=MAX_BACKUPS)
+        .filter_map(|index| {
+            let path = backup_path(data_file, index);
+            let metadata = fs::metadata(&path).ok()?;
+            Some(Backup {
+                index,
+                path,
+                modified: metadata.modified().ok(),
+            })
+        })
+        .collect()
+}
+
+// 用指定的备份覆盖数据文件，覆盖前先备份当前数据，以便撤销这次恢复
+pub fn restore(data_file: &str, index: usize) -> io::Result<()> {
+    let path = backup_path(data_file, index);
+    let content = fs::read(&path)?;
+    rotate(data_file)?;
+    storage::write_atomic(data_file, &content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_data_file(name: &str) -> String {
+        let dir =
+            std::env::temp_dir().join(format!("s_todo_backup_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("data.json").to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn rotation_keeps_newest_backups() {
+        let data_file = temp_data_file("rotate");
+        // 数据文件不存在时不产生备份
+        rotate(&data_file).unwrap();
+        assert!(list(&data_file).is_empty());
+
+        for i in 0..MAX_BACKUPS + 2 {
+            fs::write(&data_file, i.to_string()).unwrap();
+            rotate(&data_file).unwrap();
+        }
+
+        let backups = list(&data_file);
+        assert_eq!(backups.len(), MAX_BACKUPS);
+        assert_eq!(fs::read_to_string(&backups[0].path).unwrap(), "6");
+        assert_eq!(fs::read_to_string(&backups[4].path).unwrap(), "2");
+    }
+
+    #[test]
+    fn restore_backs_up_current_data() {
+        let data_file = temp_data_file("restore");
+        fs::write(&data_file, "old").unwrap();
+        rotate(&data_file).unwrap();
+        fs::write(&data_file, "new").unwrap();
+
+        restore(&data_file, 1).unwrap();
+        assert_eq!(fs::read_to_string(&data_file).unwrap(), "old");
+        assert_eq!(
+            fs::read_to_string(backup_path(&data_file, 1)).unwrap(),
+            "new"
+        );
+        assert!(restore(&data_file, 4).is_err());
+    }
+}
@@ -0,0 +1,660 @@
+use crate::config::CaldavConfig;
+use crate::ical;
+use crate::model::{base64, stable_hash, AppData, CaldavLink, Project, Todo, TrashItem, Trashed};
+use chrono::NaiveDate;
+
+// 查询任务列表中所有 VTODO 的请求
+const QUERY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<c:calendar-query xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:prop><d:getetag/><c:calendar-data/></d:prop>
+  <c:filter><c:comp-filter name="VCALENDAR"><c:comp-filter name="VTODO"/></c:comp-filter></c:filter>
+</c:calendar-query>"#;
+
+// 服务器上的一个任务
+pub struct RemoteTask {
+    pub href: String,
+    pub etag: String,
+    pub ics: String, // 完整的日历文件，更新时保留其他应用写入的属性
+    pub uid: String,
+    pub title: String,
+    pub completed: bool,
+    pub due_date: Option<NaiveDate>,
+    pub category: Option<String>, // 第一个分类，对应项目名
+}
+
+// 同步用到的服务器操作，测试中用内存中的实现代替
+pub trait Server {
+    fn list(&self) -> Result<Vec<RemoteTask>, String>;
+    // 上传任务，etag 为 None 时只创建不覆盖，返回新的 etag
+    fn put(&self, href: &str, ics: &str, etag: Option<&str>) -> Result<String, String>;
+    fn delete(&self, href: &str, etag: &str) -> Result<(), String>;
+}
+
+// 通过 HTTP 访问的 CalDAV 服务器
+pub struct HttpServer {
+    url: String,
+    auth: String,
+}
+
+impl HttpServer {
+    pub fn new(config: &CaldavConfig) -> Self {
+        let credentials = format!("{}:{}", config.username, config.password());
+        Self {
+            url: format!("{}/", config.url.trim_end_matches('/')),
+            auth: format!("Basic {}", base64(credentials.as_bytes())),
+        }
+    }
+
+    // 服务器返回的 href 通常只有路径，补全为完整地址
+    fn resolve(&self, href: &str) -> String {
+        if href.starts_with("http://") || href.starts_with("https://") {
+            return href.to_string();
+        }
+        let origin_end = self
+            .url
+            .find("://")
+            .and_then(|i| self.url[i + 3..].find('/').map(|j| i + 3 + j))
+            .unwrap_or(self.url.len());
+        format!("{}{}", &self.url[..origin_end], href)
+    }
+}
+
+fn http_error(err: ureq::Error) -> String {
+    match err {
+        ureq::Error::Status(code, response) => {
+            format!("服务器返回 {} {}", code, response.status_text())
+        }
+        ureq::Error::Transport(err) => format!("连接服务器失败：{}", err),
+    }
+}
+
+impl Server for HttpServer {
+    fn list(&self) -> Result<Vec<RemoteTask>, String> {
+        let body = ureq::request("REPORT", &self.url)
+            .set("Authorization", &self.auth)
+            .set("Depth", "1")
+            .set("Content-Type", "application/xml; charset=utf-8")
+            .send_string(QUERY)
+            .map_err(http_error)?
+            .into_string()
+            .map_err(|e| format!("读取服务器响应失败：{}", e))?;
+        Ok(parse_multistatus(&body)
+            .into_iter()
+            .filter_map(|(href, etag, ics)| RemoteTask::parse(self.resolve(&href), etag, ics))
+            .collect())
+    }
+
+    fn put(&self, href: &str, ics: &str, etag: Option<&str>) -> Result<String, String> {
+        let request = ureq::put(href)
+            .set("Authorization", &self.auth)
+            .set("Content-Type", "text/calendar; charset=utf-8");
+        let request = match etag {
+            Some(etag) => request.set("If-Match", etag),
+            None => request.set("If-None-Match", "*"),
+        };
+        let response = request.send_string(ics).map_err(http_error)?;
+        Ok(response.header("ETag").unwrap_or_default().to_string())
+    }
+
+    fn delete(&self, href: &str, etag: &str) -> Result<(), String> {
+        ureq::delete(href)
+            .set("Authorization", &self.auth)
+            .set("If-Match", etag)
+            .call()
+            .map_err(http_error)?;
+        Ok(())
+    }
+}
+
+// 找出所有本地名称（忽略命名空间前缀）为 name 的元素的内容
+fn elements<'a>(xml: &'a str, name: &str) -> Vec<&'a str> {
+    let mut found = Vec::new();
+    let mut rest = xml;
+    while let Some(open) = rest.find('<') {
+        rest = &rest[open + 1..];
+        let tag_end = rest.find('>').unwrap_or(rest.len());
+        let tag = &rest[..tag_end];
+        let full_name = tag
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or("");
+        let local_name = full_name.rsplit(':').next().unwrap_or("");
+        if local_name != name || tag.starts_with('/') {
+            continue;
+        }
+        rest = &rest[(tag_end + 1).min(rest.len())..];
+        if tag.ends_with('/') {
+            found.push("");
+            continue;
+        }
+        let close = format!("</{}>", full_name);
+        let end = rest.find(&close).unwrap_or(rest.len());
+        found.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+    found
+}
+
+// 还原 XML 中的转义字符和 CDATA
+fn xml_text(text: &str) -> String {
+    let text = text.trim();
+    if let Some(inner) = text
+        .strip_prefix("<![CDATA[")
+        .and_then(|t| t.strip_suffix("]]>"))
+    {
+        return inner.to_string();
+    }
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#13;", "\r")
+        .replace("&#xD;", "\r")
+        .replace("&amp;", "&")
+}
+
+// 解析 REPORT 返回的 multistatus，返回每个任务的 (href, etag, 日历数据)
+pub fn parse_multistatus(xml: &str) -> Vec<(String, String, String)> {
+    elements(xml, "response")
+        .into_iter()
+        .filter_map(|response| {
+            let first = |name| elements(response, name).first().map(|text| xml_text(text));
+            Some((first("href")?, first("getetag")?, first("calendar-data")?))
+        })
+        .collect()
+}
+
+// 展开折行，返回各行
+fn unfold(ics: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in ics.split('\n').map(|l| l.trim_end_matches('\r')) {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines.retain(|line| !line.is_empty());
+    lines
+}
+
+// 属性名（不含参数）和值
+fn property(line: &str) -> Option<(&str, &str)> {
+    let (name, value) = line.split_once(':')?;
+    Some((name.split(';').next().unwrap_or(name), value))
+}
+
+fn unescape(text: &str) -> String {
+    let mut result = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => result.push('\n'),
+            Some(other) => result.push(other),
+            None => {}
+        }
+    }
+    result
+}
+
+// 多个值中的第一个，值之间用没有转义的逗号分隔
+fn first_value(value: &str) -> &str {
+    let mut escaped = false;
+    for (i, c) in value.char_indices() {
+        match c {
+            ',' if !escaped => return &value[..i],
+            '\\' => escaped = !escaped,
+            _ => escaped = false,
+        }
+    }
+    value
+}
+
+impl RemoteTask {
+    // 从日历数据中取出第一个 VTODO 的字段，没有 VTODO 或 UID 时返回 None
+    pub fn parse(href: String, etag: String, ics: String) -> Option<RemoteTask> {
+        let lines = unfold(&ics);
+        let mut in_todo = false;
+        let (mut uid, mut title, mut completed, mut due_date, mut category) =
+            (None, String::new(), false, None, None);
+        for line in &lines {
+            match line.as_str() {
+                "BEGIN:VTODO" => in_todo = true,
+                "END:VTODO" => break,
+                _ if in_todo => match property(line) {
+                    Some(("UID", value)) => uid = Some(value.to_string()),
+                    Some(("SUMMARY", value)) => title = unescape(value),
+                    Some(("STATUS", value)) => completed = value == "COMPLETED",
+                    Some(("DUE", value)) => {
+                        due_date = value
+                            .get(..8)
+                            .and_then(|date| NaiveDate::parse_from_str(date, "%Y%m%d").ok())
+                    }
+                    Some(("CATEGORIES", value)) => {
+                        category = Some(unescape(first_value(value))).filter(|c| !c.is_empty());
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+        Some(RemoteTask {
+            href,
+            etag,
+            uid: uid?,
+            title,
+            completed,
+            due_date,
+            category,
+            ics,
+        })
+    }
+}
+
+// 同步的字段的哈希，和上次同步时不同表示本地有修改
+fn synced_hash(todo: &Todo) -> u64 {
+    stable_hash(&format!(
+        "{}\0{}\0{:?}",
+        todo.title, todo.completed, todo.due_date
+    ))
+}
+
+// 把本地的标题、完成状态和截止日期写入服务器上的日历数据，其余属性保持不变
+fn update_ics(ics: &str, todo: &Todo, now: u64) -> String {
+    const REPLACED: [&str; 6] = [
+        "SUMMARY",
+        "STATUS",
+        "DUE",
+        "PERCENT-COMPLETE",
+        "COMPLETED",
+        "DTSTAMP",
+    ];
+    let mut lines = Vec::new();
+    let mut in_todo = false;
+    for line in unfold(ics) {
+        match line.as_str() {
+            "BEGIN:VTODO" => in_todo = true,
+            "END:VTODO" if in_todo => {
+                in_todo = false;
+                lines.push(format!("DTSTAMP:{}", ical::format_utc(now)));
+                lines.push(format!("SUMMARY:{}", ical::escape(&todo.title)));
+                lines.push(format!("STATUS:{}", ical::status_value(todo)));
+                if let Some(due) = todo.due_date {
+                    lines.push(format!("DUE;VALUE=DATE:{}", due.format("%Y%m%d")));
+                }
+                if todo.completed {
+                    lines.push("PERCENT-COMPLETE:100".to_string());
+                    lines.push(format!("COMPLETED:{}", ical::format_utc(now)));
+                }
+            }
+            _ if in_todo && property(&line).is_some_and(|(name, _)| REPLACED.contains(&name)) => {
+                continue
+            }
+            _ => {}
+        }
+        lines.push(line);
+    }
+    lines.iter().map(|line| ical::fold(line)).collect()
+}
+
+// 用服务器上的内容更新本地 todo
+fn apply_remote(todo: &mut Todo, remote: &RemoteTask) {
+    todo.title = remote.title.clone();
+    todo.due_date = remote.due_date;
+    if remote.completed != todo.completed {
+        // 和本地完成一样先结束计时，重复规则不在这里处理，截止日期以服务器为准
+        if remote.completed && todo.is_working() {
+            todo.end_work();
+        }
+        todo.set_completed(remote.completed);
+        todo.doing = false;
+    }
+}
+
+fn link(remote: &RemoteTask, etag: String, todo: &Todo) -> CaldavLink {
+    CaldavLink {
+        uid: remote.uid.clone(),
+        href: remote.href.clone(),
+        etag,
+        synced: synced_hash(todo),
+    }
+}
+
+// 一次同步的结果
+#[derive(Default, PartialEq, Debug)]
+pub struct SyncSummary {
+    pub pulled: usize,  // 用服务器上的修改更新的 todo
+    pub pushed: usize,  // 上传到服务器的修改和新任务
+    pub created: usize, // 从服务器新增到本地的 todo
+    pub deleted: usize, // 两边删除的任务
+}
+
+impl SyncSummary {
+    pub fn message(&self) -> String {
+        format!(
+            "同步完成：下载修改 {}，上传 {}，新增 {}，删除 {}",
+            self.pulled, self.pushed, self.created, self.deleted
+        )
+    }
+}
+
+// 双向同步所有项目中的 todo（不包括归档的），两边都有修改时以服务器为准
+// 服务器上删除的任务在本地移到回收站，本地删除到回收站的任务在服务器上删除
+pub fn sync(
+    data: &mut AppData,
+    server: &impl Server,
+    base_url: &str,
+    default_project: &str,
+    now: u64,
+) -> Result<SyncSummary, String> {
+    let remote = server.list()?;
+    let mut summary = SyncSummary::default();
+    let find = |uid: &str| remote.iter().find(|task| task.uid == uid);
+    // 本地已有对应 todo 或已在本地删除的任务
+    let mut known: Vec<String> = Vec::new();
+
+    // 本地删除的任务在服务器上删除
+    for item in &mut data.trash {
+        let todos: Vec<&mut Todo> = match &mut item.item {
+            Trashed::Project(project) => project.todos.iter_mut().collect(),
+            Trashed::Todo { todo, .. } => vec![todo],
+        };
+        for todo in todos {
+            if let Some(task) = todo.caldav.as_ref().and_then(|link| find(&link.uid)) {
+                server.delete(&task.href, &task.etag)?;
+                known.push(task.uid.clone());
+                summary.deleted += 1;
+            }
+            todo.caldav = None;
+        }
+    }
+
+    // 已经同步过的 todo：按修改的一方更新，服务器上已删除的移到回收站
+    for project in &mut data.projects {
+        let mut index = 0;
+        while index < project.todos.len() {
+            let todo = &mut project.todos[index];
+            let Some(old) = todo.caldav.clone() else {
+                index += 1;
+                continue;
+            };
+            match find(&old.uid) {
+                None => {
+                    let todo = project.todos.remove(index);
+                    let item = Trashed::Todo {
+                        project: project.name.clone(),
+                        todo,
+                    };
+                    data.trash.push(TrashItem::new(item, now));
+                    summary.deleted += 1;
+                    continue;
+                }
+                Some(task) if task.etag != old.etag => {
+                    apply_remote(todo, task);
+                    todo.caldav = Some(Box::new(link(task, task.etag.clone(), todo)));
+                    summary.pulled += 1;
+                }
+                Some(task) if synced_hash(todo) != old.synced => {
+                    let ics = update_ics(&task.ics, todo, now);
+                    let etag = server.put(&task.href, &ics, Some(&task.etag))?;
+                    todo.caldav = Some(Box::new(link(task, etag, todo)));
+                    summary.pushed += 1;
+                }
+                Some(_) => {}
+            }
+            index += 1;
+        }
+    }
+
+    // 本地新的 todo 上传到服务器，归档项目中的 todo 不再同步，但也不会重新下载
+    known.extend(
+        data.projects
+            .iter()
+            .chain(&data.archived_projects)
+            .flat_map(|p| p.todos.iter().chain(&p.archived))
+            .filter_map(|todo| todo.caldav.as_ref().map(|link| link.uid.clone())),
+    );
+    for project in &mut data.projects {
+        for todo in project.todos.iter_mut().filter(|t| t.caldav.is_none()) {
+            let uid = ical::uid(&project.name, todo);
+            let href = format!("{}/{}.ics", base_url.trim_end_matches('/'), uid);
+            let ics = ical::calendar(ical::vtodo(&project.name, todo, &uid, now));
+            let etag = server.put(&href, &ics, None)?;
+            todo.caldav = Some(Box::new(CaldavLink {
+                uid,
+                href,
+                etag,
+                synced: synced_hash(todo),
+            }));
+            summary.pushed += 1;
+        }
+    }
+
+    // 服务器上新的任务加入本地，按第一个分类放入同名项目
+    for task in remote.iter().filter(|task| !known.contains(&task.uid)) {
+        let mut todo = Todo {
+            created_at: now,
+            ..Todo::new(String::new())
+        };
+        apply_remote(&mut todo, task);
+        todo.caldav = Some(Box::new(link(task, task.etag.clone(), &todo)));
+        let name = task.category.as_deref().unwrap_or(default_project);
+        match data.projects.iter_mut().find(|p| p.name == name) {
+            Some(project) => project.todos.push(todo),
+            None => data.projects.push(Project {
+                todos: vec![todo],
+                ..Project::new(name.to_string())
+            }),
+        }
+        summary.created += 1;
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::DATA_VERSION;
+    use std::cell::RefCell;
+
+    // 内存中的服务器，每次上传后 etag 加一
+    #[derive(Default)]
+    struct FakeServer {
+        tasks: RefCell<Vec<(String, String, String)>>, // (href, etag, 日历数据)
+        version: RefCell<u32>,
+    }
+
+    impl FakeServer {
+        fn next_etag(&self) -> String {
+            *self.version.borrow_mut() += 1;
+            format!("\"{}\"", self.version.borrow())
+        }
+
+        // 模拟其他应用修改服务器上的任务
+        fn edit(&self, uid: &str, from: &str, to: &str) {
+            let etag = self.next_etag();
+            let mut tasks = self.tasks.borrow_mut();
+            let task = tasks.iter_mut().find(|t| t.2.contains(uid)).unwrap();
+            task.1 = etag;
+            task.2 = task.2.replace(from, to);
+        }
+    }
+
+    impl Server for FakeServer {
+        fn list(&self) -> Result<Vec<RemoteTask>, String> {
+            Ok(self
+                .tasks
+                .borrow()
+                .iter()
+                .filter_map(|(href, etag, ics)| {
+                    RemoteTask::parse(href.clone(), etag.clone(), ics.clone())
+                })
+                .collect())
+        }
+
+        fn put(&self, href: &str, ics: &str, etag: Option<&str>) -> Result<String, String> {
+            let new_etag = self.next_etag();
+            let mut tasks = self.tasks.borrow_mut();
+            match (tasks.iter_mut().find(|t| t.0 == href), etag) {
+                (Some(task), Some(etag)) if task.1 == etag => {
+                    *task = (href.to_string(), new_etag.clone(), ics.to_string())
+                }
+                (None, None) => tasks.push((href.to_string(), new_etag.clone(), ics.to_string())),
+                _ => return Err("412".to_string()),
+            }
+            Ok(new_etag)
+        }
+
+        fn delete(&self, href: &str, _etag: &str) -> Result<(), String> {
+            self.tasks.borrow_mut().retain(|t| t.0 != href);
+            Ok(())
+        }
+    }
+
+    fn data(projects: Vec<Project>) -> AppData {
+        AppData {
+            version: DATA_VERSION,
+            saved_at: 0,
+            projects,
+            trash: vec![],
+            archived_projects: vec![],
+            templates: vec![],
+        }
+    }
+
+    fn sync_with(data: &mut AppData, server: &FakeServer) -> SyncSummary {
+        sync(
+            data,
+            server,
+            "https://dav.example.com/tasks/",
+            "CalDAV",
+            1_000,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn changes_flow_both_ways() {
+        let server = FakeServer::default();
+        let mut local = data(vec![Project {
+            todos: vec![Todo::new("写周报".to_string())],
+            ..Project::new("工作".to_string())
+        }]);
+        let summary = sync_with(&mut local, &server);
+        assert_eq!(summary.pushed, 1);
+        assert!(server.tasks.borrow()[0]
+            .0
+            .starts_with("https://dav.example.com/tasks/"));
+
+        // 另一台设备上的 s_todo 第一次同步时得到同样的项目和 todo
+        let mut other = data(vec![]);
+        assert_eq!(sync_with(&mut other, &server).created, 1);
+        assert_eq!(other.projects[0].name, "工作");
+        assert_eq!(other.projects[0].todos[0].title, "写周报");
+
+        // 本地完成后上传，服务器上的修改下载到本地
+        local.projects[0].todos[0].completed = true;
+        assert_eq!(sync_with(&mut local, &server).pushed, 1);
+        assert!(server.tasks.borrow()[0].2.contains("STATUS:COMPLETED"));
+        other.projects[0].todos[0].start_work();
+        assert_eq!(sync_with(&mut other, &server).pulled, 1);
+        assert!(other.projects[0].todos[0].completed);
+        assert!(!other.projects[0].todos[0].is_working());
+        assert_eq!(other.projects[0].todos[0].sessions.len(), 1);
+
+        let uid = local.projects[0].todos[0].caldav.clone().unwrap().uid;
+        server.edit(
+            &uid,
+            "SUMMARY:写周报",
+            "SUMMARY:写月报\r\nDUE:20260304T090000Z",
+        );
+        assert_eq!(sync_with(&mut local, &server).pulled, 1);
+        let todo = &local.projects[0].todos[0];
+        assert_eq!(todo.title, "写月报");
+        assert_eq!(todo.due_date, NaiveDate::from_ymd_opt(2026, 3, 4));
+        assert_eq!(sync_with(&mut local, &server), SyncSummary::default());
+    }
+
+    #[test]
+    fn deletions_are_synced() {
+        let server = FakeServer::default();
+        let mut local = data(vec![Project {
+            todos: vec![Todo::new("a".to_string()), Todo::new("b".to_string())],
+            ..Project::new("p".to_string())
+        }]);
+        sync_with(&mut local, &server);
+
+        // 服务器上删除的任务移到本地回收站
+        let href = server.tasks.borrow()[0].0.clone();
+        server.delete(&href, "").unwrap();
+        assert_eq!(sync_with(&mut local, &server).deleted, 1);
+        assert_eq!(local.projects[0].todos.len(), 1);
+        assert_eq!(local.trash.len(), 1);
+
+        // 本地删除到回收站的任务在服务器上删除，不会再下载回来
+        let todo = local.projects[0].todos.remove(0);
+        let item = Trashed::Todo {
+            project: "p".to_string(),
+            todo,
+        };
+        local.trash.push(TrashItem::new(item, 0));
+        assert_eq!(sync_with(&mut local, &server).deleted, 1);
+        assert!(server.tasks.borrow().is_empty());
+        assert!(local.projects[0].todos.is_empty());
+    }
+
+    #[test]
+    fn archived_projects_are_not_downloaded_again() {
+        let server = FakeServer::default();
+        let mut local = data(vec![Project {
+            todos: vec![Todo::new("a".to_string())],
+            ..Project::new("p".to_string())
+        }]);
+        sync_with(&mut local, &server);
+
+        let project = local.projects.remove(0);
+        local.archived_projects.push(project);
+        assert_eq!(sync_with(&mut local, &server), SyncSummary::default());
+        assert!(local.projects.is_empty());
+        assert_eq!(server.tasks.borrow().len(), 1);
+    }
+
+    #[test]
+    fn multistatus_and_vtodo_are_parsed() {
+        let xml = r#"<?xml version="1.0"?>
+<D:multistatus xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <D:response>
+    <D:href>/tasks/a.ics</D:href>
+    <D:propstat><D:prop>
+      <D:getetag>&quot;1&quot;</D:getetag>
+      <C:calendar-data>BEGIN:VCALENDAR&#13;
+BEGIN:VTODO&#13;
+UID:a&#13;
+SUMMARY:买菜\, 做饭&#13;
+CATEGORIES:家\,里,其他&#13;
+STATUS:COMPLETED&#13;
+DUE;VALUE=DATE:20260304&#13;
+END:VTODO&#13;
+END:VCALENDAR&#13;
+</C:calendar-data>
+    </D:prop></D:propstat>
+  </D:response>
+</D:multistatus>"#;
+        let responses = parse_multistatus(xml);
+        assert_eq!(responses.len(), 1);
+        let (href, etag, ics) = responses.into_iter().next().unwrap();
+        assert_eq!((href.as_str(), etag.as_str()), ("/tasks/a.ics", "\"1\""));
+
+        let task = RemoteTask::parse(href, etag, ics).unwrap();
+        assert_eq!(task.uid, "a");
+        assert_eq!(task.title, "买菜, 做饭");
+        assert_eq!(task.category.as_deref(), Some("家,里"));
+        assert!(task.completed);
+        assert_eq!(task.due_date, NaiveDate::from_ymd_opt(2026, 3, 4));
+        assert_eq!(base64(b"user:pass"), "dXNlcjpwYXNz");
+    }
+}
@@ -0,0 +1,622 @@
+use crate::activity::{self, Event, EventKind};
+use crate::backup;
+use crate::caldav;
+use crate::config::Config;
+use crate::github;
+use crate::ical;
+use crate::model::{
+    format_duration, now_secs, parse_quick_add, today, AppData, DurationUnit, Project, Todo,
+};
+use crate::report;
+use crate::state::UiState;
+use crate::storage;
+use crate::taskwarrior;
+use crate::theme::Icons;
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+
+// 命令行参数，不带子命令时启动终端界面
+#[derive(Parser)]
+#[command(name = env!("CARGO_PKG_NAME"), about = "终端 Todo 管理和时间跟踪，不带参数时启动终端界面")]
+pub struct Cli {
+    #[arg(
+        long,
+        global = true,
+        value_name = "文件",
+        help = "使用指定的数据文件，代替系统数据目录中的 data.json"
+    )]
+    pub data_file: Option<String>,
+    #[arg(
+        short,
+        long,
+        global = true,
+        value_name = "名称",
+        conflicts_with = "data_file",
+        help = "使用指定的工作区，每个工作区有自己的数据文件、界面状态和设置"
+    )]
+    pub workspace: Option<String>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    #[command(about = "添加 Todo，标题中的 #标签 会被识别")]
+    Add {
+        title: String,
+        #[arg(short, long, help = "项目名，不存在时自动创建，默认为界面中选中的项目")]
+        project: Option<String>,
+    },
+    #[command(about = "列出所有项目和未完成的 Todo，前面的数字是 Todo 的编号")]
+    List,
+    #[command(about = "把指定编号的 Todo 标记为完成")]
+    Done { id: usize },
+    #[command(about = "开始为指定编号的 Todo 计时")]
+    Start { id: usize },
+    #[command(about = "结束指定编号的 Todo 的计时")]
+    Stop { id: usize },
+    #[command(about = "在一行中输出正在计时的 Todo 和本次计时的时长，没有计时时输出空行")]
+    Current,
+    #[command(about = "把所有计时记录导出为 CSV 文件")]
+    Export { path: String },
+    #[command(about = "把项目导出为 Markdown，不指定文件时直接输出")]
+    Markdown {
+        project: String,
+        path: Option<String>,
+    },
+    #[command(about = "把有截止日期的 Todo 导出为日历文件")]
+    Ics { path: String },
+    #[command(about = "与 Taskwarrior 互相导入导出")]
+    Taskwarrior {
+        #[command(subcommand)]
+        action: Transfer,
+    },
+    #[command(about = "与配置的 CalDAV 服务器双向同步")]
+    Sync,
+    #[command(about = "导入 GitHub issue")]
+    Github {
+        #[command(subcommand)]
+        action: GithubAction,
+    },
+    #[command(about = "输出活动记录（添加、完成、计时等），指定文件时导出为 CSV")]
+    Activity { path: Option<String> },
+    #[command(about = "列出备份，或用指定编号的备份恢复数据")]
+    Restore { index: Option<usize> },
+    #[command(
+        about = "输出 shell 补全脚本，如 std completions bash > ~/.local/share/bash-completion/completions/std"
+    )]
+    Completions { shell: Shell },
+    // 补全脚本调用，每行输出一个项目名
+    #[command(hide = true)]
+    Projects,
+}
+
+#[derive(Subcommand)]
+pub enum Transfer {
+    #[command(about = "导入 task export 输出的 JSON")]
+    Import { path: String },
+    #[command(about = "导出为 task import 可以读取的 JSON")]
+    Export { path: String },
+}
+
+#[derive(Subcommand)]
+pub enum GithubAction {
+    #[command(about = "把仓库中打开的 issue 导入到项目")]
+    Import { repo: String, project: String },
+    #[command(about = "按 issue 的状态更新导入的 Todo")]
+    Refresh,
+}
+
+// 执行命令行子命令，不进入终端界面，返回要输出的内容
+pub fn run(command: Command) -> Result<String, String> {
+    match command {
+        Command::Add { title, project } => {
+            let mut app_data = storage::load_data()?;
+            let project = project.unwrap_or_else(|| default_project(&app_data));
+            let (project, title) = (project.trim(), title.trim());
+            if project.is_empty() || parse_quick_add(title, today()).title.is_empty() {
+                return Err("名称不能为空".to_string());
+            }
+
+            let created = add_todo(&mut app_data, project, title)?;
+            let parsed = parse_quick_add(title, today()).title;
+            let event = Event::new(EventKind::Created, project, &parsed);
+            save_with_events(&app_data, &[event])?;
+
+            Ok(if created {
+                format!("已创建项目「{}」并添加 Todo「{}」", project, title)
+            } else {
+                format!("已向项目「{}」添加 Todo「{}」", project, title)
+            })
+        }
+        Command::List => {
+            let config = Config::load()?;
+            let app_data = storage::load_data()?;
+            Ok(list(&app_data, config.duration_unit, config.icons()))
+        }
+        Command::Done { id } => {
+            let mut app_data = storage::load_data()?;
+            let project = project_of(&app_data, id);
+            let todo = todo_by_id(&mut app_data, id)?;
+            if todo.completed {
+                return Ok(format!("「{}」已经完成", todo.title));
+            }
+            let message = match todo.complete(today()) {
+                Some(next) => format!(
+                    "已完成「{}」，下一次安排在 {}",
+                    todo.title,
+                    next.format("%Y-%m-%d")
+                ),
+                None => format!("已完成「{}」", todo.title),
+            };
+            let event = Event::new(EventKind::Completed, &project, &todo.title);
+            save_with_events(&app_data, &[event])?;
+            Ok(message)
+        }
+        Command::Start { id } => {
+            let config = Config::load()?;
+            let mut app_data = storage::load_data()?;
+            let todo = todo_by_id(&mut app_data, id)?;
+            if todo.completed {
+                return Err(format!("「{}」已经完成，不能计时", todo.title));
+            }
+            if todo.is_working() {
+                return Ok(format!("「{}」已经在计时", todo.title));
+            }
+            // 和界面中一样，只允许一个计时时先结束其他计时
+            let mut events = vec![];
+            if config.single_timer {
+                for project in &mut app_data.projects {
+                    for todo in project.todos.iter_mut().filter(|t| t.is_working()) {
+                        todo.end_work();
+                        events.push(Event::new(
+                            EventKind::TimerStopped,
+                            &project.name,
+                            &todo.title,
+                        ));
+                    }
+                }
+            }
+            let project = project_of(&app_data, id);
+            let todo = todo_by_id(&mut app_data, id)?;
+            todo.start_work();
+            let message = format!("开始为「{}」计时", todo.title);
+            events.push(Event::new(EventKind::TimerStarted, &project, &todo.title));
+            save_with_events(&app_data, &events)?;
+            Ok(message)
+        }
+        Command::Stop { id } => {
+            let unit = Config::load()?.duration_unit;
+            let mut app_data = storage::load_data()?;
+            let project = project_of(&app_data, id);
+            let todo = todo_by_id(&mut app_data, id)?;
+            if !todo.is_working() {
+                return Ok(format!("「{}」没有在计时", todo.title));
+            }
+            let elapsed = todo.session_elapsed(now_secs());
+            todo.end_work();
+            let message = format!(
+                "已结束「{}」的计时，本次 {}",
+                todo.title,
+                format_duration(elapsed, unit)
+            );
+            let event = Event::new(EventKind::TimerStopped, &project, &todo.title);
+            save_with_events(&app_data, &[event])?;
+            Ok(message)
+        }
+        Command::Current => {
+            let config = Config::load()?;
+            let app_data = storage::load_data()?;
+            Ok(current(
+                &app_data,
+                config.duration_unit,
+                config.icons(),
+                now_secs(),
+            ))
+        }
+        Command::Export { path } => {
+            let app_data = storage::load_data()?;
+            std::fs::write(&path, report::sessions_csv(&app_data.projects))
+                .map_err(|e| format!("写入 {} 失败：{}", path, e))?;
+            Ok(format!("已把计时记录导出到 {}", path))
+        }
+        Command::Ics { path } => {
+            let app_data = storage::load_data()?;
+            std::fs::write(&path, ical::export(&app_data.projects, now_secs()))
+                .map_err(|e| format!("写入 {} 失败：{}", path, e))?;
+            Ok(format!("已把有截止日期的 Todo 导出到 {}", path))
+        }
+        Command::Sync => {
+            let config = Config::load()?
+                .caldav
+                .ok_or_else(|| "未配置 CalDAV，请在 config.json 中设置 caldav".to_string())?;
+            let mut app_data = storage::load_data()?;
+            let server = caldav::HttpServer::new(&config);
+            let summary = caldav::sync(
+                &mut app_data,
+                &server,
+                &config.url,
+                &config.project,
+                now_secs(),
+            )?;
+            storage::write_data(&app_data, true).map_err(|e| format!("保存失败：{}", e))?;
+            Ok(summary.message())
+        }
+        Command::Github {
+            action: GithubAction::Import { repo, project },
+        } => {
+            let client = github::Client::new(Config::load()?.github_token.as_deref());
+            let issues = client.open_issues(&repo)?;
+            let mut app_data = storage::load_data()?;
+            let project = project.trim();
+            let index = match app_data
+                .projects
+                .iter()
+                .position(|p| p.name.to_lowercase() == project.to_lowercase())
+            {
+                Some(index) => index,
+                None => {
+                    app_data.projects.push(Project::new(project.to_string()));
+                    app_data.projects.len() - 1
+                }
+            };
+            let imported = github::import_issues(&mut app_data.projects[index], &repo, &issues);
+            storage::write_data(&app_data, true).map_err(|e| format!("保存失败：{}", e))?;
+            Ok(format!(
+                "已从 {} 导入 {} 个 issue（共 {} 个打开的 issue）",
+                repo,
+                imported,
+                issues.len()
+            ))
+        }
+        Command::Github {
+            action: GithubAction::Refresh,
+        } => {
+            let client = github::Client::new(Config::load()?.github_token.as_deref());
+            let mut app_data = storage::load_data()?;
+            let mut changed = 0;
+            for todo in app_data
+                .projects
+                .iter_mut()
+                .flat_map(|p| p.todos.iter_mut())
+            {
+                let Some((repo, number)) = github::issue_ref(todo).map(|(r, n)| (r.to_string(), n))
+                else {
+                    continue;
+                };
+                let issue = client.issue(&repo, number)?;
+                if github::apply_state(todo, &issue) {
+                    changed += 1;
+                }
+            }
+            storage::write_data(&app_data, true).map_err(|e| format!("保存失败：{}", e))?;
+            Ok(format!("已更新 {} 个 Todo 的完成状态", changed))
+        }
+        Command::Taskwarrior {
+            action: Transfer::Import { path },
+        } => {
+            let json =
+                std::fs::read_to_string(&path).map_err(|e| format!("读取 {} 失败：{}", path, e))?;
+            let mut app_data = storage::load_data()?;
+            let imported = taskwarrior::import(&mut app_data.projects, &json)?;
+            storage::write_data(&app_data, true).map_err(|e| format!("保存失败：{}", e))?;
+            Ok(format!("已从 Taskwarrior 导入 {} 个 Todo", imported))
+        }
+        Command::Taskwarrior {
+            action: Transfer::Export { path },
+        } => {
+            let app_data = storage::load_data()?;
+            std::fs::write(&path, taskwarrior::export(&app_data.projects))
+                .map_err(|e| format!("写入 {} 失败：{}", path, e))?;
+            Ok(format!("已导出到 {}，可以用 task import 导入", path))
+        }
+        Command::Markdown { project, path } => {
+            let app_data = storage::load_data()?;
+            // 归档的项目也可以导出
+            let project = app_data
+                .projects
+                .iter()
+                .chain(&app_data.archived_projects)
+                .find(|p| p.name.to_lowercase() == project.trim().to_lowercase())
+                .ok_or_else(|| format!("没有名为「{}」的项目", project))?;
+            let markdown = report::project_markdown(project, Config::load()?.duration_unit);
+            match path {
+                Some(path) => {
+                    std::fs::write(&path, markdown)
+                        .map_err(|e| format!("写入 {} 失败：{}", path, e))?;
+                    Ok(format!("已把项目「{}」导出到 {}", project.name, path))
+                }
+                None => Ok(markdown),
+            }
+        }
+        Command::Activity { path } => {
+            let events = activity::load(&activity::log_file_path());
+            match path {
+                Some(path) => {
+                    std::fs::write(&path, activity::csv(&events))
+                        .map_err(|e| format!("写入 {} 失败：{}", path, e))?;
+                    Ok(format!("已把 {} 条活动记录导出到 {}", events.len(), path))
+                }
+                None => Ok(events
+                    .iter()
+                    .map(Event::format)
+                    .collect::<Vec<_>>()
+                    .join("\n")),
+            }
+        }
+        Command::Completions { shell } => Ok(completions(shell)),
+        Command::Projects => Ok(storage::load_data()?
+            .projects
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect::<Vec<_>>()
+            .join("\n")),
+        Command::Restore { index: None } => {
+            let backups = backup::list(&storage::data_file_path());
+            if backups.is_empty() {
+                return Ok("没有可用的备份".to_string());
+            }
+
+            let mut lines = vec!["可用的备份（编号越小越新）：".to_string()];
+            for backup in backups {
+                lines.push(format!("  {}  {}", backup.index, backup.format_time()));
+            }
+            lines.push(format!(
+                "使用 {} restore <编号> 恢复",
+                env!("CARGO_PKG_NAME")
+            ));
+            Ok(lines.join("\n"))
+        }
+        Command::Restore { index: Some(index) } => {
+            backup::restore(&storage::data_file_path(), index)
+                .map_err(|e| format!("恢复备份 {} 失败：{}", index, e))?;
+            Ok(format!(
+                "已从备份 {} 恢复数据，恢复前的数据已备份为 1 号",
+                index
+            ))
+        }
+    }
+}
+
+// bash 补全：-p/--project 之后和 markdown 的第一个参数补全项目名，其余交给生成的补全函数
+const BASH_PROJECTS: &str = r#"
+_BIN_projects() {
+    local cur="${COMP_WORDS[COMP_CWORD]}" prev="${COMP_WORDS[COMP_CWORD-1]}"
+    if [[ "$prev" == "-p" || "$prev" == "--project" ]] \
+        || [[ "${COMP_WORDS[1]}" == "markdown" && $COMP_CWORD -eq 2 ]]; then
+        local IFS=$'\n'
+        COMPREPLY=($(compgen -W "$(BIN projects 2>/dev/null)" -- "$cur"))
+        return 0
+    fi
+    _BIN "$@"
+}
+complete -F _BIN_projects -o bashdefault -o default BIN
+"#;
+
+// fish 补全：同样的位置补全项目名
+const FISH_PROJECTS: &str = r#"
+complete -c BIN -n "__fish_seen_subcommand_from add" -s p -l project -xa "(BIN projects 2>/dev/null)"
+complete -c BIN -n "__fish_seen_subcommand_from markdown" -a "(BIN projects 2>/dev/null)"
+"#;
+
+// 生成补全脚本，bash 和 fish 还会补全数据文件中的项目名
+pub fn completions(shell: Shell) -> String {
+    let mut command = Cli::command();
+    let bin = command.get_name().to_string();
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut command, &bin, &mut script);
+    let mut script = String::from_utf8_lossy(&script).into_owned();
+    match shell {
+        Shell::Bash => script.push_str(&BASH_PROJECTS.replace("BIN", &bin)),
+        Shell::Fish => script.push_str(&FISH_PROJECTS.replace("BIN", &bin)),
+        _ => {}
+    }
+    script.trim_end().to_string()
+}
+
+// 不指定项目时添加到界面中选中的项目，没有项目时新建「收件箱」
+fn default_project(app_data: &AppData) -> String {
+    UiState::load()
+        .project
+        .and_then(|index| app_data.projects.get(index))
+        .or(app_data.projects.first())
+        .map_or_else(|| "收件箱".to_string(), |project| project.name.clone())
+}
+
+// 按 list 中显示的编号查找 todo，编号按所有项目中 todo 的顺序从 1 开始
+pub fn todo_by_id(app_data: &mut AppData, id: usize) -> Result<&mut Todo, String> {
+    app_data
+        .projects
+        .iter_mut()
+        .flat_map(|p| &mut p.todos)
+        .nth(id.wrapping_sub(1))
+        .ok_or_else(|| format!("没有编号为 {} 的 Todo", id))
+}
+
+// 编号对应的 todo 所在项目的名称，编号无效时为空
+fn project_of(app_data: &AppData, id: usize) -> String {
+    app_data
+        .projects
+        .iter()
+        .flat_map(|p| p.todos.iter().map(|_| p.name.as_str()))
+        .nth(id.wrapping_sub(1))
+        .unwrap_or_default()
+        .to_string()
+}
+
+// 保存数据，成功后把这次的活动追加到活动记录
+fn save_with_events(app_data: &AppData, events: &[Event]) -> Result<(), String> {
+    storage::write_data(app_data, true).map_err(|e| format!("保存失败：{}", e))?;
+    activity::append(&activity::log_file_path(), events)
+        .map_err(|e| format!("写入活动记录失败：{}", e))
+}
+
+// 向指定名称（不区分大小写）的项目添加 todo，标题支持快速添加的写法
+// 项目不存在时创建，返回是否新建了项目；同名项目已归档时返回错误，不再新建重名的项目
+pub fn add_todo(app_data: &mut AppData, project_name: &str, title: &str) -> Result<bool, String> {
+    let todo = parse_quick_add(title, today()).into_todo();
+    let same_name = |project: &Project| project.name.to_lowercase() == project_name.to_lowercase();
+    if let Some(project) = app_data.projects.iter_mut().find(|p| same_name(p)) {
+        project.todos.push(todo);
+        return Ok(false);
+    }
+    if let Some(project) = app_data.archived_projects.iter().find(|p| same_name(p)) {
+        return Err(format!(
+            "项目「{}」已归档，请先在界面中取消归档",
+            project.name
+        ));
+    }
+    app_data.projects.push(Project {
+        todos: vec![todo],
+        ..Project::new(project_name.to_string())
+    });
+    Ok(true)
+}
+
+// 列出所有项目及其未完成的 todo
+pub fn list(app_data: &AppData, unit: DurationUnit, icons: &Icons) -> String {
+    let mut lines = Vec::new();
+    let mut id = 0;
+    for project in &app_data.projects {
+        let pending = project.todos.iter().filter(|t| !t.completed).count();
+        lines.push(format!("{} {} ({})", icons.project, project.name, pending));
+        for todo in &project.todos {
+            id += 1;
+            if todo.completed {
+                continue;
+            }
+            let timer_indicator = if todo.is_working() {
+                format!("{} ", icons.timer)
+            } else {
+                String::new()
+            };
+            let time_str = if todo.total_duration() > 0 {
+                format!(" [{}]", todo.format_duration(unit))
+            } else {
+                String::new()
+            };
+            lines.push(format!(
+                "{:>3} {} {}{}{}",
+                id, icons.todo, timer_indicator, todo.title, time_str
+            ));
+        }
+    }
+    lines.join("\n")
+}
+
+// 供 tmux、starship、i3bar 等状态栏使用，只输出一行，多个计时用 " | " 分隔
+pub fn current(app_data: &AppData, unit: DurationUnit, icons: &Icons, now: u64) -> String {
+    app_data
+        .projects
+        .iter()
+        .flat_map(|p| &p.todos)
+        .filter(|t| t.is_working())
+        .map(|t| {
+            format!(
+                "{} {} {}",
+                icons.timer,
+                t.title,
+                format_duration(t.session_elapsed(now), unit)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::DATA_VERSION;
+    use crate::theme::{ASCII, EMOJI};
+
+    fn sample_data() -> AppData {
+        let mut done = Todo::new("done".to_string());
+        done.completed = true;
+        let mut timed = Todo::new("timed".to_string());
+        timed.log_session(0, 90);
+        AppData {
+            version: DATA_VERSION,
+            saved_at: 0,
+            projects: vec![Project {
+                todos: vec![done, timed],
+                ..Project::new("work".to_string())
+            }],
+            trash: vec![],
+            archived_projects: vec![],
+            templates: vec![],
+        }
+    }
+
+    #[test]
+    fn add_appends_to_existing_or_creates_project() {
+        let mut data = sample_data();
+        assert_eq!(add_todo(&mut data, "Work", "a"), Ok(false));
+        assert_eq!(data.projects[0].todos.len(), 3);
+        assert_eq!(add_todo(&mut data, "home", "b"), Ok(true));
+        assert_eq!(data.projects[1].name, "home");
+        assert_eq!(data.projects[1].todos[0].title, "b");
+
+        // 归档项目的名称不能再用来新建项目
+        data.archived_projects.push(Project::new("old".to_string()));
+        assert!(add_todo(&mut data, "OLD", "c").is_err());
+        assert_eq!(data.projects.len(), 2);
+    }
+
+    #[test]
+    fn list_shows_only_pending_todos() {
+        assert_eq!(
+            list(&sample_data(), DurationUnit::Month, &EMOJI),
+            "📁 work (1)\n  2 ⭕ timed [1m 30s]"
+        );
+        assert_eq!(
+            list(&sample_data(), DurationUnit::Month, &ASCII),
+            format!(
+                "{} work (1)\n  2 {} timed [1m 30s]",
+                ASCII.project, ASCII.todo
+            )
+        );
+    }
+
+    #[test]
+    fn current_shows_running_timers_on_one_line() {
+        let mut data = sample_data();
+        assert_eq!(current(&data, DurationUnit::Month, &EMOJI, 100), "");
+        data.projects[0].todos[1].start_time = Some(10);
+        assert_eq!(
+            current(&data, DurationUnit::Month, &EMOJI, 100),
+            "⏱️ timed 1m 30s"
+        );
+    }
+
+    #[test]
+    fn completions_include_project_names() {
+        let bash = completions(Shell::Bash);
+        assert!(bash.contains("complete -F _std_projects"));
+        assert!(bash.contains("$(std projects 2>/dev/null)"));
+        assert!(completions(Shell::Fish).contains("-xa \"(std projects 2>/dev/null)\""));
+        assert!(completions(Shell::Zsh).contains("#compdef std"));
+    }
+
+    #[test]
+    fn ids_count_todos_across_projects() {
+        let mut data = sample_data();
+        add_todo(&mut data, "home", "b").unwrap();
+        assert_eq!(todo_by_id(&mut data, 2).unwrap().title, "timed");
+        assert_eq!(todo_by_id(&mut data, 3).unwrap().title, "b");
+        assert!(todo_by_id(&mut data, 0).is_err());
+        assert!(todo_by_id(&mut data, 4).is_err());
+    }
+
+    #[test]
+    fn subcommands_are_parsed() {
+        let cli = Cli::try_parse_from(["std", "add", "写周报", "-p", "work"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Add { title, project: Some(project) }) if title == "写周报" && project == "work"
+        ));
+        let cli = Cli::try_parse_from(["std", "start", "3"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Start { id: 3 })));
+        assert!(Cli::try_parse_from(["std"]).unwrap().command.is_none());
+        assert!(Cli::try_parse_from(["std", "done", "x"]).is_err());
+    }
+}
@@ -0,0 +1,202 @@
+use crate::model::{DurationUnit, SortMode};
+use crate::paths;
+use crate::theme::{Icons, ASCII, EMOJI};
+use chrono::format::{Item, StrftimeItems};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+// 通用设置，从 config.json 加载，缺省的字段使用默认值
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub timer_alert_minutes: u64, // 单次计时超过该分钟数时提醒，0 表示不提醒
+    pub duration_unit: DurationUnit, // 时长显示的最大单位
+    pub trash_retention_days: u64, // 回收站内容保留的天数，0 表示一直保留
+    pub pomodoro_work_minutes: u64, // 番茄钟工作时长
+    pub pomodoro_break_minutes: u64, // 番茄钟休息时长
+    pub idle_minutes: u64, // 计时中超过该分钟数没有按键时，回来后询问是否保留空闲时间，0 表示不检测
+    pub single_timer: bool, // 开始计时时自动停止其他正在计时的 todo
+    pub desktop_notifications: bool, // 计时提醒、番茄钟和到期提醒是否同时发送桌面通知
+    pub caldav: Option<CaldavConfig>, // CalDAV 同步设置，未设置时不能同步
+    pub github_token: Option<String>, // 导入 GitHub issue 使用的 token，未设置时读取环境变量 GITHUB_TOKEN
+    pub autosave_seconds: u64,        // 计时进行中定期保存的间隔，0 表示只在修改时保存
+    pub date_format: String,          // 列表中截止日期的显示格式，chrono 的 strftime 写法
+    pub default_sort: SortMode,       // 新建项目的排序方式
+    pub ascii: bool,                  // 用 ASCII 字符代替 emoji 图标
+    pub project_time_includes_completed: bool, // 项目列表中的耗时合计是否包括已完成的 todo
+}
+
+// CalDAV 服务器设置
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CaldavConfig {
+    pub url: String, // 任务列表（日历集合）的地址
+    pub username: String,
+    #[serde(default)]
+    pub password: String, // 为空时读取环境变量 S_TODO_CALDAV_PASSWORD
+    #[serde(default = "CaldavConfig::default_project")]
+    pub project: String, // 服务器上没有分类的任务放入这个项目
+}
+
+impl CaldavConfig {
+    fn default_project() -> String {
+        "CalDAV".to_string()
+    }
+
+    pub fn password(&self) -> String {
+        match self.password.is_empty() {
+            true => std::env::var("S_TODO_CALDAV_PASSWORD").unwrap_or_default(),
+            false => self.password.clone(),
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            timer_alert_minutes: 90,
+            duration_unit: DurationUnit::default(),
+            trash_retention_days: 30,
+            pomodoro_work_minutes: 25,
+            pomodoro_break_minutes: 5,
+            idle_minutes: 15,
+            single_timer: true,
+            desktop_notifications: true,
+            caldav: None,
+            github_token: None,
+            autosave_seconds: 60,
+            date_format: Self::DEFAULT_DATE_FORMAT.to_string(),
+            default_sort: SortMode::default(),
+            ascii: false,
+            project_time_includes_completed: true,
+        }
+    }
+}
+
+impl Config {
+    const DEFAULT_DATE_FORMAT: &str = "%m-%d";
+
+    // 加载配置文件，文件不存在时使用默认配置
+    // 在其他工作区中时，工作区的 config.<名称>.json 中的设置覆盖 config.json 中的
+    pub fn load() -> Result<Config, String> {
+        let content = std::fs::read_to_string(Self::get_config_file_path()).ok();
+        let overrides = match paths::workspace() {
+            Some(_) => std::fs::read_to_string(paths::workspace_config_file("config.json")).ok(),
+            None => None,
+        };
+        match (content, overrides) {
+            (content, Some(overrides)) => {
+                Self::with_overrides(content.as_deref().unwrap_or("{}"), &overrides)
+            }
+            (Some(content), None) => Self::from_json(&content),
+            (None, None) => Ok(Config::default()),
+        }
+    }
+
+    // 用工作区配置中的字段替换 config.json 中的同名字段
+    pub fn with_overrides(content: &str, overrides: &str) -> Result<Config, String> {
+        let mut config: serde_json::Value =
+            serde_json::from_str(content).map_err(|e| format!("配置文件格式错误：{}", e))?;
+        let overrides: serde_json::Map<String, serde_json::Value> = serde_json::from_str(overrides)
+            .map_err(|e| format!("工作区配置文件格式错误：{}", e))?;
+        match config.as_object_mut() {
+            Some(config) => config.extend(overrides),
+            None => return Err("配置文件格式错误：应为 JSON 对象".to_string()),
+        }
+        Self::from_json(&config.to_string())
+    }
+
+    pub fn from_json(content: &str) -> Result<Config, String> {
+        let config: Config =
+            serde_json::from_str(content).map_err(|e| format!("配置文件格式错误：{}", e))?;
+        // 格式无效时 chrono 在显示日期时会 panic，加载时就拒绝
+        if StrftimeItems::new(&config.date_format).any(|item| item == Item::Error) {
+            return Err(format!(
+                "配置文件格式错误：无效的日期格式 {}",
+                config.date_format
+            ));
+        }
+        Ok(config)
+    }
+
+    // 获取配置文件路径，放在系统配置目录中
+    pub fn get_config_file_path() -> String {
+        paths::config_file("config.json")
+    }
+
+    // 番茄钟的工作和休息时长（秒），至少 1 分钟
+    pub fn pomodoro_secs(&self) -> (u64, u64) {
+        (
+            self.pomodoro_work_minutes.max(1).saturating_mul(60),
+            self.pomodoro_break_minutes.max(1).saturating_mul(60),
+        )
+    }
+
+    // 空闲检测阈值（秒），未启用时返回 None
+    pub fn idle_secs(&self) -> Option<u64> {
+        (self.idle_minutes > 0).then_some(self.idle_minutes.saturating_mul(60))
+    }
+
+    // 定期保存的间隔，未启用时返回 None
+    pub fn autosave_interval(&self) -> Option<Duration> {
+        (self.autosave_seconds > 0).then_some(Duration::from_secs(self.autosave_seconds))
+    }
+
+    // 界面和命令行输出使用的图标
+    pub fn icons(&self) -> &'static Icons {
+        match self.ascii {
+            true => &ASCII,
+            false => &EMOJI,
+        }
+    }
+
+    // 计时提醒阈值（秒），未启用时返回 None
+    pub fn timer_alert_secs(&self) -> Option<u64> {
+        (self.timer_alert_minutes > 0).then_some(self.timer_alert_minutes.saturating_mul(60))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_fields_use_defaults_and_bad_date_formats_are_rejected() {
+        let config =
+            Config::from_json(r#"{"date_format": "%Y/%m/%d", "default_sort": "title"}"#).unwrap();
+        assert_eq!(config.date_format, "%Y/%m/%d");
+        assert_eq!(config.default_sort, SortMode::Title);
+        assert_eq!(config.autosave_interval(), Some(Duration::from_secs(60)));
+        assert!(Config::from_json(r#"{"date_format": "%Q"}"#).is_err());
+    }
+
+    #[test]
+    fn workspace_settings_override_the_shared_config() {
+        let config = Config::with_overrides(
+            r#"{"date_format": "%Y/%m/%d", "pomodoro_work_minutes": 50}"#,
+            r#"{"pomodoro_work_minutes": 30, "single_timer": false}"#,
+        )
+        .unwrap();
+        assert_eq!(config.date_format, "%Y/%m/%d");
+        assert_eq!(config.pomodoro_work_minutes, 30);
+        assert!(!config.single_timer);
+        assert!(Config::with_overrides("{}", r#"{"date_format": "%Q"}"#).is_err());
+        assert!(Config::with_overrides("{}", "[1]").is_err());
+    }
+
+    #[test]
+    fn huge_minute_settings_saturate() {
+        let config = Config::from_json(
+            &serde_json::json!({
+                "pomodoro_work_minutes": u64::MAX,
+                "pomodoro_break_minutes": u64::MAX,
+                "idle_minutes": u64::MAX,
+                "timer_alert_minutes": u64::MAX,
+            })
+            .to_string(),
+        )
+        .unwrap();
+        assert_eq!(config.pomodoro_secs(), (u64::MAX, u64::MAX));
+        assert_eq!(config.idle_secs(), Some(u64::MAX));
+        assert_eq!(config.timer_alert_secs(), Some(u64::MAX));
+    }
+}
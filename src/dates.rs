@@ -0,0 +1,229 @@
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
+
+// 星期的英文和中文写法
+const WEEKDAYS: [(Weekday, &[&str]); 7] = [
+    (Weekday::Mon, &["monday", "mon", "周一", "星期一"]),
+    (Weekday::Tue, &["tuesday", "tue", "周二", "星期二"]),
+    (Weekday::Wed, &["wednesday", "wed", "周三", "星期三"]),
+    (Weekday::Thu, &["thursday", "thu", "周四", "星期四"]),
+    (Weekday::Fri, &["friday", "fri", "周五", "星期五"]),
+    (Weekday::Sat, &["saturday", "sat", "周六", "星期六"]),
+    (Weekday::Sun, &["sunday", "sun", "周日", "星期日", "星期天"]),
+];
+
+// 月份的英文写法，下标加一是月份
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+// 无法识别日期时的提示
+pub const HINT: &str =
+    "可以输入 2026-03-04、today、tomorrow、friday、next monday、in 3 days、jan 15、3月15日 等";
+
+// 解析日期，支持：
+// - YYYY-MM-DD
+// - today、tomorrow、yesterday 和 今天、明天、后天、昨天
+// - 星期几（今天之后最近的一天），next 星期几 / 下周几（下一周的那一天），next week / 下周（下周一）
+// - in 3 days、in 2 weeks、in 1 month 和 3天后、2周后、1个月后
+// - jan 15、15 january、1月15日，没有年份时取今天之后最近的那一天
+pub fn parse_date(input: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let input = input.trim().to_lowercase();
+    if let Ok(date) = NaiveDate::parse_from_str(&input, "%Y-%m-%d") {
+        return Some(date);
+    }
+    match input.as_str() {
+        "today" | "今天" => return Some(today),
+        "tomorrow" | "明天" => return today.checked_add_days(Days::new(1)),
+        "后天" => return today.checked_add_days(Days::new(2)),
+        "yesterday" | "昨天" => return today.checked_sub_days(Days::new(1)),
+        "next week" | "下周" => return Some(next_week(today, Weekday::Mon)),
+        _ => {}
+    }
+    if let Some(weekday) = weekday(&input) {
+        return Some(next_weekday(today, weekday));
+    }
+    let next = input
+        .strip_prefix("next ")
+        .or_else(|| input.strip_prefix("下"))
+        .and_then(|rest| weekday(rest.trim()));
+    if let Some(weekday) = next {
+        return Some(next_week(today, weekday));
+    }
+    relative(&input, today).or_else(|| month_day(&input, today))
+}
+
+// 按界面的日期格式（如 "%m-%d"、"%d/%m"）解析，格式中没有年份时取今天之后最近的那一天
+pub fn parse_with_format(input: &str, format: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let input = input.trim();
+    if let Ok(date) = NaiveDate::parse_from_str(input, format) {
+        return Some(date);
+    }
+    let date = NaiveDate::parse_from_str(
+        &format!("{} {}", input, today.year()),
+        &format!("{} %Y", format),
+    )
+    .ok()?;
+    Some(not_before(date, today))
+}
+
+fn weekday(name: &str) -> Option<Weekday> {
+    WEEKDAYS
+        .iter()
+        .find(|(_, names)| names.contains(&name))
+        .map(|(weekday, _)| *weekday)
+}
+
+// 今天之后最近的星期几，今天就是该星期几时返回下周的同一天
+fn next_weekday(today: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let ahead = (weekday.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+    today + Days::new(if ahead == 0 { 7 } else { ahead as u64 })
+}
+
+// 下一周（周一开始）的星期几
+fn next_week(today: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let monday = today - Days::new(today.weekday().num_days_from_monday() as u64);
+    monday + Days::new(7 + weekday.num_days_from_monday() as u64)
+}
+
+// "in 3 days"、"3天后" 这样相对今天的日期
+fn relative(input: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let (count, unit) = match input.strip_prefix("in ") {
+        Some(rest) => rest.split_once(' ')?,
+        None => {
+            let rest = input.strip_suffix('后')?;
+            let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+            rest.split_at(digits)
+        }
+    };
+    let count: u32 = count.trim().parse().ok()?;
+    match unit.trim().trim_end_matches('s') {
+        "day" | "天" => today.checked_add_days(Days::new(count as u64)),
+        "week" | "周" | "个星期" | "星期" => {
+            today.checked_add_days(Days::new(count as u64 * 7))
+        }
+        "month" | "个月" | "月" => today.checked_add_months(Months::new(count)),
+        _ => None,
+    }
+}
+
+// "jan 15"、"15 jan"、"january 15"、"1月15日"
+fn month_day(input: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let (month, day) = match input.split_once('月') {
+        Some((month, day)) => (
+            month.parse().ok()?,
+            day.trim_end_matches(['日', '号']).parse().ok()?,
+        ),
+        None => {
+            let (first, second) = input.split_once(' ')?;
+            let (name, day) = match first.parse::<u32>() {
+                Ok(day) => (second, day),
+                Err(_) => (first, second.trim().parse().ok()?),
+            };
+            let month = MONTHS
+                .iter()
+                .position(|m| name.trim_end_matches('.').starts_with(m))?;
+            (month as u32 + 1, day)
+        }
+    };
+    let date = NaiveDate::from_ymd_opt(today.year(), month, day)?;
+    Some(not_before(date, today))
+}
+
+// 某天所在月份的月历，每行一周，从周一开始，不属于该月的格子为空
+pub fn month_weeks(date: NaiveDate) -> Vec<[Option<NaiveDate>; 7]> {
+    let first = date.with_day(1).unwrap_or(date);
+    let mut weeks = Vec::new();
+    let mut week = [None; 7];
+    let mut day = first;
+    while day.month() == first.month() {
+        let column = day.weekday().num_days_from_monday() as usize;
+        week[column] = Some(day);
+        if column == 6 {
+            weeks.push(std::mem::take(&mut week));
+        }
+        day = day + Days::new(1);
+    }
+    if week.iter().any(Option::is_some) {
+        weeks.push(week);
+    }
+    weeks
+}
+
+// 已经过去的日期推到明年
+fn not_before(date: NaiveDate, today: NaiveDate) -> NaiveDate {
+    match date < today {
+        true => date.with_year(today.year() + 1).unwrap_or(date),
+        false => date,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2026-03-04 是星期三
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, 4).unwrap()
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(y, m, d)
+    }
+
+    #[test]
+    fn relative_dates_count_from_today() {
+        let cases = [
+            ("2026-03-20", date(2026, 3, 20)),
+            ("Tomorrow", date(2026, 3, 5)),
+            ("后天", date(2026, 3, 6)),
+            ("friday", date(2026, 3, 6)),
+            ("周一", date(2026, 3, 9)),
+            ("wed", date(2026, 3, 11)),
+            ("next friday", date(2026, 3, 13)),
+            ("下周三", date(2026, 3, 11)),
+            ("next week", date(2026, 3, 9)),
+            ("in 3 days", date(2026, 3, 7)),
+            ("in 2 weeks", date(2026, 3, 18)),
+            ("in 1 month", date(2026, 4, 4)),
+            ("10天后", date(2026, 3, 14)),
+            ("someday", None),
+            ("in x days", None),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(parse_date(input, today()), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn month_and_day_without_year_pick_the_next_occurrence() {
+        assert_eq!(parse_date("jan 15", today()), date(2027, 1, 15));
+        assert_eq!(parse_date("15 March", today()), date(2026, 3, 15));
+        assert_eq!(parse_date("3月4日", today()), date(2026, 3, 4));
+        assert_eq!(parse_date("feb 30", today()), None);
+        assert_eq!(
+            parse_with_format("01/03", "%d/%m", today()),
+            date(2027, 3, 1)
+        );
+        assert_eq!(
+            parse_with_format("03-20", "%m-%d", today()),
+            date(2026, 3, 20)
+        );
+    }
+
+    #[test]
+    fn month_weeks_start_on_monday() {
+        // 2026 年 3 月 1 日是周日，31 日是周二
+        let weeks = month_weeks(today());
+        assert_eq!(weeks.len(), 6);
+        assert_eq!(weeks[0][6], date(2026, 3, 1));
+        assert!(weeks[0][..6].iter().all(Option::is_none));
+        assert_eq!(weeks[1][0], date(2026, 3, 2));
+        assert_eq!(weeks[5][1], date(2026, 3, 31));
+        assert_eq!(weeks[5][2], None);
+        // 2027 年 2 月正好四周
+        assert_eq!(
+            month_weeks(NaiveDate::from_ymd_opt(2027, 2, 10).unwrap()).len(),
+            4
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use crate::model::{Project, Todo};
+use serde::Deserialize;
+
+// 每页获取的 issue 数量，GitHub 允许的最大值
+const PER_PAGE: usize = 100;
+
+// REST API 返回的 issue 中用到的字段
+#[derive(Deserialize)]
+pub struct Issue {
+    pub number: u64,
+    pub title: String,
+    pub html_url: String,
+    pub state: String, // open 或 closed
+    #[serde(default)]
+    pub labels: Vec<Label>,
+    #[serde(default)]
+    pub pull_request: Option<serde_json::Value>, // 拉取请求也会出现在 issue 列表中
+}
+
+#[derive(Deserialize)]
+pub struct Label {
+    pub name: String,
+}
+
+// 访问 GitHub REST API，token 为空时匿名访问（有更低的频率限制）
+pub struct Client {
+    token: String,
+}
+
+impl Client {
+    // token 未配置时读取环境变量 GITHUB_TOKEN
+    pub fn new(token: Option<&str>) -> Self {
+        let token = token
+            .map(str::to_string)
+            .or_else(|| std::env::var("GITHUB_TOKEN").ok())
+            .unwrap_or_default();
+        Self { token }
+    }
+
+    fn get(&self, path: &str) -> Result<String, String> {
+        let mut request = ureq::get(&format!("https://api.github.com{}", path))
+            .set("Accept", "application/vnd.github+json")
+            .set("User-Agent", "s_todo");
+        if !self.token.is_empty() {
+            request = request.set("Authorization", &format!("Bearer {}", self.token));
+        }
+        let response = request.call().map_err(|err| match err {
+            ureq::Error::Status(code, response) => {
+                format!("GitHub 返回 {} {}", code, response.status_text())
+            }
+            ureq::Error::Transport(err) => format!("连接 GitHub 失败：{}", err),
+        })?;
+        response
+            .into_string()
+            .map_err(|e| format!("读取 GitHub 响应失败：{}", e))
+    }
+
+    // 获取仓库中所有打开的 issue，不包括拉取请求
+    pub fn open_issues(&self, repo: &str) -> Result<Vec<Issue>, String> {
+        let mut issues = Vec::new();
+        for page in 1.. {
+            let json = self.get(&format!(
+                "/repos/{}/issues?state=open&per_page={}&page={}",
+                repo, PER_PAGE, page
+            ))?;
+            let batch = parse_issues(&json)?;
+            let last = batch.len() < PER_PAGE;
+            issues.extend(batch.into_iter().filter(|i| i.pull_request.is_none()));
+            if last {
+                break;
+            }
+        }
+        Ok(issues)
+    }
+
+    pub fn issue(&self, repo: &str, number: u64) -> Result<Issue, String> {
+        let json = self.get(&format!("/repos/{}/issues/{}", repo, number))?;
+        serde_json::from_str(&json).map_err(|e| format!("GitHub 数据格式错误：{}", e))
+    }
+}
+
+pub fn parse_issues(json: &str) -> Result<Vec<Issue>, String> {
+    serde_json::from_str(json).map_err(|e| format!("GitHub 数据格式错误：{}", e))
+}
+
+// 写在描述第一行的 issue 标记，如 "owner/repo#12 https://github.com/owner/repo/issues/12"
+fn marker(repo: &str, issue: &Issue) -> String {
+    format!("{}#{} {}", repo, issue.number, issue.html_url)
+}
+
+// 从描述第一行读取 issue 所在的仓库和编号
+pub fn issue_ref(todo: &Todo) -> Option<(&str, u64)> {
+    let mut words = todo.description.lines().next()?.split_whitespace();
+    let (repo, number) = words.next()?.rsplit_once('#')?;
+    let url = words.next()?;
+    if !url.starts_with("https://github.com/") || !repo.contains('/') {
+        return None;
+    }
+    Some((repo, number.parse().ok()?))
+}
+
+// 把 issue 加入项目，标签作为 todo 的标签，已经导入过的 issue 跳过，返回新增的数量
+pub fn import_issues(project: &mut Project, repo: &str, issues: &[Issue]) -> usize {
+    let mut imported = 0;
+    for issue in issues {
+        let exists = project
+            .todos
+            .iter()
+            .chain(&project.archived)
+            .any(|todo| issue_ref(todo) == Some((repo, issue.number)));
+        if exists {
+            continue;
+        }
+        project.todos.push(Todo {
+            description: marker(repo, issue),
+            tags: issue
+                .labels
+                .iter()
+                .map(|label| label.name.replace(char::is_whitespace, "-"))
+                .collect(),
+            ..Todo::new(issue.title.clone())
+        });
+        imported += 1;
+    }
+    imported
+}
+
+// 按 issue 的状态更新 todo，关闭的 issue 标记为完成，重新打开的恢复为未完成，返回是否有修改
+pub fn apply_state(todo: &mut Todo, issue: &Issue) -> bool {
+    let closed = issue.state == "closed";
+    if todo.completed == closed {
+        return false;
+    }
+    todo.set_completed(closed);
+    todo.doing = false;
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ISSUES: &str = r#"[
+        {"number": 12, "title": "登录失败", "html_url": "https://github.com/o/r/issues/12",
+         "state": "open", "labels": [{"name": "bug"}, {"name": "good first issue"}]},
+        {"number": 13, "title": "加一个功能", "html_url": "https://github.com/o/r/pull/13",
+         "state": "open", "pull_request": {"url": "..."}}
+    ]"#;
+
+    #[test]
+    fn issues_are_imported_once_with_their_reference() {
+        let issues: Vec<Issue> = parse_issues(ISSUES)
+            .unwrap()
+            .into_iter()
+            .filter(|i| i.pull_request.is_none())
+            .collect();
+        let mut project = Project::new("r".to_string());
+        assert_eq!(import_issues(&mut project, "o/r", &issues), 1);
+        assert_eq!(import_issues(&mut project, "o/r", &issues), 0);
+
+        let todo = &mut project.todos[0];
+        assert_eq!(todo.title, "登录失败");
+        assert_eq!(todo.description, "o/r#12 https://github.com/o/r/issues/12");
+        assert_eq!(todo.tags, vec!["bug", "good-first-issue"]);
+        assert_eq!(issue_ref(todo), Some(("o/r", 12)));
+
+        let mut closed = parse_issues(ISSUES).unwrap().remove(0);
+        closed.state = "closed".to_string();
+        assert!(apply_state(todo, &closed));
+        assert!(todo.completed);
+        assert!(!apply_state(todo, &closed));
+    }
+}
@@ -0,0 +1,143 @@
+use crate::model::{stable_hash, Project, Status, Todo};
+use chrono::{TimeZone, Utc};
+
+// 日历中 todo 的唯一标识，由创建时间、项目名和标题计算，重复导出时保持不变
+// 已经和 CalDAV 同步过的 todo 使用同步时的标识
+pub fn uid(project: &str, todo: &Todo) -> String {
+    if let Some(link) = &todo.caldav {
+        return link.uid.clone();
+    }
+    let hash = stable_hash(&format!("{}\0{}", project, todo.title));
+    format!("{}-{:016x}@s_todo", todo.created_at, hash)
+}
+
+// 转义文本中的特殊字符
+pub fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+// 按规范把超过 75 字节的行折行，续行以空格开头，不在字符中间断开
+pub fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+// UTC 时间，如 "20260304T013000Z"
+pub fn format_utc(ts: u64) -> String {
+    Utc.timestamp_opt(ts as i64, 0)
+        .earliest()
+        .map_or_else(String::new, |dt| dt.format("%Y%m%dT%H%M%SZ").to_string())
+}
+
+// 状态对应的 STATUS 属性
+pub fn status_value(todo: &Todo) -> &'static str {
+    match todo.status() {
+        Status::Todo => "NEEDS-ACTION",
+        Status::Doing => "IN-PROCESS",
+        Status::Done => "COMPLETED",
+    }
+}
+
+// 一个 todo 的 VTODO 组件的各行（未折行），项目名和标签作为分类
+pub fn vtodo(project: &str, todo: &Todo, uid: &str, now: u64) -> Vec<String> {
+    let categories: Vec<String> = std::iter::once(project)
+        .chain(todo.tags.iter().map(String::as_str))
+        .map(escape)
+        .collect();
+
+    let mut lines = vec![
+        "BEGIN:VTODO".to_string(),
+        format!("UID:{}", uid),
+        format!("DTSTAMP:{}", format_utc(now)),
+    ];
+    if todo.created_at > 0 {
+        lines.push(format!("CREATED:{}", format_utc(todo.created_at)));
+    }
+    lines.push(format!("SUMMARY:{}", escape(&todo.title)));
+    if !todo.description.is_empty() {
+        lines.push(format!("DESCRIPTION:{}", escape(&todo.description)));
+    }
+    if let Some(due) = todo.due_date {
+        lines.push(format!("DUE;VALUE=DATE:{}", due.format("%Y%m%d")));
+    }
+    lines.push(format!("STATUS:{}", status_value(todo)));
+    if todo.completed {
+        lines.push("PERCENT-COMPLETE:100".to_string());
+    }
+    lines.push(format!("CATEGORIES:{}", categories.join(",")));
+    lines.push("END:VTODO".to_string());
+    lines
+}
+
+// 把 VTODO 等组件包装成完整的日历文件
+pub fn calendar(components: Vec<String>) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//s_todo//s_todo//ZH".to_string(),
+    ];
+    lines.extend(components);
+    lines.push("END:VCALENDAR".to_string());
+    lines.iter().map(|line| fold(line)).collect()
+}
+
+// 把有截止日期的 todo 导出为 iCalendar 的 VTODO
+pub fn export(projects: &[Project], now: u64) -> String {
+    let mut components = Vec::new();
+    for project in projects {
+        for todo in project.todos.iter().filter(|t| t.due_date.is_some()) {
+            components.extend(vtodo(&project.name, todo, &uid(&project.name, todo), now));
+        }
+    }
+    calendar(components)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn only_todos_with_due_dates_are_exported() {
+        let mut due = Todo::new("写周报; 发给 A,B".to_string());
+        due.created_at = 0;
+        due.due_date = NaiveDate::from_ymd_opt(2026, 3, 4);
+        due.completed = true;
+        due.tags = vec!["work".to_string()];
+        let project = Project {
+            todos: vec![due, Todo::new("no due".to_string())],
+            ..Project::new("p".to_string())
+        };
+
+        let ics = export(&[project], 0);
+        assert_eq!(ics.matches("BEGIN:VTODO").count(), 1);
+        assert!(ics.contains("SUMMARY:写周报\\; 发给 A\\,B\r\n"));
+        assert!(ics.contains("DUE;VALUE=DATE:20260304\r\n"));
+        assert!(ics.contains("STATUS:COMPLETED\r\n"));
+        assert!(ics.contains("CATEGORIES:p,work\r\n"));
+        assert!(ics.contains("DTSTAMP:19700101T000000Z\r\n"));
+        assert!(!ics.contains("CREATED"));
+    }
+
+    #[test]
+    fn long_lines_are_folded_on_char_boundaries() {
+        let folded = fold(&format!("SUMMARY:{}", "番".repeat(30)));
+        let lines: Vec<&str> = folded.split("\r\n").collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines.iter().all(|line| line.len() <= 75));
+        assert!(lines[1].starts_with(' '));
+    }
+}
@@ -0,0 +1,887 @@
+use crate::app::{App, InputMode, Panel, ResumeChoice, View};
+use crate::keys::Action;
+use crate::model::now_secs;
+use crate::palette::Command;
+use crate::report;
+use crate::text_input::TextInput;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::{Margin, Position, Rect};
+use std::time::{Duration, Instant};
+
+// 两次点击间隔不超过这个时间时算作双击
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+
+// 按键处理后需要主循环完成的操作
+#[derive(Debug, PartialEq, Eq)]
+pub enum Outcome {
+    None,
+    Save,
+    Quit,
+    EditDescription, // 暂停界面，用外部编辑器编辑描述
+    Copy(String),    // 复制到系统剪贴板
+}
+
+// 处理一次按键
+pub fn handle_key(app: &mut App, key: KeyEvent) -> Outcome {
+    // 按键后清除一次性的提示信息，版本过新的警告需要一直显示
+    if !app.read_only {
+        app.message = None;
+    }
+
+    // 原始模式下 Ctrl+C 不会产生 SIGINT，在任何界面中都直接退出，退出前会保存
+    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+        return Outcome::Quit;
+    }
+
+    // 计时中空闲太久后的第一次按键只用来唤醒，弹出询问如何处理空闲时间
+    if app.record_activity(now_secs()) {
+        return Outcome::None;
+    }
+
+    // 数据文件损坏：n 重新开始，q 或 Esc 退出，不会写回数据文件
+    if app.view == View::CorruptData {
+        return match key.code {
+            KeyCode::Char('n') => {
+                app.start_fresh();
+                Outcome::Save
+            }
+            KeyCode::Char('q') | KeyCode::Esc => Outcome::Quit,
+            _ => Outcome::None,
+        };
+    }
+
+    // 覆盖视图打开时，按键只作用于该视图
+    if app.view != View::Main && app.input_mode == InputMode::Normal {
+        if handle_view_key(app, key.code) {
+            return Outcome::Save;
+        }
+        return Outcome::None;
+    }
+
+    if app.input_mode == InputMode::Normal && handle_motion(app, key) {
+        return Outcome::None;
+    }
+
+    let mut should_save = false;
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    let alt = key.modifiers.contains(KeyModifiers::ALT);
+
+    match app.input_mode {
+        // Ctrl+P 打开全局查找，不受按键绑定影响
+        InputMode::Normal if ctrl && key.code == KeyCode::Char('p') => app.start_finding(),
+        // Alt+J/Alt+K 和 J/K 一样调整任务位置
+        InputMode::Normal
+            if alt
+                && app.active_panel == Panel::Todos
+                && matches!(key.code, KeyCode::Char('j') | KeyCode::Char('k')) =>
+        {
+            should_save = app.move_selected_todo(key.code == KeyCode::Char('j'));
+        }
+        // 按当前的按键绑定查找操作并执行
+        InputMode::Normal => return perform(app, app.keys.action_for(key.code), key.code),
+        // 编辑描述时 Enter 换行，Ctrl+S 保存
+        InputMode::EditingDescription => match key.code {
+            KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                should_save = app.submit_input();
+            }
+            KeyCode::Enter => app.input.insert('\n'),
+            KeyCode::Esc => app.input_mode = InputMode::Normal,
+            _ => {
+                edit_text(&mut app.input, key);
+            }
+        },
+        // 添加、重命名、移动和搜索共用同一套输入处理
+        _ => match key.code {
+            // 命令面板中的操作和按下对应的按键一样执行
+            KeyCode::Enter if app.input_mode == InputMode::Commanding => {
+                match app.take_palette_command() {
+                    Some(Command::Action(action)) => {
+                        return perform(app, Some(action), KeyCode::Null);
+                    }
+                    Some(command) => should_save = app.run_command(command),
+                    None => {}
+                }
+            }
+            KeyCode::Enter => should_save = app.submit_input(),
+            // 全局查找中用上下键或 Ctrl+N/Ctrl+P 选择结果
+            KeyCode::Down => app.select_finder(true),
+            KeyCode::Up => app.select_finder(false),
+            KeyCode::Char('n') if ctrl => app.select_finder(true),
+            KeyCode::Char('p') if ctrl => app.select_finder(false),
+            KeyCode::Esc => app.cancel_input(),
+            _ => {
+                if edit_text(&mut app.input, key) {
+                    app.input_changed();
+                }
+            }
+        },
+    }
+
+    if should_save {
+        Outcome::Save
+    } else {
+        Outcome::None
+    }
+}
+
+// 粘贴的文本插入输入框的光标处，单行输入框中换行替换为空格，不在输入时忽略
+pub fn handle_paste(app: &mut App, text: &str) {
+    match app.input_mode {
+        InputMode::Normal => {}
+        InputMode::EditingDescription => app.input.insert_str(&text.replace("\r\n", "\n")),
+        _ => {
+            let line = text.lines().collect::<Vec<_>>().join(" ");
+            app.input.insert_str(&line);
+            app.input_changed();
+        }
+    }
+}
+
+// 输入框中的编辑按键：左右移动、Home/End、Ctrl+W 删除前一个词、Ctrl+U 删除到行首
+// 返回文本是否有变化
+fn edit_text(input: &mut TextInput, key: KeyEvent) -> bool {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    match key.code {
+        KeyCode::Char('w') if ctrl => input.delete_word(),
+        KeyCode::Char('u') if ctrl => input.delete_to_line_start(),
+        KeyCode::Char(c) if !ctrl => {
+            input.insert(c);
+            true
+        }
+        KeyCode::Backspace => input.delete_backward(),
+        KeyCode::Delete => input.delete_forward(),
+        KeyCode::Left => {
+            input.move_left();
+            false
+        }
+        KeyCode::Right => {
+            input.move_right();
+            false
+        }
+        KeyCode::Home => {
+            input.move_home();
+            false
+        }
+        KeyCode::End => {
+            input.move_end();
+            false
+        }
+        _ => false,
+    }
+}
+
+// 覆盖视图中的按键，Esc 或退出键关闭视图，返回是否有修改
+fn handle_view_key(app: &mut App, code: KeyCode) -> bool {
+    // 上次未结束的计时：k 保留，d 丢弃，t 或 Esc 记到上次退出为止
+    if app.view == View::ResumeTimers {
+        let choice = match code {
+            KeyCode::Char('k') => ResumeChoice::Keep,
+            KeyCode::Char('d') => ResumeChoice::Discard,
+            KeyCode::Char('t') | KeyCode::Esc => ResumeChoice::Truncate,
+            _ => return false,
+        };
+        return app.resume_timers(choice);
+    }
+
+    // 空闲询问：d 丢弃空闲时间，k 或 Esc 保留
+    if app.view == View::IdlePrompt {
+        return match code {
+            KeyCode::Char('d') => app.resolve_idle(false),
+            KeyCode::Char('k') | KeyCode::Esc => app.resolve_idle(true),
+            _ => false,
+        };
+    }
+
+    let action = app.keys.action_for(code);
+    if code == KeyCode::Esc || action == Some(Action::Quit) {
+        app.close_view();
+        return false;
+    }
+
+    match app.view {
+        // 时间报告：切换面板的键切换范围，左右方向键切换到前后的日期，上下键滚动
+        View::Report => match action {
+            _ if code == KeyCode::Left => app.shift_report_period(true),
+            _ if code == KeyCode::Right => app.shift_report_period(false),
+            Some(Action::ShowReport) => app.close_view(),
+            Some(Action::SwitchPanel) => app.cycle_report_range(),
+            Some(Action::MoveDown) => app.scroll_report(true),
+            Some(Action::MoveUp) => app.scroll_report(false),
+            _ => {}
+        },
+        // 子任务：添加、完成和删除子任务
+        View::Subtasks => match action {
+            Some(Action::MoveDown) => app.select_subtask(true),
+            Some(Action::MoveUp) => app.select_subtask(false),
+            Some(Action::ToggleComplete) => return app.toggle_selected_subtask(),
+            Some(Action::Delete) => return app.delete_selected_subtask(),
+            Some(Action::AddItem) => {
+                app.input_mode = InputMode::AddingSubtask;
+                app.input.clear();
+            }
+            _ => {}
+        },
+        // 删除项目确认：只有 y 确认删除，其他键取消
+        View::ConfirmDeleteProject => {
+            app.close_view();
+            if matches!(code, KeyCode::Char('y') | KeyCode::Char('Y')) {
+                return app.delete_selected_project();
+            }
+        }
+        // 批量删除确认：只有 y 确认删除，其他键取消并保留选择
+        View::ConfirmDeleteTodos => {
+            app.close_view();
+            if matches!(code, KeyCode::Char('y') | KeyCode::Char('Y')) {
+                return app.delete_marked();
+            }
+        }
+        // 回收站：Enter 恢复，删除键永久删除
+        View::Trash => match action {
+            _ if code == KeyCode::Enter => return app.restore_selected_trash(),
+            Some(Action::Delete) => return app.purge_selected_trash(),
+            Some(Action::MoveDown) => app.select_trash(true),
+            Some(Action::MoveUp) => app.select_trash(false),
+            Some(Action::ShowTrash) => app.close_view(),
+            _ => {}
+        },
+        // 看板：切换面板的键和左右方向键切换列，h/l 把卡片移到相邻的列
+        View::Board => match action {
+            _ if code == KeyCode::Char('h') => return app.move_board_card(false),
+            _ if code == KeyCode::Char('l') => return app.move_board_card(true),
+            _ if code == KeyCode::Left => app.switch_board_column(false),
+            _ if code == KeyCode::Right => app.switch_board_column(true),
+            Some(Action::ShowBoard) => app.close_view(),
+            Some(Action::SwitchPanel) => app.switch_board_column(true),
+            Some(Action::MoveDown) => app.select_board_card(true),
+            Some(Action::MoveUp) => app.select_board_card(false),
+            _ => {}
+        },
+        // 番茄钟提示：任意键关闭
+        View::PomodoroNotice => app.close_view(),
+        View::ResumeTimers | View::IdlePrompt | View::CorruptData => {}
+        View::Stats => {
+            if action == Some(Action::ShowStats) {
+                app.close_view();
+            }
+        }
+        // 计时记录：修改或删除选中的记录
+        View::Sessions => match action {
+            Some(Action::MoveDown) => app.select_session(true),
+            Some(Action::MoveUp) => app.select_session(false),
+            Some(Action::Rename) => app.start_editing_session(),
+            Some(Action::AddItem) | Some(Action::LogTime) => app.start_logging_time(),
+            Some(Action::Delete) => return app.delete_selected_session(),
+            Some(Action::ShowSessions) => app.close_view(),
+            _ => {}
+        },
+        // 按键帮助：上下键滚动
+        View::Help => match action {
+            Some(Action::MoveDown) => app.scroll_help(true),
+            Some(Action::MoveUp) => app.scroll_help(false),
+            Some(Action::ShowHelp) => app.close_view(),
+            _ => {}
+        },
+        View::Details => {
+            if action == Some(Action::ShowDetails) {
+                app.close_view();
+            }
+        }
+        // 月历：左右方向键切换日期，上下键切换到前后一周，PageUp/PageDown 翻月
+        View::Calendar => match action {
+            _ if code == KeyCode::Left => app.move_calendar_day(-1),
+            _ if code == KeyCode::Right => app.move_calendar_day(1),
+            _ if code == KeyCode::PageUp => app.shift_calendar_month(true),
+            _ if code == KeyCode::PageDown => app.shift_calendar_month(false),
+            Some(Action::MoveDown) => app.move_calendar_day(7),
+            Some(Action::MoveUp) => app.move_calendar_day(-7),
+            Some(Action::ShowCalendar) => app.close_view(),
+            _ => {}
+        },
+        // 工作区：Enter 切换，添加键输入新工作区的名称
+        View::Workspaces => match action {
+            _ if code == KeyCode::Enter => app.switch_to_selected_workspace(),
+            Some(Action::MoveDown) => app.select_workspace(true),
+            Some(Action::MoveUp) => app.select_workspace(false),
+            Some(Action::AddItem) => {
+                app.input_mode = InputMode::AddingWorkspace;
+                app.input.clear();
+            }
+            Some(Action::SwitchWorkspace) => app.close_view(),
+            _ => {}
+        },
+        // 沉浸模式：只有关闭
+        View::Zen => {
+            if action == Some(Action::ZenMode) {
+                app.close_view();
+            }
+        }
+        // 日程：Enter 跳到选中的 todo
+        View::Agenda => match action {
+            _ if code == KeyCode::Enter => app.confirm_agenda(),
+            Some(Action::MoveDown) => app.select_agenda(true),
+            Some(Action::MoveUp) => app.select_agenda(false),
+            Some(Action::ShowAgenda) => app.close_view(),
+            _ => {}
+        },
+        // 今日回顾：上下键滚动
+        View::Today => match action {
+            Some(Action::MoveDown) => app.scroll_today(true),
+            Some(Action::MoveUp) => app.scroll_today(false),
+            Some(Action::ShowToday) => app.close_view(),
+            _ => {}
+        },
+        // 活动记录：上下键滚动
+        View::Activity => match action {
+            Some(Action::MoveDown) => app.scroll_activity(true),
+            Some(Action::MoveUp) => app.scroll_activity(false),
+            Some(Action::ShowActivity) => app.close_view(),
+            _ => {}
+        },
+        // 消息记录：上下键滚动
+        View::Messages => match action {
+            Some(Action::MoveDown) => app.scroll_messages(true),
+            Some(Action::MoveUp) => app.scroll_messages(false),
+            Some(Action::ShowMessages) => app.close_view(),
+            _ => {}
+        },
+        // 归档：Enter 放回列表
+        View::Archive => match action {
+            _ if code == KeyCode::Enter => return app.unarchive_selected(),
+            Some(Action::MoveDown) => app.select_archived(true),
+            Some(Action::MoveUp) => app.select_archived(false),
+            Some(Action::ShowArchive) => app.close_view(),
+            _ => {}
+        },
+        // 归档的项目：Enter 放回项目列表，导出键导出为 Markdown
+        View::ArchivedProjects => match action {
+            _ if code == KeyCode::Enter => return app.unarchive_selected_project(),
+            Some(Action::MoveDown) => app.select_archived_project(true),
+            Some(Action::MoveUp) => app.select_archived_project(false),
+            Some(Action::ExportMarkdown) => app.export_archived_project(),
+            Some(Action::ShowArchivedProjects) => app.close_view(),
+            _ => {}
+        },
+        // 模板：Enter 用模板创建，删除键删除模板
+        View::Templates => match action {
+            _ if code == KeyCode::Enter => return app.instantiate_selected_template(),
+            Some(Action::MoveDown) => app.select_template(true),
+            Some(Action::MoveUp) => app.select_template(false),
+            Some(Action::Delete) => return app.delete_selected_template(),
+            Some(Action::ShowTemplates) => app.close_view(),
+            _ => {}
+        },
+        // 选择目标项目：Enter 移动
+        View::ProjectPicker => match action {
+            _ if code == KeyCode::Enter => return app.move_to_picked_project(),
+            Some(Action::MoveDown) => app.select_picker(true),
+            Some(Action::MoveUp) => app.select_picker(false),
+            _ => {}
+        },
+        View::Main => {}
+    }
+    false
+}
+
+// 执行普通模式下的操作，code 为触发的按键，从命令面板执行时为 KeyCode::Null
+fn perform(app: &mut App, action: Option<Action>, code: KeyCode) -> Outcome {
+    let mut should_save = false;
+    match action {
+        Some(Action::Quit) => return Outcome::Quit,
+        Some(Action::Save) => app.save_manually(),
+        Some(Action::SwitchPanel) => app.switch_panel(),
+        Some(Action::MoveDown) => app.select_next(),
+        Some(Action::MoveUp) => app.select_previous(),
+        Some(Action::JumpToTop) => app.select_position(0),
+        Some(Action::JumpToBottom) => app.select_position(usize::MAX),
+        // 多选模式下空格选中任务，批量操作作用于选中的任务
+        Some(Action::SelectMode) => app.toggle_select_mode(),
+        Some(Action::ToggleComplete) if app.selecting => app.toggle_mark(),
+        Some(Action::ToggleAllComplete) if app.selecting => {
+            should_save = app.complete_marked();
+        }
+        Some(Action::Delete) if app.selecting => app.request_delete_marked(),
+        Some(Action::MoveTodos) if app.selecting => app.start_moving(),
+        Some(Action::FilterTag) if app.selecting => app.start_tagging_marked(),
+        Some(Action::MoveToProject) if app.active_panel == Panel::Todos => {
+            app.open_project_picker();
+        }
+        Some(Action::MoveToProject) => app.open_merge_picker(),
+        None if app.selecting && code == KeyCode::Esc => app.exit_select_mode(),
+        // 单面板模式下 Enter 进入选中的项目，Esc 或 Backspace 返回项目列表
+        _ if app.single_pane && app.active_panel == Panel::Projects && code == KeyCode::Enter => {
+            app.switch_panel();
+        }
+        _ if app.single_pane
+            && app.active_panel == Panel::Todos
+            && matches!(code, KeyCode::Esc | KeyCode::Backspace) =>
+        {
+            app.switch_panel();
+        }
+        Some(Action::ToggleComplete) if app.active_panel == Panel::Todos => {
+            should_save = app.toggle_selected_todo();
+        }
+        Some(Action::ToggleAllComplete) if app.active_panel == Panel::Todos => {
+            // 批量修改后只保存一次
+            should_save = app.toggle_all_todos();
+        }
+        Some(Action::AddItem) => app.start_adding(),
+        Some(Action::ShowReport) => app.open_report(),
+        Some(Action::ShowTrash) => app.open_trash(),
+        Some(Action::ShowBoard) => app.open_board(),
+        Some(Action::ShowArchive) => app.open_archive(),
+        Some(Action::ShowStats) => app.view = View::Stats,
+        Some(Action::ShowHelp) => app.open_help(),
+        Some(Action::CommandPalette) => app.start_command_palette(),
+        Some(Action::ShowMessages) => app.open_messages(),
+        Some(Action::ShowActivity) => app.open_activity(),
+        Some(Action::ShowToday) => app.open_today(),
+        Some(Action::ShowCalendar) => app.open_calendar(),
+        Some(Action::ShowAgenda) => app.open_agenda(),
+        Some(Action::ZenMode) => app.open_zen(),
+        Some(Action::SwitchWorkspace) => app.open_workspaces(),
+        Some(Action::ExportMarkdown) => app.export_project_markdown(),
+        Some(Action::ArchiveCompleted) => should_save = app.archive_completed(),
+        Some(Action::ToggleFocus) => app.toggle_focus_mode(),
+        Some(Action::ToggleDetailPane) => app.toggle_detail_pane(),
+        Some(Action::ShrinkProjects) => app.resize_projects(false),
+        Some(Action::GrowProjects) => app.resize_projects(true),
+        Some(Action::SinglePane) => app.toggle_single_pane(),
+        Some(Action::HideCompleted) => app.toggle_hide_completed(),
+        Some(Action::ToggleAscii) => app.toggle_ascii(),
+        Some(Action::CollapseProjects) if app.active_panel == Panel::Projects => {
+            app.collapse_other_projects();
+        }
+        Some(Action::FoldProject) if app.active_panel == Panel::Projects => {
+            should_save = app.fold_selected_project(true);
+        }
+        Some(Action::UnfoldProject) if app.active_panel == Panel::Projects => {
+            should_save = app.fold_selected_project(false);
+        }
+        Some(Action::SetParentProject) if app.active_panel == Panel::Projects => {
+            app.start_setting_parent();
+        }
+        Some(Action::ArchiveProject) if app.active_panel == Panel::Projects => {
+            should_save = app.archive_selected_project();
+        }
+        Some(Action::ShowArchivedProjects) => app.open_archived_projects(),
+        Some(Action::ToggleDescription) => app.toggle_description(),
+        Some(Action::ScrollDescriptionDown) => app.scroll_description(true),
+        Some(Action::ScrollDescriptionUp) => app.scroll_description(false),
+        Some(Action::CycleSort) if app.active_panel == Panel::Todos => {
+            should_save = app.cycle_sort_mode();
+        }
+        Some(Action::ToggleTimer) if app.active_panel == Panel::Todos => {
+            // 切换当前 todo 的计时状态
+            should_save = app.toggle_current_todo_timer();
+        }
+        Some(Action::MoveTodoDown) if app.active_panel == Panel::Todos => {
+            should_save = app.move_selected_todo(true);
+        }
+        Some(Action::MoveTodoUp) if app.active_panel == Panel::Todos => {
+            should_save = app.move_selected_todo(false);
+        }
+        Some(Action::SetDueDate) if app.active_panel == Panel::Todos => {
+            app.start_setting_due_date();
+        }
+        Some(Action::FilterTag) => app.start_filtering_tag(),
+        Some(Action::Search) if app.active_panel == Panel::Todos => app.start_searching(),
+        Some(Action::SearchNext) => app.jump_to_match(true),
+        Some(Action::SearchPrevious) => app.jump_to_match(false),
+        Some(Action::CycleRecurrence) if app.active_panel == Panel::Todos => {
+            should_save = app.cycle_recurrence();
+        }
+        Some(Action::ShowSessions) if app.active_panel == Panel::Todos => {
+            app.open_sessions();
+        }
+        Some(Action::LogTime) if app.active_panel == Panel::Todos => {
+            app.start_logging_time();
+        }
+        Some(Action::SetEstimate) if app.active_panel == Panel::Todos => {
+            app.start_setting_estimate();
+        }
+        Some(Action::Pomodoro) if app.active_panel == Panel::Todos => {
+            should_save = app.toggle_pomodoro();
+        }
+        Some(Action::OpenSubtasks) if app.active_panel == Panel::Todos => {
+            app.open_subtasks();
+        }
+        Some(Action::EditDescription) if app.active_panel == Panel::Todos => {
+            app.start_editing_description();
+        }
+        Some(Action::EditDescriptionExternal) if app.active_panel == Panel::Todos => {
+            return Outcome::EditDescription;
+        }
+        Some(Action::ShowDetails) if app.active_panel == Panel::Todos => app.open_details(),
+        Some(Action::Yank) if app.active_panel == Panel::Todos => {
+            if let Some(todo) = app.get_current_todo() {
+                return Outcome::Copy(todo.title.clone());
+            }
+        }
+        Some(Action::YankDetails) if app.active_panel == Panel::Todos => {
+            if let Some(todo) = app.get_current_todo() {
+                return Outcome::Copy(report::todo_details(todo, app.config.duration_unit));
+            }
+        }
+        // 重命名当前选中的项目或 todo
+        Some(Action::Rename) => app.start_renaming(),
+        Some(Action::Duplicate) => should_save = app.duplicate_selected(),
+        Some(Action::SaveTemplate) => app.start_saving_template(),
+        Some(Action::ShowTemplates) => app.open_templates(),
+        Some(Action::Delete) => {
+            should_save = match app.active_panel {
+                Panel::Projects => app.request_delete_project(),
+                Panel::Todos => app.delete_selected_todo(),
+            };
+        }
+        _ => {}
+    }
+    if should_save {
+        Outcome::Save
+    } else {
+        Outcome::None
+    }
+}
+
+// 普通模式下类似 vim 的按键序列：数字前缀（如 5j、3G）、gg 跳到开头、Ctrl+D/Ctrl+U 翻半页
+// 数字和 g 没有绑定操作时才作为序列的一部分，返回按键是否已处理
+// 其他按键会清空还没完成的序列，数字前缀只对移动选择有效
+fn handle_motion(app: &mut App, key: KeyEvent) -> bool {
+    let count = app.count_prefix.take();
+    let pending_g = std::mem::take(&mut app.pending_g);
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    let action = app.keys.action_for(key.code);
+    match key.code {
+        KeyCode::Char(c @ ('d' | 'u')) if ctrl => {
+            let steps = app.half_page().saturating_mul(count.unwrap_or(1));
+            app.move_selection(steps, c == 'd');
+        }
+        KeyCode::Char(c) if !ctrl && action.is_none() && c.is_ascii_digit() => {
+            // 单独的 0 不是前缀
+            if c == '0' && count.is_none() {
+                return false;
+            }
+            let digit = c.to_digit(10).unwrap_or(0) as usize;
+            app.count_prefix = Some(count.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+        }
+        KeyCode::Char('g') if !ctrl && action.is_none() => match pending_g {
+            true => app.select_position(count.map_or(0, |n| n.saturating_sub(1))),
+            false => {
+                app.pending_g = true;
+                app.count_prefix = count;
+            }
+        },
+        _ => match (action, count) {
+            (Some(Action::MoveDown), Some(n)) => app.move_selection(n, true),
+            (Some(Action::MoveUp), Some(n)) => app.move_selection(n, false),
+            (Some(Action::JumpToBottom), Some(n)) => app.select_position(n.saturating_sub(1)),
+            _ => return false,
+        },
+    }
+    true
+}
+
+// 处理鼠标：点击选中列表中的项目或 todo 并切换到所在的面板，双击 todo 切换完成状态，
+// 滚轮在指针所在的面板中移动选择；只在主界面且没有输入时响应
+pub fn handle_mouse(app: &mut App, mouse: MouseEvent) -> Outcome {
+    if app.record_activity(now_secs()) {
+        return Outcome::None;
+    }
+    if app.view != View::Main || app.input_mode != InputMode::Normal {
+        return Outcome::None;
+    }
+    let position = Position::new(mouse.column, mouse.row);
+    match mouse.kind {
+        MouseEventKind::Down(MouseButton::Left) => {
+            if let Some(row) = list_row(app.todo_list_area, app.todo_state.offset(), position) {
+                if !app.click_todo(row) {
+                    return Outcome::None;
+                }
+                let double = app
+                    .last_click
+                    .is_some_and(|(at, last)| last == row && at.elapsed() <= DOUBLE_CLICK);
+                if double {
+                    app.last_click = None;
+                    if app.toggle_selected_todo() {
+                        return Outcome::Save;
+                    }
+                } else {
+                    app.last_click = Some((Instant::now(), row));
+                }
+            } else if let Some(row) =
+                list_row(app.project_list_area, app.project_state.offset(), position)
+            {
+                app.click_project(row);
+            }
+        }
+        MouseEventKind::ScrollDown | MouseEventKind::ScrollUp => {
+            let panel = if app.todo_list_area.contains(position) {
+                Panel::Todos
+            } else if app.project_list_area.contains(position) {
+                Panel::Projects
+            } else {
+                return Outcome::None;
+            };
+            if app.active_panel != panel {
+                app.switch_panel();
+            }
+            match mouse.kind {
+                MouseEventKind::ScrollDown => app.select_next(),
+                _ => app.select_previous(),
+            }
+        }
+        _ => {}
+    }
+    Outcome::None
+}
+
+// 点击位置在列表中对应的行（加上滚动的偏移），点在边框上或列表外时为 None
+fn list_row(area: Rect, offset: usize, position: Position) -> Option<usize> {
+    let inner = area.inner(Margin::new(1, 1));
+    inner
+        .contains(position)
+        .then(|| offset + (position.y - inner.y) as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Project;
+
+    fn press(app: &mut App, code: KeyCode) -> Outcome {
+        handle_key(app, KeyEvent::from(code))
+    }
+
+    #[test]
+    fn typed_keys_add_a_todo_and_request_save() {
+        let mut app = App::with_projects(vec![Project::new("p".to_string())]);
+        app.active_panel = Panel::Todos;
+        assert_eq!(press(&mut app, KeyCode::Char('a')), Outcome::None);
+        for c in "买菜".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        assert_eq!(press(&mut app, KeyCode::Enter), Outcome::Save);
+        assert_eq!(app.projects[0].todos[0].title, "买菜");
+        assert_eq!(press(&mut app, KeyCode::Char('q')), Outcome::Quit);
+    }
+
+    #[test]
+    fn corrupt_data_screen_offers_fresh_start_or_quit() {
+        let mut app = App::with_projects(vec![]);
+        app.read_only = true;
+        app.view = View::CorruptData;
+        assert_eq!(press(&mut app, KeyCode::Char('a')), Outcome::None);
+        assert_eq!(press(&mut app, KeyCode::Esc), Outcome::Quit);
+        assert!(app.read_only);
+
+        assert_eq!(press(&mut app, KeyCode::Char('n')), Outcome::Save);
+        assert!(!app.read_only);
+        assert!(app.view == View::Main);
+        assert!(!app.projects.is_empty());
+    }
+
+    #[test]
+    fn single_pane_enter_drills_in_and_escape_goes_back() {
+        let mut project = Project::new("p".to_string());
+        project.todos = vec![crate::model::Todo::new("t".to_string())];
+        let mut app = App::with_projects(vec![project]);
+        app.project_state.select(Some(0));
+        press(&mut app, KeyCode::Char('='));
+        assert!(app.single_pane);
+
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.active_panel, Panel::Todos);
+        assert_eq!(app.todo_state.selected(), Some(0));
+        // Todo 面板中 Enter 仍然打开子任务
+        press(&mut app, KeyCode::Enter);
+        assert!(app.view == View::Subtasks);
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.active_panel, Panel::Todos);
+
+        assert_eq!(press(&mut app, KeyCode::Esc), Outcome::None);
+        assert_eq!(app.active_panel, Panel::Projects);
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Backspace);
+        assert_eq!(app.active_panel, Panel::Projects);
+    }
+
+    #[test]
+    fn escape_closes_overlay_views() {
+        let mut app = App::with_projects(vec![]);
+        app.view = View::Stats;
+        assert_eq!(press(&mut app, KeyCode::Esc), Outcome::None);
+        assert!(app.view == View::Main);
+    }
+
+    #[test]
+    fn bulk_delete_asks_for_confirmation_once() {
+        let mut project = Project::new("p".to_string());
+        for title in ["a", "b", "c"] {
+            project
+                .todos
+                .push(crate::model::Todo::new(title.to_string()));
+        }
+        let mut app = App::with_projects(vec![project]);
+        app.active_panel = Panel::Todos;
+        for code in ['v', ' ', 'j', ' '] {
+            press(&mut app, KeyCode::Char(code));
+        }
+        assert_eq!(press(&mut app, KeyCode::Char('d')), Outcome::None);
+        assert!(app.view == View::ConfirmDeleteTodos);
+        // 取消后选择保留
+        assert_eq!(press(&mut app, KeyCode::Char('n')), Outcome::None);
+        assert!(app.view == View::Main);
+        assert_eq!(app.marked_todos.len(), 2);
+
+        press(&mut app, KeyCode::Char('d'));
+        assert_eq!(press(&mut app, KeyCode::Char('y')), Outcome::Save);
+        assert_eq!(app.projects[0].todos.len(), 1);
+        assert_eq!(app.trash.len(), 2);
+        assert!(!app.selecting);
+    }
+
+    #[test]
+    fn mouse_clicks_select_rows_and_double_click_completes() {
+        let mut work = Project::new("work".to_string());
+        for title in ["a", "b", "c"] {
+            work.todos.push(crate::model::Todo::new(title.to_string()));
+        }
+        let mut app = App::with_projects(vec![Project::new("inbox".to_string()), work]);
+        let mut terminal =
+            ratatui::Terminal::new(ratatui::backend::TestBackend::new(100, 20)).unwrap();
+        terminal.draw(|f| crate::ui::ui(f, &mut app)).unwrap();
+        let click = |app: &mut App, area: Rect, row: u16| {
+            let mouse = MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                column: area.x + 2,
+                row: area.y + 1 + row,
+                modifiers: KeyModifiers::NONE,
+            };
+            handle_mouse(app, mouse)
+        };
+
+        // 点在边框上不做任何事
+        let projects = app.project_list_area;
+        let border = Position::new(projects.x + 2, projects.y);
+        assert_eq!(list_row(projects, 0, border), None);
+        click(&mut app, projects, 1);
+        assert_eq!(app.project_state.selected(), Some(1));
+        terminal.draw(|f| crate::ui::ui(f, &mut app)).unwrap();
+
+        let todos = app.todo_list_area;
+        assert_eq!(click(&mut app, todos, 2), Outcome::None);
+        assert_eq!(app.active_panel, Panel::Todos);
+        assert_eq!(app.selected_todo_index(), Some(2));
+        assert_eq!(click(&mut app, todos, 2), Outcome::Save);
+        assert!(app.projects[1].todos[2].completed);
+        // 列表外的空行不改变选择
+        assert_eq!(click(&mut app, todos, 5), Outcome::None);
+        assert_eq!(app.selected_todo_index(), Some(2));
+
+        let scroll = MouseEvent {
+            kind: MouseEventKind::ScrollUp,
+            column: projects.x + 2,
+            row: projects.y + 1,
+            modifiers: KeyModifiers::NONE,
+        };
+        handle_mouse(&mut app, scroll);
+        assert_eq!(app.active_panel, Panel::Projects);
+        assert_eq!(app.project_state.selected(), Some(0));
+    }
+
+    #[test]
+    fn vim_style_counts_and_jumps_move_the_selection() {
+        let mut project = Project::new("p".to_string());
+        for i in 0..30 {
+            project.todos.push(crate::model::Todo::new(i.to_string()));
+        }
+        let mut app = App::with_projects(vec![project]);
+        app.active_panel = Panel::Todos;
+        app.todo_list_area = Rect::new(0, 0, 40, 12);
+        let keys = |app: &mut App, keys: &str| {
+            for c in keys.chars() {
+                press(app, KeyCode::Char(c));
+            }
+        };
+
+        keys(&mut app, "5j");
+        assert_eq!(app.todo_state.selected(), Some(5));
+        keys(&mut app, "12j");
+        assert_eq!(app.todo_state.selected(), Some(17));
+        // 数字前缀不会越过结尾，也不会回到开头
+        keys(&mut app, "99j");
+        assert_eq!(app.todo_state.selected(), Some(29));
+        keys(&mut app, "gg");
+        assert_eq!(app.todo_state.selected(), Some(0));
+        keys(&mut app, "G");
+        assert_eq!(app.todo_state.selected(), Some(29));
+        keys(&mut app, "3G");
+        assert_eq!(app.todo_state.selected(), Some(2));
+        keys(&mut app, "10gg");
+        assert_eq!(app.todo_state.selected(), Some(9));
+
+        let ctrl = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+        handle_key(&mut app, ctrl('d'));
+        assert_eq!(app.todo_state.selected(), Some(14));
+        handle_key(&mut app, ctrl('u'));
+        handle_key(&mut app, ctrl('u'));
+        assert_eq!(app.todo_state.selected(), Some(4));
+        // Ctrl+D 不会删除任务
+        assert_eq!(app.projects[0].todos.len(), 30);
+
+        // 其他按键清空未完成的序列
+        keys(&mut app, "3g");
+        press(&mut app, KeyCode::Esc);
+        keys(&mut app, "k");
+        assert_eq!(app.todo_state.selected(), Some(3));
+        assert_eq!(app.count_prefix, None);
+        assert!(!app.pending_g);
+    }
+
+    #[test]
+    fn command_palette_runs_actions_and_extra_commands_by_name() {
+        let mut app = App::with_projects(vec![Project::new("p".to_string())]);
+        let run = |app: &mut App, name: &str| {
+            press(app, KeyCode::Char(':'));
+            assert!(app.input_mode == InputMode::Commanding);
+            for c in name.chars() {
+                press(app, KeyCode::Char(c));
+            }
+            press(app, KeyCode::Enter)
+        };
+
+        assert_eq!(run(&mut app, "sort by due"), Outcome::Save);
+        assert_eq!(app.projects[0].sort_mode, crate::model::SortMode::DueDate);
+        assert!(app.input_mode == InputMode::Normal);
+
+        assert_eq!(run(&mut app, "回收站"), Outcome::None);
+        assert!(app.view == View::Trash);
+        press(&mut app, KeyCode::Esc);
+
+        assert_eq!(run(&mut app, "quit"), Outcome::Quit);
+        // 没有匹配的命令时什么也不做
+        assert_eq!(run(&mut app, "qqqzzz"), Outcome::None);
+        assert!(app.view == View::Main);
+    }
+
+    #[test]
+    fn ctrl_c_quits_even_while_typing() {
+        let mut app = App::with_projects(vec![Project::new("p".to_string())]);
+        press(&mut app, KeyCode::Char('a'));
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        assert_eq!(handle_key(&mut app, ctrl_c), Outcome::Quit);
+    }
+
+    #[test]
+    fn pasted_text_is_inserted_at_the_cursor_on_one_line() {
+        let mut app = App::with_projects(vec![Project::new("p".to_string())]);
+        handle_paste(&mut app, "ignored");
+        assert!(app.input.is_empty());
+
+        app.active_panel = Panel::Todos;
+        press(&mut app, KeyCode::Char('a'));
+        press(&mut app, KeyCode::Char('!'));
+        press(&mut app, KeyCode::Left);
+        handle_paste(&mut app, "read https://example.com/a\r\nlater\n");
+        assert_eq!(app.input, "read https://example.com/a later!");
+    }
+}
@@ -0,0 +1,504 @@
+use crate::paths;
+use crossterm::event::KeyCode;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// 普通模式下可绑定按键的操作
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Quit,
+    Save,
+    SwitchPanel,
+    MoveDown,
+    MoveUp,
+    ToggleComplete,
+    ToggleAllComplete,
+    AddItem,
+    Rename,
+    ToggleTimer,
+    Delete,
+    CycleSort,
+    ToggleDescription,
+    ScrollDescriptionDown,
+    ScrollDescriptionUp,
+    ShowReport,
+    ToggleFocus,
+    HideCompleted,
+    CollapseProjects,
+    SelectMode,
+    MoveTodos,
+    SetDueDate,
+    FilterTag,
+    OpenSubtasks,
+    CycleRecurrence,
+    EditDescription,
+    EditDescriptionExternal,
+    ShowTrash,
+    Search,
+    SearchNext,
+    SearchPrevious,
+    MoveTodoDown,
+    MoveTodoUp,
+    MoveToProject,
+    ShowBoard,
+    ArchiveCompleted,
+    ShowArchive,
+    ShowStats,
+    ShowSessions,
+    LogTime,
+    SetEstimate,
+    Pomodoro,
+    ExportMarkdown,
+    ShowHelp,
+    ToggleAscii,
+    ShowMessages,
+    Yank,
+    YankDetails,
+    ShowDetails,
+    ShowActivity,
+    ShowToday,
+    ShowCalendar,
+    ShowAgenda,
+    ZenMode,
+    SwitchWorkspace,
+    FoldProject,
+    UnfoldProject,
+    SetParentProject,
+    ArchiveProject,
+    ShowArchivedProjects,
+    Duplicate,
+    SaveTemplate,
+    ShowTemplates,
+    JumpToTop,
+    JumpToBottom,
+    CommandPalette,
+    ToggleDetailPane,
+    ShrinkProjects,
+    GrowProjects,
+    SinglePane,
+}
+
+impl Action {
+    // 配置文件中的名称，如 move_down
+    pub fn name(self) -> String {
+        serde_json::to_value(self)
+            .ok()
+            .and_then(|value| value.as_str().map(str::to_string))
+            .unwrap_or_default()
+    }
+
+    // 所有操作，按帮助中显示的顺序排列
+    pub const ALL: &[Action] = &[
+        Action::MoveDown,
+        Action::MoveUp,
+        Action::JumpToTop,
+        Action::JumpToBottom,
+        Action::SwitchPanel,
+        Action::AddItem,
+        Action::Rename,
+        Action::Duplicate,
+        Action::SaveTemplate,
+        Action::ShowTemplates,
+        Action::Delete,
+        Action::ToggleComplete,
+        Action::ToggleAllComplete,
+        Action::ToggleTimer,
+        Action::Pomodoro,
+        Action::LogTime,
+        Action::SetEstimate,
+        Action::ShowSessions,
+        Action::SetDueDate,
+        Action::CycleRecurrence,
+        Action::OpenSubtasks,
+        Action::EditDescription,
+        Action::EditDescriptionExternal,
+        Action::ToggleDescription,
+        Action::ScrollDescriptionDown,
+        Action::ScrollDescriptionUp,
+        Action::ShowDetails,
+        Action::ToggleDetailPane,
+        Action::SinglePane,
+        Action::Yank,
+        Action::YankDetails,
+        Action::MoveTodoDown,
+        Action::MoveTodoUp,
+        Action::MoveToProject,
+        Action::SelectMode,
+        Action::MoveTodos,
+        Action::CycleSort,
+        Action::FilterTag,
+        Action::Search,
+        Action::SearchNext,
+        Action::SearchPrevious,
+        Action::HideCompleted,
+        Action::ToggleFocus,
+        Action::ShrinkProjects,
+        Action::GrowProjects,
+        Action::ZenMode,
+        Action::SwitchWorkspace,
+        Action::ToggleAscii,
+        Action::CollapseProjects,
+        Action::FoldProject,
+        Action::UnfoldProject,
+        Action::SetParentProject,
+        Action::ArchiveCompleted,
+        Action::ShowArchive,
+        Action::ArchiveProject,
+        Action::ShowArchivedProjects,
+        Action::ShowTrash,
+        Action::ShowBoard,
+        Action::ShowToday,
+        Action::ShowCalendar,
+        Action::ShowAgenda,
+        Action::ShowReport,
+        Action::ShowStats,
+        Action::ExportMarkdown,
+        Action::Save,
+        Action::ShowMessages,
+        Action::ShowActivity,
+        Action::CommandPalette,
+        Action::ShowHelp,
+        Action::Quit,
+    ];
+
+    // 帮助中显示的说明
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::Quit => "退出",
+            Action::Save => "保存",
+            Action::SwitchPanel => "切换面板",
+            Action::MoveDown => "下移选择",
+            Action::MoveUp => "上移选择",
+            Action::JumpToTop => "跳到第一项",
+            Action::JumpToBottom => "跳到最后一项，有数字前缀时跳到第几项",
+            Action::CommandPalette => "命令面板",
+            Action::ToggleDetailPane => "显示/隐藏详情面板",
+            Action::ShrinkProjects => "缩小项目面板",
+            Action::GrowProjects => "放大项目面板",
+            Action::SinglePane => "单面板模式：一次只显示一个面板，Enter 进入项目，Esc 返回",
+            Action::ToggleComplete => "切换完成状态，多选模式下选中任务",
+            Action::ToggleAllComplete => "全部完成或全部恢复",
+            Action::AddItem => "添加项目或 Todo",
+            Action::Rename => "重命名",
+            Action::Duplicate => "复制 Todo 或项目",
+            Action::SaveTemplate => "把项目或 Todo 保存为模板",
+            Action::ShowTemplates => "从模板创建项目或 Todo",
+            Action::ToggleTimer => "开始或结束计时",
+            Action::Delete => "删除",
+            Action::CycleSort => "切换排序方式",
+            Action::ToggleDescription => "展开或收起描述",
+            Action::ScrollDescriptionDown => "向下滚动描述",
+            Action::ScrollDescriptionUp => "向上滚动描述",
+            Action::ShowReport => "时间报告",
+            Action::ToggleFocus => "专注模式",
+            Action::HideCompleted => "隐藏/显示已完成",
+            Action::CollapseProjects => "只显示选中的项目",
+            Action::SelectMode => "多选模式",
+            Action::MoveTodos => "把选中的任务移到其他项目",
+            Action::SetDueDate => "设置截止日期",
+            Action::FilterTag => "按标签筛选，多选模式下给选中的任务添加标签",
+            Action::OpenSubtasks => "子任务",
+            Action::CycleRecurrence => "切换重复周期",
+            Action::EditDescription => "编辑描述",
+            Action::EditDescriptionExternal => "用外部编辑器编辑描述",
+            Action::ShowTrash => "回收站",
+            Action::Search => "搜索",
+            Action::SearchNext => "下一个搜索结果",
+            Action::SearchPrevious => "上一个搜索结果",
+            Action::MoveTodoDown => "把 Todo 下移一位",
+            Action::MoveTodoUp => "把 Todo 上移一位",
+            Action::MoveToProject => "把 Todo 移到其他项目，在项目面板中把项目合并到其他项目",
+            Action::ShowBoard => "看板",
+            Action::ArchiveCompleted => "归档已完成的 Todo",
+            Action::ShowArchive => "归档",
+            Action::ShowStats => "统计图表",
+            Action::ShowSessions => "计时记录",
+            Action::LogTime => "补记耗时",
+            Action::SetEstimate => "设置预计耗时",
+            Action::Pomodoro => "番茄钟",
+            Action::ExportMarkdown => "导出为 Markdown",
+            Action::ShowHelp => "显示所有按键",
+            Action::ShowMessages => "最近的提示记录",
+            Action::Yank => "复制标题到剪贴板",
+            Action::ShowDetails => "查看详情",
+            Action::ShowActivity => "活动记录",
+            Action::ShowToday => "今日回顾",
+            Action::ShowCalendar => "月历",
+            Action::ShowAgenda => "日程",
+            Action::ZenMode => "沉浸模式",
+            Action::SwitchWorkspace => "切换工作区",
+            Action::FoldProject => "收起子项目",
+            Action::UnfoldProject => "展开子项目",
+            Action::SetParentProject => "设置上级项目",
+            Action::ArchiveProject => "归档项目",
+            Action::ShowArchivedProjects => "查看归档的项目",
+            Action::YankDetails => "复制标题、耗时和描述到剪贴板",
+            Action::ToggleAscii => "切换 emoji 和 ASCII 图标",
+        }
+    }
+}
+
+// 按键绑定，从 keys.json 加载，未配置的操作使用默认按键
+#[derive(Clone)]
+pub struct KeyBindings {
+    bindings: HashMap<Action, Vec<KeyCode>>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        use Action::*;
+        let bindings = [
+            (Quit, vec![KeyCode::Char('q')]),
+            (Save, vec![KeyCode::Char('s')]),
+            (SwitchPanel, vec![KeyCode::Tab]),
+            (MoveDown, vec![KeyCode::Char('j'), KeyCode::Down]),
+            (MoveUp, vec![KeyCode::Char('k'), KeyCode::Up]),
+            (JumpToTop, vec![KeyCode::Home]),
+            (JumpToBottom, vec![KeyCode::Char('G'), KeyCode::End]),
+            (CommandPalette, vec![KeyCode::Char(':')]),
+            (ToggleDetailPane, vec![KeyCode::Char('|')]),
+            (ShrinkProjects, vec![KeyCode::Char('[')]),
+            (GrowProjects, vec![KeyCode::Char(']')]),
+            (SinglePane, vec![KeyCode::Char('=')]),
+            (ToggleComplete, vec![KeyCode::Char(' ')]),
+            (ToggleAllComplete, vec![KeyCode::Char('A')]),
+            (AddItem, vec![KeyCode::Char('a')]),
+            (Rename, vec![KeyCode::Char('r')]),
+            (ToggleTimer, vec![KeyCode::Char('t')]),
+            (Delete, vec![KeyCode::Char('d')]),
+            (CycleSort, vec![KeyCode::Char('o')]),
+            (ToggleDescription, vec![KeyCode::Char('x')]),
+            (ScrollDescriptionDown, vec![KeyCode::PageDown]),
+            (ScrollDescriptionUp, vec![KeyCode::PageUp]),
+            (ShowReport, vec![KeyCode::Char('R')]),
+            (ToggleFocus, vec![KeyCode::Char('f')]),
+            (HideCompleted, vec![KeyCode::Char('u')]),
+            (CollapseProjects, vec![KeyCode::Char('c')]),
+            (SelectMode, vec![KeyCode::Char('v')]),
+            (MoveTodos, vec![KeyCode::Char('m')]),
+            (SetDueDate, vec![KeyCode::Char('D')]),
+            (FilterTag, vec![KeyCode::Char('#')]),
+            (OpenSubtasks, vec![KeyCode::Enter]),
+            (CycleRecurrence, vec![KeyCode::Char('p')]),
+            (EditDescription, vec![KeyCode::Char('e')]),
+            (EditDescriptionExternal, vec![KeyCode::Char('E')]),
+            (ShowTrash, vec![KeyCode::Char('T')]),
+            (Search, vec![KeyCode::Char('/')]),
+            (SearchNext, vec![KeyCode::Char('n')]),
+            (SearchPrevious, vec![KeyCode::Char('N')]),
+            (MoveTodoDown, vec![KeyCode::Char('J')]),
+            (MoveTodoUp, vec![KeyCode::Char('K')]),
+            (MoveToProject, vec![KeyCode::Char('M')]),
+            (ShowBoard, vec![KeyCode::Char('b')]),
+            (ArchiveCompleted, vec![KeyCode::Char('X')]),
+            (ShowArchive, vec![KeyCode::Char('Z')]),
+            (ShowStats, vec![KeyCode::Char('S')]),
+            (ShowSessions, vec![KeyCode::Char('L')]),
+            (LogTime, vec![KeyCode::Char('+')]),
+            (SetEstimate, vec![KeyCode::Char('i')]),
+            (Pomodoro, vec![KeyCode::Char('P')]),
+            (ExportMarkdown, vec![KeyCode::Char('W')]),
+            (ShowHelp, vec![KeyCode::Char('?')]),
+            (ToggleAscii, vec![KeyCode::Char('U')]),
+            (ShowMessages, vec![KeyCode::Char('H')]),
+            (Yank, vec![KeyCode::Char('y')]),
+            (YankDetails, vec![KeyCode::Char('Y')]),
+            (ShowDetails, vec![KeyCode::Char('I')]),
+            (ShowActivity, vec![KeyCode::Char('@')]),
+            (ShowToday, vec![KeyCode::Char('V')]),
+            (ShowCalendar, vec![KeyCode::Char('C')]),
+            (ShowAgenda, vec![KeyCode::Char('O')]),
+            (ZenMode, vec![KeyCode::Char('F')]),
+            (SwitchWorkspace, vec![KeyCode::Char('w')]),
+            (FoldProject, vec![KeyCode::Left]),
+            (UnfoldProject, vec![KeyCode::Right]),
+            (SetParentProject, vec![KeyCode::Char('B')]),
+            (ArchiveProject, vec![KeyCode::Char('l')]),
+            (ShowArchivedProjects, vec![KeyCode::Char('Q')]),
+            (Duplicate, vec![KeyCode::Char('*')]),
+            (SaveTemplate, vec![KeyCode::Char('>')]),
+            (ShowTemplates, vec![KeyCode::Char('<')]),
+        ];
+        Self {
+            bindings: bindings.into_iter().collect(),
+        }
+    }
+}
+
+impl KeyBindings {
+    // 加载按键配置文件，文件不存在时使用默认绑定
+    pub fn load() -> Result<KeyBindings, String> {
+        match std::fs::read_to_string(Self::get_keys_file_path()) {
+            Ok(content) => Self::from_json(&content),
+            Err(_) => Ok(KeyBindings::default()),
+        }
+    }
+
+    // 解析形如 {"move_down": ["n", "down"]} 的配置，覆盖对应操作的默认按键
+    pub fn from_json(content: &str) -> Result<KeyBindings, String> {
+        let config: HashMap<Action, Vec<String>> =
+            serde_json::from_str(content).map_err(|e| format!("按键配置格式错误：{}", e))?;
+
+        let mut keys = KeyBindings::default();
+        for (action, names) in config {
+            let codes = names
+                .iter()
+                .map(|name| parse_key(name).ok_or_else(|| format!("无法识别的按键：{}", name)))
+                .collect::<Result<Vec<_>, _>>()?;
+            keys.bindings.insert(action, codes);
+        }
+        match keys.conflicts().first() {
+            Some((code, actions)) => Err(format!(
+                "按键冲突：{} 同时绑定了 {}",
+                key_name(*code),
+                actions
+                    .iter()
+                    .map(|action| action.description())
+                    .collect::<Vec<_>>()
+                    .join("、")
+            )),
+            None => Ok(keys),
+        }
+    }
+
+    // 绑定到多个操作的按键，以及这些操作
+    pub fn conflicts(&self) -> Vec<(KeyCode, Vec<Action>)> {
+        let mut conflicts: Vec<(KeyCode, Vec<Action>)> = Vec::new();
+        for &action in Action::ALL {
+            for code in self.bindings.get(&action).into_iter().flatten() {
+                match conflicts.iter_mut().find(|(c, _)| c == code) {
+                    Some((_, actions)) => actions.push(action),
+                    None => conflicts.push((*code, vec![action])),
+                }
+            }
+        }
+        conflicts.retain(|(_, actions)| actions.len() > 1);
+        conflicts
+    }
+
+    // 获取按键配置文件路径，放在系统配置目录中
+    pub fn get_keys_file_path() -> String {
+        paths::config_file("keys.json")
+    }
+
+    // 查找按键对应的操作
+    pub fn action_for(&self, code: KeyCode) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(_, codes)| codes.contains(&code))
+            .map(|(action, _)| *action)
+    }
+
+    // 操作的所有按键，用 / 分隔，用于帮助中的按键列表
+    pub fn labels(&self, action: Action) -> String {
+        match self.bindings.get(&action) {
+            Some(codes) if !codes.is_empty() => codes
+                .iter()
+                .map(|code| key_name(*code))
+                .collect::<Vec<_>>()
+                .join("/"),
+            _ => "-".to_string(),
+        }
+    }
+
+    // 操作的第一个按键的显示名称，用于帮助信息
+    pub fn label(&self, action: Action) -> String {
+        self.bindings
+            .get(&action)
+            .and_then(|codes| codes.first())
+            .map_or_else(|| "-".to_string(), |code| key_name(*code))
+    }
+}
+
+// 把配置中的按键名称解析为 KeyCode
+fn parse_key(name: &str) -> Option<KeyCode> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c));
+    }
+
+    let code = match name.to_lowercase().as_str() {
+        "space" => KeyCode::Char(' '),
+        "tab" => KeyCode::Tab,
+        "enter" => KeyCode::Enter,
+        "esc" => KeyCode::Esc,
+        "backspace" => KeyCode::Backspace,
+        "delete" => KeyCode::Delete,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        other => {
+            let n = other.strip_prefix('f')?.parse().ok()?;
+            KeyCode::F(n)
+        }
+    };
+    Some(code)
+}
+
+// 按键的显示名称
+fn key_name(code: KeyCode) -> String {
+    match code {
+        KeyCode::Char(' ') => "空格".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::Up => "↑".to_string(),
+        KeyCode::Down => "↓".to_string(),
+        KeyCode::Left => "←".to_string(),
+        KeyCode::Right => "→".to_string(),
+        KeyCode::F(n) => format!("F{}", n),
+        other => format!("{:?}", other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_match_builtin_keys() {
+        let keys = KeyBindings::default();
+        assert_eq!(keys.action_for(KeyCode::Char('j')), Some(Action::MoveDown));
+        assert_eq!(keys.action_for(KeyCode::Down), Some(Action::MoveDown));
+        assert_eq!(
+            keys.action_for(KeyCode::Char(' ')),
+            Some(Action::ToggleComplete)
+        );
+        assert_eq!(keys.action_for(KeyCode::Char('z')), None);
+        assert_eq!(keys.label(Action::SwitchPanel), "Tab");
+    }
+
+    #[test]
+    fn config_overrides_only_listed_actions() {
+        let keys =
+            KeyBindings::from_json(r#"{"move_down": ["h", "down"], "quit": ["F10"]}"#).unwrap();
+        assert_eq!(keys.action_for(KeyCode::Char('h')), Some(Action::MoveDown));
+        assert_eq!(keys.action_for(KeyCode::Char('j')), None);
+        assert_eq!(keys.action_for(KeyCode::F(10)), Some(Action::Quit));
+        assert_eq!(keys.action_for(KeyCode::Char('k')), Some(Action::MoveUp));
+        assert_eq!(keys.label(Action::MoveDown), "h");
+    }
+
+    #[test]
+    fn every_action_has_a_default_key_without_conflicts() {
+        let keys = KeyBindings::default();
+        assert!(keys.conflicts().is_empty());
+        assert!(Action::ALL.iter().all(|&action| keys.label(action) != "-"));
+        assert_eq!(keys.labels(Action::MoveDown), "j/↓");
+
+        let err = KeyBindings::from_json(r#"{"move_down": ["k"]}"#)
+            .err()
+            .unwrap();
+        assert_eq!(err, "按键冲突：k 同时绑定了 下移选择、上移选择");
+    }
+
+    #[test]
+    fn unknown_key_or_action_is_an_error() {
+        assert!(KeyBindings::from_json(r#"{"move_down": ["hyper"]}"#).is_err());
+        assert!(KeyBindings::from_json(r#"{"fly": ["x"]}"#).is_err());
+    }
+}
@@ -0,0 +1,27 @@
+pub mod activity;
+pub mod app;
+pub mod backup;
+pub mod caldav;
+pub mod cli;
+pub mod config;
+pub mod dates;
+pub mod github;
+pub mod ical;
+pub mod input;
+pub mod keys;
+pub mod message;
+pub mod model;
+pub mod palette;
+pub mod paths;
+pub mod pomodoro;
+pub mod report;
+pub mod runner;
+pub mod state;
+pub mod storage;
+pub mod taskwarrior;
+pub mod text_input;
+pub mod theme;
+pub mod ui;
+
+pub use app::App;
+pub use model::{Project, Todo};
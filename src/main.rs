@@ -1,793 +1,295 @@
+use clap::Parser;
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    cursor,
+    event::{
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        Event,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use ratatui::{
-    backend::{Backend, CrosstermBackend},
-    layout::{Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
-    Frame, Terminal,
+use ratatui::{backend::CrosstermBackend, Terminal};
+use s_todo::{
+    app::App,
+    cli,
+    model::base64,
+    paths,
+    runner::{run_app, Host, Input},
 };
-use serde::{Deserialize, Serialize};
+use signal_hook::consts::TERM_SIGNALS;
 use std::{
+    collections::hash_map::RandomState,
     error::Error,
-    io,
-    time::{SystemTime, UNIX_EPOCH},
+    fs::{File, OpenOptions},
+    hash::{BuildHasher, Hasher},
+    io::{self, Write},
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    process::Command,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
 };
 
-#[derive(Clone, Serialize, Deserialize)]
-struct Todo {
-    title: String,
-    description: String,
-    completed: bool,
-    // 时间记录字段
-    start_time: Option<u64>, // 开始时间（时间戳）
-    end_time: Option<u64>,   // 结束时间（时间戳）
-    total_duration: u64,     // 总耗时（秒）
-}
-
-impl Todo {
-    fn new(title: String) -> Self {
-        Self {
-            title,
-            description: String::new(),
-            completed: false,
-            start_time: None,
-            end_time: None,
-            total_duration: 0,
+fn main() -> Result<(), Box<dyn Error>> {
+    let cli = cli::Cli::parse();
+    if let Some(path) = cli.data_file {
+        paths::set_data_file(path);
+    }
+    if let Some(workspace) = cli.workspace {
+        if let Err(err) = paths::set_workspace(Some(workspace)) {
+            eprintln!("{}", err);
+            std::process::exit(1);
         }
     }
 
-    // 开始工作 - 记录开始时间
-    fn start_work(&mut self) {
-        self.start_time = Some(
-            SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
-        );
-        self.end_time = None; // 清除结束时间
+    // 带子命令时作为命令行工具运行，不进入终端界面
+    if let Some(command) = cli.command {
+        match cli::run(command) {
+            Ok(output) => println!("{}", output),
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
     }
 
-    // 结束工作 - 记录结束时间并计算耗时
-    fn end_work(&mut self) {
-        if let Some(start) = self.start_time {
-            let now = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs();
+    // 发生 panic 时先恢复终端再打印信息，否则终端会停留在原始模式
+    install_panic_hook(|| {
+        let _ = restore_terminal();
+    });
 
-            self.end_time = Some(now);
-            let session_duration = now - start;
-            self.total_duration += session_duration;
-        }
+    // 收到 SIGTERM、SIGINT 等终止信号时只记下来，由主循环正常退出并保存
+    let terminated = Arc::new(AtomicBool::new(false));
+    for signal in TERM_SIGNALS {
+        signal_hook::flag::register(*signal, Arc::clone(&terminated))?;
     }
 
-    // 切换工作状态
-    fn toggle_work(&mut self) {
-        if self.start_time.is_some() && self.end_time.is_none() {
-            // 正在工作，结束工作
-            self.end_work();
-        } else {
-            // 没有工作或已结束，开始新的工作
-            self.start_work();
-        }
-    }
+    // 设置终端
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(
+        stdout,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
 
-    // 检查是否正在工作
-    fn is_working(&self) -> bool {
-        self.start_time.is_some() && self.end_time.is_none()
-    }
+    let mut app = App::load();
 
-    // 格式化时间显示
-    fn format_duration(&self) -> String {
-        let total_seconds = self.total_duration;
+    let mut host = TerminalHost { terminated };
+    let res = panic::catch_unwind(AssertUnwindSafe(|| {
+        run_app(&mut terminal, &mut app, &mut host)
+    }));
 
-        if total_seconds == 0 {
-            return String::new();
-        }
+    // 无论正常退出、出错还是 panic 都保存，正在进行的计时保留到下次启动时处理
+    // panic 时内存中的数据可能不完整，保存前会轮换备份，上一次保存的数据仍在备份中
+    let saved = app.save_data();
+    // 记住选中位置，下次启动时恢复
+    let _ = app.ui_state().save();
+
+    restore_terminal()?;
 
-        let months = total_seconds / 2592000; // 30天 * 24小时 * 60分钟 * 60秒 = 2592000秒 ≈ 1个月
-        let days = (total_seconds % 2592000) / 86400; // 86400 秒 = 1 天
-        let hours = (total_seconds % 86400) / 3600;
-        let minutes = (total_seconds % 3600) / 60;
-        let seconds = total_seconds % 60;
+    // 保存失败时修改只在内存中，退出后在终端中提示
+    if !saved && !app.read_only {
+        if let Some(message) = &app.message {
+            eprintln!("{}", message.text);
+        }
+    }
+    if app.has_running_timer() {
+        println!("计时仍在进行，下次启动时可以选择保留、截断或丢弃关闭期间的时间");
+    }
 
-        match (months, days, hours, minutes, seconds) {
-            // 有月份的情况
-            (mo, d, h, _, _) if mo > 0 => match (d, h) {
-                (d, h) if d > 0 && h > 0 => format!("{}mo {}d {}h", mo, d, h),
-                (d, _) if d > 0 => format!("{}mo {}d", mo, d),
-                (_, h) if h > 0 => format!("{}mo {}h", mo, h),
-                _ => format!("{}mo", mo),
-            },
-            // 有天数的情况
-            (0, d, h, m, _) if d > 0 => match (h, m) {
-                (h, m) if h > 0 && m > 0 => format!("{}d {}h {}m", d, h, m),
-                (h, _) if h > 0 => format!("{}d {}h", d, h),
-                (_, m) if m > 0 => format!("{}d {}m", d, m),
-                _ => format!("{}d", d),
-            },
-            // 有小时的情况
-            (0, 0, h, m, s) if h > 0 => match (m, s) {
-                (m, s) if m > 0 && s > 0 => format!("{}h {}m {}s", h, m, s),
-                (m, _) if m > 0 => format!("{}h {}m", h, m),
-                (_, s) if s > 0 => format!("{}h {}s", h, s),
-                _ => format!("{}h", h),
-            },
-            // 有分钟的情况
-            (0, 0, 0, m, s) if m > 0 => {
-                if s > 0 {
-                    format!("{}m {}s", m, s)
-                } else {
-                    format!("{}m", m)
-                }
+    match res {
+        Ok(Ok(())) => {}
+        Ok(Err(err)) => println!("{:?}", err),
+        // panic 信息已由 panic hook 打印，继续展开以非零状态退出
+        Err(payload) => {
+            if saved {
+                eprintln!("程序异常退出，已保存数据");
             }
-            // 只有秒的情况
-            (0, 0, 0, 0, s) if s > 0 => format!("{}s", s),
-            // 默认情况（应该不会到达这里）
-            _ => String::new(),
+            panic::resume_unwind(payload);
         }
     }
-}
 
-#[derive(Clone, Serialize, Deserialize)]
-struct Project {
-    name: String,
-    todos: Vec<Todo>,
+    Ok(())
 }
 
-#[derive(Serialize, Deserialize)]
-struct AppData {
-    projects: Vec<Project>,
+// 在默认的 panic hook 之前先执行 restore
+fn install_panic_hook(restore: impl Fn() + Send + Sync + 'static) {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        restore();
+        default_hook(info);
+    }));
 }
 
-struct App {
-    projects: Vec<Project>,
-    project_state: ListState,
-    todo_state: ListState,
-    active_panel: Panel,
-    input_mode: InputMode,
-    input: String,
+// 恢复终端：退出原始模式和备用屏幕，显示光标
+fn restore_terminal() -> io::Result<()> {
+    disable_raw_mode()?;
+    leave_screen(&mut io::stdout())
 }
 
-#[derive(PartialEq)]
-enum Panel {
-    Projects,
-    Todos,
+// 退出备用屏幕，关闭鼠标和括号粘贴，显示光标
+fn leave_screen(out: &mut impl Write) -> io::Result<()> {
+    execute!(
+        out,
+        LeaveAlternateScreen,
+        DisableMouseCapture,
+        DisableBracketedPaste,
+        cursor::Show
+    )
 }
 
-#[derive(PartialEq)]
-enum InputMode {
-    Normal,
-    AddingProject,
-    AddingTodo,
-    RenamingProject,
-    RenamingTodo,
+// 在后台线程发送桌面通知，避免阻塞界面，没有通知服务时忽略错误
+fn notify_desktop(body: String) {
+    std::thread::spawn(move || {
+        let _ = notify_rust::Notification::new()
+            .appname("s_todo")
+            .summary("s_todo")
+            .body(&body)
+            .show();
+    });
 }
 
-impl App {
-    fn new() -> App {
-        let mut app = App {
-            projects: Self::load_data(),
-            project_state: ListState::default(),
-            todo_state: ListState::default(),
-            active_panel: Panel::Projects,
-            input_mode: InputMode::Normal,
-            input: String::new(),
-        };
-
-        if !app.projects.is_empty() {
-            app.project_state.select(Some(0));
-            app.todo_state.select(Some(0));
-        }
-        app
-    }
-
-    // 加载数据
-    fn load_data() -> Vec<Project> {
-        let data_file = Self::get_data_file_path();
-
-        if let Ok(content) = std::fs::read_to_string(&data_file) {
-            if let Ok(app_data) = serde_json::from_str::<AppData>(&content) {
-                return app_data.projects;
-            }
-        }
-
-        // 如果加载失败，返回默认数据
-        vec![
-            Project {
-                name: "工作项目".to_string(),
-                todos: vec![Todo::new("完成报告".to_string())],
-            },
-            Project {
-                name: "个人学习".to_string(),
-                todos: vec![Todo::new("学习 Rust".to_string())],
-            },
-        ]
-    }
-
-    // 保存数据
-    fn save_data(&self) {
-        let app_data = AppData {
-            projects: self.projects.clone(),
-        };
-
-        let data_file = Self::get_data_file_path();
-
-        // 确保目录存在
-        if let Some(parent) = std::path::Path::new(&data_file).parent() {
-            let _ = std::fs::create_dir_all(parent);
-        }
+// 在真实终端中运行时的输入和外部交互
+struct TerminalHost {
+    terminated: Arc<AtomicBool>, // 收到了终止信号
+}
 
-        if let Ok(json) = serde_json::to_string_pretty(&app_data) {
-            let _ = std::fs::write(&data_file, json);
+impl Host for TerminalHost {
+    fn next_input(&mut self, timeout: Duration) -> io::Result<Input> {
+        if self.terminated.load(Ordering::Relaxed) {
+            return Ok(Input::End);
         }
-    }
-
-    // 获取数据文件路径
-    fn get_data_file_path() -> String {
-        if let Some(home) = std::env::var_os("HOME") {
-            format!("{}/.config/s_todo/data.json", home.to_string_lossy())
-        } else {
-            "./s_todo_data.json".to_string()
+        // 等待输入时被信号打断不算错误，下一轮检查是否需要退出
+        match event::poll(timeout) {
+            Ok(true) => {}
+            Ok(false) => return Ok(Input::Tick),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => return Ok(Input::Tick),
+            Err(err) => return Err(err),
         }
+        Ok(match event::read()? {
+            Event::Key(key) => Input::Key(key),
+            Event::Mouse(mouse) => Input::Mouse(mouse),
+            Event::Paste(text) => Input::Paste(text),
+            Event::Resize(_, _) => Input::Resize,
+            _ => Input::Tick,
+        })
     }
 
-    fn get_current_project(&self) -> Option<&Project> {
-        self.project_state.selected().map(|i| &self.projects[i])
+    fn bell(&mut self) -> io::Result<()> {
+        io::stdout().write_all(b"\x07")?;
+        io::stdout().flush()
     }
 
-    fn get_current_todos(&self) -> Vec<&Todo> {
-        if let Some(project) = self.get_current_project() {
-            project.todos.iter().collect()
-        } else {
-            vec![]
-        }
+    fn notify(&mut self, body: String) {
+        notify_desktop(body);
     }
 
-    // 获取当前选中的 todo（可变引用）
-    fn get_current_todo_mut(&mut self) -> Option<&mut Todo> {
-        if let (Some(project_idx), Some(todo_idx)) =
-            (self.project_state.selected(), self.todo_state.selected())
-        {
-            self.projects
-                .get_mut(project_idx)
-                .and_then(|project| project.todos.get_mut(todo_idx))
-        } else {
-            None
-        }
+    // 用 OSC 52 控制序列让终端写入剪贴板，SSH 会话中也能复制到本地
+    fn copy(&mut self, text: &str) -> io::Result<()> {
+        write!(io::stdout(), "\x1b]52;c;{}\x07", base64(text.as_bytes()))?;
+        io::stdout().flush()
     }
 
-    // 切换当前 todo 的计时状态
-    fn toggle_current_todo_timer(&mut self) -> bool {
-        self.get_current_todo_mut()
-            .filter(|todo| !todo.completed) // 只有未完成的任务才能计时
-            .map(|todo| {
-                todo.toggle_work();
-                true
-            })
-            .unwrap_or(false)
+    // 暂停界面运行 $EDITOR，编辑器运行失败时只返回提示，只有恢复终端失败才返回错误
+    fn edit(&mut self, text: &str) -> io::Result<Result<String, String>> {
+        restore_terminal()?;
+        let edited = create_temp_file().and_then(|(path, mut file)| {
+            let edited = file
+                .write_all(text.as_bytes())
+                .and_then(|_| run_editor(&path))
+                .and_then(|_| std::fs::read_to_string(&path));
+            drop(file);
+            let _ = std::fs::remove_file(&path);
+            edited
+        });
+        enable_raw_mode()?;
+        execute!(
+            io::stdout(),
+            EnterAlternateScreen,
+            EnableMouseCapture,
+            EnableBracketedPaste
+        )?;
+
+        Ok(edited.map_err(|err| format!("编辑器运行失败：{}", err)))
     }
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
-    // 设置终端
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
-
-    let app = App::new();
-    let res = run_app(&mut terminal, app);
-
-    // 恢复终端
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
-
-    if let Err(err) = res {
-        println!("{:?}", err);
-    }
-
-    Ok(())
-}
-
-fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<()> {
+// 在临时目录新建只有自己能读写的描述文件，文件名随机且不覆盖已有文件，
+// 避免其他用户提前放好同名文件或符号链接
+fn create_temp_file() -> io::Result<(PathBuf, File)> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
     loop {
-        terminal.draw(|f| ui(f, &mut app))?;
-
-        if let Event::Key(key) = event::read()? {
-            let mut should_save = false;
-
-            match app.input_mode {
-                InputMode::Normal => match key.code {
-                    KeyCode::Char('q') => {
-                        app.save_data(); // 退出前保存
-                        return Ok(());
-                    }
-                    KeyCode::Char('s') => {
-                        app.save_data();
-                        continue;
-                    }
-                    KeyCode::Tab => {
-                        app.active_panel = match app.active_panel {
-                            Panel::Projects => {
-                                // 切换到 Todo 面板时，确保有选中项
-                                let todos = app.get_current_todos();
-                                if !todos.is_empty() && app.todo_state.selected().is_none() {
-                                    app.todo_state.select(Some(0));
-                                }
-                                Panel::Todos
-                            }
-                            Panel::Todos => {
-                                // 切换到项目面板时，确保有选中项
-                                if !app.projects.is_empty()
-                                    && app.project_state.selected().is_none()
-                                {
-                                    app.project_state.select(Some(0));
-                                }
-                                Panel::Projects
-                            }
-                        };
-                    }
-                    KeyCode::Char('j') | KeyCode::Down => match app.active_panel {
-                        Panel::Projects => {
-                            let i = match app.project_state.selected() {
-                                Some(i) => {
-                                    if i >= app.projects.len() - 1 {
-                                        0
-                                    } else {
-                                        i + 1
-                                    }
-                                }
-                                None => 0,
-                            };
-                            app.project_state.select(Some(i));
-                            app.todo_state.select(Some(0));
-                        }
-                        Panel::Todos => {
-                            let todos = app.get_current_todos();
-                            if !todos.is_empty() {
-                                let i = match app.todo_state.selected() {
-                                    Some(i) => {
-                                        if i >= todos.len() - 1 {
-                                            0
-                                        } else {
-                                            i + 1
-                                        }
-                                    }
-                                    None => 0,
-                                };
-                                app.todo_state.select(Some(i));
-                            }
-                        }
-                    },
-                    KeyCode::Char('k') | KeyCode::Up => match app.active_panel {
-                        Panel::Projects => {
-                            let i = match app.project_state.selected() {
-                                Some(i) => {
-                                    if i == 0 {
-                                        app.projects.len() - 1
-                                    } else {
-                                        i - 1
-                                    }
-                                }
-                                None => 0,
-                            };
-                            app.project_state.select(Some(i));
-                            app.todo_state.select(Some(0));
-                        }
-                        Panel::Todos => {
-                            let todos = app.get_current_todos();
-                            if !todos.is_empty() {
-                                let i = match app.todo_state.selected() {
-                                    Some(i) => {
-                                        if i == 0 {
-                                            todos.len() - 1
-                                        } else {
-                                            i - 1
-                                        }
-                                    }
-                                    None => 0,
-                                };
-                                app.todo_state.select(Some(i));
-                            }
-                        }
-                    },
-                    KeyCode::Char(' ') => {
-                        if app.active_panel == Panel::Todos {
-                            if let (Some(project_idx), Some(todo_idx)) =
-                                (app.project_state.selected(), app.todo_state.selected())
-                            {
-                                let todo = &mut app.projects[project_idx].todos[todo_idx];
-
-                                // 如果正在计时且要标记为完成，自动结束计时
-                                if todo.is_working() && !todo.completed {
-                                    todo.end_work();
-                                }
-
-                                // 切换完成状态
-                                todo.completed = !todo.completed;
-                                should_save = true;
-                            }
-                        }
-                    }
-                    KeyCode::Char('a') => {
-                        app.input_mode = match app.active_panel {
-                            Panel::Projects => InputMode::AddingProject,
-                            Panel::Todos => InputMode::AddingTodo,
-                        };
-                        app.input.clear();
-                    }
-                    KeyCode::Char('t') => {
-                        // 切换当前 todo 的计时状态
-                        if app.active_panel == Panel::Todos && app.toggle_current_todo_timer() {
-                            should_save = true;
-                        }
-                    }
-                    KeyCode::Char('r') => {
-                        // 重命名当前选中的项目或 todo
-                        match app.active_panel {
-                            Panel::Projects => {
-                                if let Some(idx) = app.project_state.selected() {
-                                    app.input_mode = InputMode::RenamingProject;
-                                    app.input = app.projects[idx].name.clone();
-                                }
-                            }
-                            Panel::Todos => {
-                                if let (Some(project_idx), Some(todo_idx)) =
-                                    (app.project_state.selected(), app.todo_state.selected())
-                                {
-                                    app.input_mode = InputMode::RenamingTodo;
-                                    app.input =
-                                        app.projects[project_idx].todos[todo_idx].title.clone();
-                                }
-                            }
-                        }
-                    }
-                    KeyCode::Char('d') => match app.active_panel {
-                        Panel::Projects => {
-                            if let Some(idx) = app.project_state.selected() {
-                                if idx < app.projects.len() {
-                                    app.projects.remove(idx);
-                                    if app.projects.is_empty() {
-                                        app.project_state.select(None);
-                                    } else if idx >= app.projects.len() {
-                                        app.project_state.select(Some(app.projects.len() - 1));
-                                    }
-                                    should_save = true;
-                                }
-                            }
-                        }
-                        Panel::Todos => {
-                            if let (Some(project_idx), Some(todo_idx)) =
-                                (app.project_state.selected(), app.todo_state.selected())
-                            {
-                                if todo_idx < app.projects[project_idx].todos.len() {
-                                    app.projects[project_idx].todos.remove(todo_idx);
-                                    let todos_len = app.projects[project_idx].todos.len();
-                                    if todos_len == 0 {
-                                        app.todo_state.select(None);
-                                    } else if todo_idx >= todos_len {
-                                        app.todo_state.select(Some(todos_len - 1));
-                                    }
-                                    should_save = true;
-                                }
-                            }
-                        }
-                    },
-                    _ => {}
-                },
-                InputMode::AddingProject => match key.code {
-                    KeyCode::Enter => {
-                        if !app.input.is_empty() {
-                            app.projects.push(Project {
-                                name: app.input.clone(),
-                                todos: vec![],
-                            });
-                            // 自动选中新添加的项目
-                            let new_index = app.projects.len() - 1;
-                            app.project_state.select(Some(new_index));
-                            // 清空 todo 选择，因为新项目没有 todo
-                            app.todo_state.select(None);
-                            app.input.clear();
-                            should_save = true;
-                        }
-                        app.input_mode = InputMode::Normal;
-                    }
-                    KeyCode::Char(c) => app.input.push(c),
-                    KeyCode::Backspace => {
-                        app.input.pop();
-                    }
-                    KeyCode::Esc => app.input_mode = InputMode::Normal,
-                    _ => {}
-                },
-                InputMode::AddingTodo => match key.code {
-                    KeyCode::Enter => {
-                        if !app.input.is_empty() {
-                            if let Some(project_idx) = app.project_state.selected() {
-                                app.projects[project_idx]
-                                    .todos
-                                    .push(Todo::new(app.input.clone()));
-                                // 自动选中新添加的 todo
-                                let new_todo_index = app.projects[project_idx].todos.len() - 1;
-                                app.todo_state.select(Some(new_todo_index));
-                                should_save = true;
-                            }
-                            app.input.clear();
-                        }
-                        app.input_mode = InputMode::Normal;
-                    }
-                    KeyCode::Char(c) => app.input.push(c),
-                    KeyCode::Backspace => {
-                        app.input.pop();
-                    }
-                    KeyCode::Esc => app.input_mode = InputMode::Normal,
-                    _ => {}
-                },
-                InputMode::RenamingProject => match key.code {
-                    KeyCode::Enter => {
-                        if !app.input.is_empty() {
-                            if let Some(idx) = app.project_state.selected() {
-                                app.projects[idx].name = app.input.clone();
-                                should_save = true;
-                            }
-                            app.input.clear();
-                        }
-                        app.input_mode = InputMode::Normal;
-                    }
-                    KeyCode::Char(c) => app.input.push(c),
-                    KeyCode::Backspace => {
-                        app.input.pop();
-                    }
-                    KeyCode::Esc => app.input_mode = InputMode::Normal,
-                    _ => {}
-                },
-                InputMode::RenamingTodo => match key.code {
-                    KeyCode::Enter => {
-                        if !app.input.is_empty() {
-                            if let (Some(project_idx), Some(todo_idx)) =
-                                (app.project_state.selected(), app.todo_state.selected())
-                            {
-                                app.projects[project_idx].todos[todo_idx].title = app.input.clone();
-                                should_save = true;
-                            }
-                            app.input.clear();
-                        }
-                        app.input_mode = InputMode::Normal;
-                    }
-                    KeyCode::Char(c) => app.input.push(c),
-                    KeyCode::Backspace => {
-                        app.input.pop();
-                    }
-                    KeyCode::Esc => app.input_mode = InputMode::Normal,
-                    _ => {}
-                },
-            }
-
-            // 如果有修改，自动保存
-            if should_save {
-                app.save_data();
-            }
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u32(std::process::id());
+        let path =
+            std::env::temp_dir().join(format!("s_todo_description_{:016x}.md", hasher.finish()));
+        match options.open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err),
         }
     }
 }
 
-fn ui(f: &mut Frame, app: &mut App) {
-    let terminal_width = f.area().width;
-
-    // 根据终端宽度动态调整布局
-    let (left_constraint, right_constraint) = if terminal_width < 80 {
-        // 窄屏幕：垂直布局
-        (Constraint::Percentage(100), Constraint::Percentage(0))
-    } else if terminal_width < 120 {
-        // 中等屏幕：左侧较窄
-        (Constraint::Min(25), Constraint::Min(40))
-    } else {
-        // 宽屏幕：正常比例
-        (Constraint::Percentage(30), Constraint::Percentage(70))
-    };
-
-    // 当屏幕太窄时使用垂直布局
-    let chunks = if terminal_width < 80 {
-        let vertical_chunks = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
-            .split(f.area());
-        vertical_chunks
+// 运行 $EDITOR（未设置时用 vi），支持 "code -w" 这样带参数的写法
+fn run_editor(path: &Path) -> io::Result<()> {
+    let editor = std::env::var("EDITOR").unwrap_or_else(|_| "vi".to_string());
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+    let status = Command::new(program).args(parts).arg(path).status()?;
+    if status.success() {
+        Ok(())
     } else {
-        Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([left_constraint, right_constraint].as_ref())
-            .split(f.area())
-    };
-
-    // 左侧：项目列表
-    let project_items: Vec<ListItem> = app
-        .projects
-        .iter()
-        .map(|project| {
-            let name = if chunks[0].width < 20 {
-                // 极窄时只显示项目名
-                if project.name.len() > chunks[0].width as usize - 5 {
-                    format!(
-                        "📁{}",
-                        &project.name
-                            [..std::cmp::min(project.name.len(), chunks[0].width as usize - 8)]
-                    )
-                } else {
-                    format!("📁{}", project.name)
-                }
-            } else {
-                // 正常显示
-                format!("📁 {} ({})", project.name, project.todos.len())
-            };
-            ListItem::new(name)
-        })
-        .collect();
-
-    let projects_title = if terminal_width < 80 {
-        format!(
-            "项目 [{}]",
-            if app.active_panel == Panel::Projects {
-                "选中"
-            } else {
-                "未选中"
-            }
-        )
-    } else {
-        "项目".to_string()
-    };
-
-    let projects_list = List::new(project_items)
-        .block(
-            Block::default()
-                .title(projects_title)
-                .borders(Borders::ALL)
-                .border_style(if app.active_panel == Panel::Projects {
-                    Style::default().fg(Color::Yellow)
-                } else {
-                    Style::default()
-                }),
-        )
-        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
-        .highlight_symbol(">> ");
-
-    f.render_stateful_widget(projects_list, chunks[0], &mut app.project_state);
-
-    // 右侧：Todo列表（如果有空间显示）
-    if chunks.len() > 1 && chunks[1].width > 10 {
-        let todos = app.get_current_todos();
-        let todo_items: Vec<ListItem> = todos
-            .iter()
-            .map(|todo| {
-                let status = if todo.completed { "✅" } else { "⭕" };
-                let timer_indicator = if todo.is_working() { "⏱️ " } else { "" };
-                let time_str = if todo.total_duration > 0 {
-                    format!(" [{}]", todo.format_duration())
-                } else {
-                    String::new()
-                };
-
-                let title = if chunks[1].width < 30 {
-                    // 窄屏时截断文本
-                    let max_len = chunks[1].width as usize - 12;
-                    if todo.title.len() > max_len {
-                        format!(
-                            "{} {}{}...",
-                            status,
-                            timer_indicator,
-                            &todo.title[..max_len]
-                        )
-                    } else {
-                        format!("{} {}{}{}", status, timer_indicator, todo.title, time_str)
-                    }
-                } else {
-                    format!("{} {}{}{}", status, timer_indicator, todo.title, time_str)
-                };
-                ListItem::new(title)
-            })
-            .collect();
-
-        let todos_title = if terminal_width < 80 {
-            format!(
-                "Todo [{}]",
-                if app.active_panel == Panel::Todos {
-                    "选中"
-                } else {
-                    "未选中"
-                }
-            )
-        } else {
-            format!(
-                "Todo - {}",
-                app.get_current_project().map_or("无项目", |p| &p.name)
-            )
-        };
-
-        let todos_list = List::new(todo_items)
-            .block(
-                Block::default()
-                    .title(todos_title)
-                    .borders(Borders::ALL)
-                    .border_style(if app.active_panel == Panel::Todos {
-                        Style::default().fg(Color::Yellow)
-                    } else {
-                        Style::default()
-                    }),
-            )
-            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
-            .highlight_symbol(">> ");
-
-        f.render_stateful_widget(todos_list, chunks[1], &mut app.todo_state);
+        Err(io::Error::other(format!("编辑器退出状态 {}", status)))
     }
+}
 
-    // 输入框 - 调整弹窗大小
-    if app.input_mode != InputMode::Normal {
-        let input_title = match app.input_mode {
-            InputMode::AddingProject => "添加新项目",
-            InputMode::AddingTodo => "添加新Todo",
-            InputMode::RenamingProject => "重命名项目",
-            InputMode::RenamingTodo => "重命名Todo",
-            _ => "",
-        };
-
-        let input = Paragraph::new(app.input.as_str())
-            .block(Block::default().title(input_title).borders(Borders::ALL));
-
-        // 根据终端大小调整弹窗
-        let (popup_width, popup_height) = if terminal_width < 60 {
-            (90, 3) // 窄屏时占更多比例
-        } else {
-            (60, 3) // 正常大小
-        };
-
-        let popup_area = centered_rect(popup_width, popup_height, f.area());
-        f.render_widget(ratatui::widgets::Clear, popup_area);
-        f.render_widget(input, popup_area);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn panic_restores_the_screen_before_reporting() {
+        let restored = Arc::new(Mutex::new(Vec::new()));
+        let output = Arc::clone(&restored);
+        install_panic_hook(move || {
+            let _ = leave_screen(&mut *output.lock().unwrap());
+        });
+        let result = panic::catch_unwind(|| panic!("测试 panic"));
+        drop(panic::take_hook());
+
+        assert!(result.is_err());
+        let restored = String::from_utf8(restored.lock().unwrap().clone()).unwrap();
+        // 退出备用屏幕、关闭鼠标和括号粘贴、显示光标
+        for sequence in ["\x1b[?1049l", "\x1b[?1000l", "\x1b[?2004l", "\x1b[?25h"] {
+            assert!(restored.contains(sequence), "{:?}", restored);
+        }
     }
 
-    // 在底部显示帮助信息
-    if f.area().height > 5 {
-        let help_text =
-            "Tab(切换) j/k(上下) 空格(完成) a(添加) r(重命名) t(计时) d(删除) s(保存) q(退出)";
-        let help_area = ratatui::layout::Rect {
-            x: 0,
-            y: f.area().height - 1,
-            width: f.area().width,
-            height: 1,
-        };
-
-        let help_paragraph = Paragraph::new(help_text).style(Style::default().fg(Color::Gray));
-
-        f.render_widget(help_paragraph, help_area);
+    #[test]
+    fn description_temp_files_are_private_and_unique() {
+        let (first, _) = create_temp_file().unwrap();
+        let (second, _) = create_temp_file().unwrap();
+        assert_ne!(first, second);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&first).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        let _ = std::fs::remove_file(first);
+        let _ = std::fs::remove_file(second);
     }
 }
-
-fn centered_rect(percent_x: u16, height: u16, r: ratatui::layout::Rect) -> ratatui::layout::Rect {
-    let popup_layout = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Length((r.height - height) / 2),
-            Constraint::Length(height),
-            Constraint::Length((r.height - height) / 2),
-        ])
-        .split(r);
-
-    Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([
-            Constraint::Percentage((100 - percent_x) / 2),
-            Constraint::Percentage(percent_x),
-            Constraint::Percentage((100 - percent_x) / 2),
-        ])
-        .split(popup_layout[1])[1]
-}
@@ -0,0 +1,67 @@
+use std::collections::VecDeque;
+
+// 消息记录最多保留的条数
+const LOG_LIMIT: usize = 50;
+
+// 提示的级别，决定底部提示的颜色
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Info,    // 操作完成的确认
+    Warning, // 操作没有执行，如没有选中任务
+    Error,   // 保存、导出等读写文件失败
+}
+
+#[derive(Clone, Debug)]
+pub struct Message {
+    pub level: Level,
+    pub text: String,
+    pub at: u64, // 产生的时间
+}
+
+// 最近的提示记录，超过上限时丢弃最早的
+#[derive(Default)]
+pub struct MessageLog {
+    entries: VecDeque<Message>,
+}
+
+impl MessageLog {
+    pub fn push(&mut self, message: Message) {
+        if self.entries.len() == LOG_LIMIT {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(message);
+    }
+
+    // 从新到旧
+    pub fn iter(&self) -> impl Iterator<Item = &Message> {
+        self.entries.iter().rev()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_keeps_only_the_latest_messages() {
+        let mut log = MessageLog::default();
+        for i in 0..LOG_LIMIT + 5 {
+            log.push(Message {
+                level: Level::Info,
+                text: i.to_string(),
+                at: i as u64,
+            });
+        }
+        assert_eq!(log.len(), LOG_LIMIT);
+        assert_eq!(log.iter().next().unwrap().text, (LOG_LIMIT + 4).to_string());
+        assert_eq!(log.iter().last().unwrap().text, "5");
+    }
+}
@@ -0,0 +1,1456 @@
+use crate::dates;
+use chrono::{Local, Months, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// 当前时间戳（秒）
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+// 今天的本地日期
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+// 文本的 FNV-1a 哈希，结果不依赖 Rust 版本，用于导出时生成稳定的标识
+pub fn stable_hash(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+// 标准 Base64 编码，用于 HTTP 认证和终端剪贴板
+pub fn base64(data: &[u8]) -> String {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::new();
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(TABLE[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+// 时长显示的最大单位，更大的时长用该单位累计，如按天显示时 45 天为 "45d"
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DurationUnit {
+    #[default]
+    Month, // 按 30 天计算
+    Week,
+    Day,
+    Hour,
+}
+
+impl DurationUnit {
+    // 从该单位开始依次使用的单位（秒数，后缀）
+    fn units(self) -> &'static [(u64, &'static str)] {
+        const SMALL: [(u64, &str); 4] = [(86400, "d"), (3600, "h"), (60, "m"), (1, "s")];
+        match self {
+            DurationUnit::Month => &[(2592000, "mo"), SMALL[0], SMALL[1], SMALL[2], SMALL[3]],
+            DurationUnit::Week => &[(604800, "w"), SMALL[0], SMALL[1], SMALL[2], SMALL[3]],
+            DurationUnit::Day => &SMALL,
+            DurationUnit::Hour => &[SMALL[1], SMALL[2], SMALL[3]],
+        }
+    }
+}
+
+// 格式化时长显示，如 "2h 30m 15s"，为 0 时返回空字符串
+// 从第一个非零单位开始最多显示三个单位，其中为 0 的省略
+pub fn format_duration(total_seconds: u64, largest: DurationUnit) -> String {
+    let mut rest = total_seconds;
+    let values: Vec<(u64, &str)> = largest
+        .units()
+        .iter()
+        .map(|&(size, suffix)| {
+            let value = rest / size;
+            rest %= size;
+            (value, suffix)
+        })
+        .collect();
+
+    let Some(first) = values.iter().position(|&(value, _)| value > 0) else {
+        return String::new();
+    };
+    values[first..]
+        .iter()
+        .take(3)
+        .filter(|&&(value, _)| value > 0)
+        .map(|(value, suffix)| format!("{}{}", value, suffix))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// 解析 format_duration 风格的时长，如 "1h30m"、"45m"、"2d"，不带单位的数字按分钟计
+// 数值太大溢出时返回 None
+pub fn parse_duration(input: &str) -> Option<u64> {
+    let input = input.trim().to_lowercase();
+    if let Ok(minutes) = input.parse::<u64>() {
+        return minutes.checked_mul(60);
+    }
+
+    let mut total: u64 = 0;
+    let mut number = String::new();
+    for c in input.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            'd' => 86400,
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return None,
+        };
+        let secs = number.parse::<u64>().ok()?.checked_mul(unit)?;
+        total = total.checked_add(secs)?;
+        number.clear();
+    }
+    (number.is_empty() && total > 0).then_some(total)
+}
+
+// 从输入中拆出标题和标签，如 "buy milk #errand #home"，重复的标签只保留一个
+pub fn parse_tags(input: &str) -> (String, Vec<String>) {
+    let mut words = Vec::new();
+    let mut tags: Vec<String> = Vec::new();
+    for word in input.split_whitespace() {
+        match word.strip_prefix('#').filter(|tag| !tag.is_empty()) {
+            Some(tag) => {
+                if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                    tags.push(tag.to_string());
+                }
+            }
+            None => words.push(word),
+        }
+    }
+    (words.join(" "), tags)
+}
+
+// 快速添加时从输入中拆出的各项，如 "写周报 !high #work due:friday est:2h"
+pub struct QuickAdd {
+    pub title: String,
+    pub tags: Vec<String>,
+    pub priority: Option<Priority>,
+    pub due_date: Option<NaiveDate>,
+    pub estimate: Option<u64>, // 预计耗时（秒）
+}
+
+impl QuickAdd {
+    pub fn into_todo(self) -> Todo {
+        Todo {
+            tags: self.tags,
+            priority: self.priority,
+            due_date: self.due_date,
+            estimate: self.estimate,
+            ..Todo::new(self.title)
+        }
+    }
+}
+
+// 解析快速添加的输入：!优先级、#标签、due:日期、est:时长，无法识别的写法保留在标题中
+pub fn parse_quick_add(input: &str, today: NaiveDate) -> QuickAdd {
+    let mut words = Vec::new();
+    let mut priority = None;
+    let mut due_date = None;
+    let mut estimate = None;
+    for word in input.split_whitespace() {
+        if let Some(p) = word.strip_prefix('!').and_then(Priority::parse) {
+            priority = Some(p);
+        } else if let Some(date) = word
+            .strip_prefix("due:")
+            .and_then(|date| dates::parse_date(date, today))
+        {
+            due_date = Some(date);
+        } else if let Some(secs) = word.strip_prefix("est:").and_then(parse_duration) {
+            estimate = Some(secs);
+        } else {
+            words.push(word);
+        }
+    }
+    let (title, tags) = parse_tags(&words.join(" "));
+    QuickAdd {
+        title,
+        tags,
+        priority,
+        due_date,
+        estimate,
+    }
+}
+
+// 模糊匹配：query 的字符按顺序出现在 text 中即匹配（不区分大小写），返回分数，越高越好
+// 连续匹配和在单词开头的匹配加分，query 为空时匹配所有内容
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let query: Vec<char> = query
+        .to_lowercase()
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    let Some(&first) = query.first() else {
+        return Some(-(text.len() as i64));
+    };
+
+    // 从首字符的每个出现位置开始贪心匹配，取最高分
+    let best = (0..text.len())
+        .filter(|&i| text[i] == first)
+        .filter_map(|i| greedy_score(&query, &text, i))
+        .max()?;
+    // 同样的匹配下，较短的文本排在前面
+    Some(best * 1000 - text.len() as i64)
+}
+
+fn greedy_score(query: &[char], text: &[char], mut start: usize) -> Option<i64> {
+    let mut score = 0;
+    let mut last: Option<usize> = None;
+    for &q in query {
+        let pos = start + text[start..].iter().position(|&c| c == q)?;
+        score += 1;
+        if last.is_some_and(|last| last + 1 == pos) {
+            score += 5;
+        }
+        if pos == 0 || !text[pos - 1].is_alphanumeric() {
+            score += 3;
+        }
+        last = Some(pos);
+        start = pos + 1;
+    }
+    Some(score)
+}
+
+// 优先级，按 低 < 中 < 高 排序
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    Low,
+    Medium,
+    High,
+}
+
+impl Priority {
+    // 快速添加中 ! 后面的写法，如 !high、!h、!高
+    pub fn parse(text: &str) -> Option<Priority> {
+        match text.to_lowercase().as_str() {
+            "high" | "h" | "高" => Some(Priority::High),
+            "medium" | "med" | "m" | "中" => Some(Priority::Medium),
+            "low" | "l" | "低" => Some(Priority::Low),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Priority::High => "高",
+            Priority::Medium => "中",
+            Priority::Low => "低",
+        }
+    }
+}
+
+// 重复规则
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Recurrence {
+    Daily,
+    Weekly,
+    Monthly, // 没有对应日子的月份取月末
+}
+
+impl Recurrence {
+    // 循环切换：不重复 → 每天 → 每周 → 每月 → 不重复
+    pub fn cycle(current: Option<Recurrence>) -> Option<Recurrence> {
+        match current {
+            None => Some(Recurrence::Daily),
+            Some(Recurrence::Daily) => Some(Recurrence::Weekly),
+            Some(Recurrence::Weekly) => Some(Recurrence::Monthly),
+            Some(Recurrence::Monthly) => None,
+        }
+    }
+
+    // 下一次的日期
+    pub fn next(self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Recurrence::Daily => date + chrono::Days::new(1),
+            Recurrence::Weekly => date + chrono::Days::new(7),
+            Recurrence::Monthly => date
+                .checked_add_months(Months::new(1))
+                .unwrap_or(NaiveDate::MAX),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Recurrence::Daily => "每天",
+            Recurrence::Weekly => "每周",
+            Recurrence::Monthly => "每月",
+        }
+    }
+}
+
+// 看板中的状态，由 completed 和 doing 决定
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Status {
+    Todo,
+    Doing,
+    Done,
+}
+
+impl Status {
+    // 看板的列，从左到右
+    pub const ALL: [Status; 3] = [Status::Todo, Status::Doing, Status::Done];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Status::Todo => "待办",
+            Status::Doing => "进行中",
+            Status::Done => "已完成",
+        }
+    }
+}
+
+// todo 下的子任务
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Subtask {
+    pub title: String,
+    pub completed: bool,
+}
+
+// 同步过的 todo 与 CalDAV 服务器上的任务的对应关系
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct CaldavLink {
+    pub uid: String,
+    pub href: String, // 任务在服务器上的地址
+    pub etag: String, // 上次同步时服务器上的版本，用于判断服务器上是否有修改
+    pub synced: u64,  // 上次同步时标题、完成状态和截止日期的哈希，用于判断本地是否有修改
+}
+
+// 一次计时记录
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Session {
+    pub start: u64, // 开始时间（时间戳）
+    pub end: u64,   // 结束时间（时间戳）
+}
+
+impl Session {
+    // 耗时（秒），手动修改过的数据文件中结束时间早于开始时间时为 0
+    pub fn duration(&self) -> u64 {
+        self.end.saturating_sub(self.start)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Todo {
+    pub title: String,
+    pub description: String,
+    pub completed: bool,
+    // 时间记录字段
+    pub start_time: Option<u64>, // 开始时间（时间戳）
+    pub end_time: Option<u64>,   // 结束时间（时间戳）
+    #[serde(default)]
+    pub created_at: u64, // 创建时间（时间戳），旧数据为 0
+    #[serde(default)]
+    pub completed_at: Option<u64>, // 完成时间（时间戳），未完成或旧数据为 None
+    #[serde(default)]
+    pub sessions: Vec<Session>, // 每次计时的记录，耗时由这些记录计算
+    #[serde(default)]
+    pub untracked: u64, // 旧版本只记录了总耗时，没有对应计时记录的秒数
+    #[serde(default)]
+    pub counted_from: u64, // 耗时只统计这个时间之后开始的计时，重复的 todo 完成后更新
+    #[serde(default)]
+    pub due_date: Option<NaiveDate>, // 截止日期
+    #[serde(default)]
+    pub tags: Vec<String>, // 标签，不带 #
+    #[serde(default)]
+    pub subtasks: Vec<Subtask>,
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>, // 重复规则，完成后自动安排下一次
+    #[serde(default)]
+    pub doing: bool, // 正在进行，未完成时在看板中显示在「进行中」列
+    #[serde(default)]
+    pub pomodoros: u32, // 完成的番茄钟数量
+    #[serde(default)]
+    pub priority: Option<Priority>,
+    #[serde(default)]
+    pub estimate: Option<u64>, // 预计耗时（秒）
+    #[serde(default)]
+    pub caldav: Option<Box<CaldavLink>>, // 与 CalDAV 服务器上的任务的对应关系，没有同步过时为 None
+    #[serde(skip)]
+    pub alerted: bool, // 本次计时是否已经提醒过
+}
+
+impl Todo {
+    pub fn new(title: String) -> Self {
+        Self {
+            title,
+            description: String::new(),
+            completed: false,
+            start_time: None,
+            end_time: None,
+            created_at: now_secs(),
+            sessions: vec![],
+            untracked: 0,
+            counted_from: 0,
+            due_date: None,
+            tags: vec![],
+            subtasks: vec![],
+            recurrence: None,
+            doing: false,
+            pomodoros: 0,
+            priority: None,
+            estimate: None,
+            completed_at: None,
+            caldav: None,
+            alerted: false,
+        }
+    }
+
+    // 复制为新的 todo，保留标题、描述、标签、子任务和截止日期等设置
+    // 不带耗时、番茄钟、完成状态和同步关系，子任务都改为未完成
+    pub fn duplicate(&self) -> Todo {
+        Todo {
+            title: self.title.clone(),
+            description: self.description.clone(),
+            due_date: self.due_date,
+            tags: self.tags.clone(),
+            subtasks: self
+                .subtasks
+                .iter()
+                .map(|s| Subtask {
+                    title: s.title.clone(),
+                    completed: false,
+                })
+                .collect(),
+            recurrence: self.recurrence,
+            priority: self.priority,
+            estimate: self.estimate,
+            ..Todo::new(String::new())
+        }
+    }
+
+    // 开始工作 - 记录开始时间
+    pub fn start_work(&mut self) {
+        self.start_time = Some(now_secs());
+        self.end_time = None; // 清除结束时间
+        self.alerted = false;
+    }
+
+    // 结束工作 - 记录结束时间并计算耗时
+    pub fn end_work(&mut self) {
+        self.end_work_at(now_secs());
+    }
+
+    // 以指定时间结束工作，结束时间早于开始时间时按开始时间计
+    pub fn end_work_at(&mut self, end: u64) {
+        if let Some(start) = self.start_time {
+            let end = end.max(start);
+            self.end_time = Some(end);
+            self.log_session(start, end);
+        }
+    }
+
+    // 添加一段计时记录，按开始时间插入，保持记录从旧到新排列
+    pub fn log_session(&mut self, start: u64, end: u64) {
+        let index = self.sessions.partition_point(|s| s.start <= start);
+        self.sessions.insert(index, Session { start, end });
+    }
+
+    // 总耗时（秒），不包括正在进行的计时
+    pub fn total_duration(&self) -> u64 {
+        let tracked = self
+            .sessions
+            .iter()
+            .filter(|s| s.start >= self.counted_from)
+            .fold(0, |total: u64, s| total.saturating_add(s.duration()));
+        self.untracked.saturating_add(tracked)
+    }
+
+    // 全部计时记录的耗时，包括重复的 todo 之前几次的耗时
+    pub fn lifetime_duration(&self) -> u64 {
+        let tracked = self
+            .sessions
+            .iter()
+            .fold(0, |total: u64, s| total.saturating_add(s.duration()));
+        self.untracked.saturating_add(tracked)
+    }
+
+    // 标记为完成，会先结束正在进行的计时
+    // 重复的 todo 不会保持完成，而是把截止日期推到今天之后的下一次，并重置计时和子任务
+    // 返回安排的下一次日期
+    pub fn complete(&mut self, today: NaiveDate) -> Option<NaiveDate> {
+        if self.is_working() {
+            self.end_work();
+        }
+        self.doing = false;
+        let Some(recurrence) = self.recurrence else {
+            self.set_completed(true);
+            return None;
+        };
+
+        // 按原来的截止日期推算，保持固定的日子，错过的次数直接跳过
+        let mut next = self.due_date.unwrap_or(today);
+        while next <= today {
+            next = recurrence.next(next);
+        }
+        self.due_date = Some(next);
+        self.completed = false;
+        self.start_time = None;
+        self.end_time = None;
+        // 计时记录保留在报告中，下一次的耗时从现在开始统计
+        self.untracked = 0;
+        self.counted_from = now_secs();
+        for subtask in &mut self.subtasks {
+            subtask.completed = false;
+        }
+        Some(next)
+    }
+
+    // 修改完成状态，标记为完成时记录完成时间，取消完成时清除
+    pub fn set_completed(&mut self, completed: bool) {
+        if completed != self.completed {
+            self.completed_at = completed.then(now_secs);
+        }
+        self.completed = completed;
+    }
+
+    pub fn status(&self) -> Status {
+        match (self.completed, self.doing) {
+            (true, _) => Status::Done,
+            (false, true) => Status::Doing,
+            (false, false) => Status::Todo,
+        }
+    }
+
+    // 修改状态，改为已完成时和完成 todo 一样处理重复规则，返回安排的下一次日期
+    pub fn set_status(&mut self, status: Status, today: NaiveDate) -> Option<NaiveDate> {
+        if status == Status::Done {
+            return self.complete(today);
+        }
+        self.set_completed(false);
+        self.doing = status == Status::Doing;
+        None
+    }
+
+    // 子任务完成进度（已完成数，总数），没有子任务时返回 None
+    pub fn subtask_progress(&self) -> Option<(usize, usize)> {
+        if self.subtasks.is_empty() {
+            return None;
+        }
+        let done = self.subtasks.iter().filter(|s| s.completed).count();
+        Some((done, self.subtasks.len()))
+    }
+
+    // 标题或描述中是否包含关键字（不区分大小写），query 需为小写
+    pub fn matches(&self, query: &str) -> bool {
+        self.title.to_lowercase().contains(query) || self.description.to_lowercase().contains(query)
+    }
+
+    // 是否带有指定标签（不区分大小写）
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    // 标题和标签，重命名时预填到输入框
+    pub fn title_with_tags(&self) -> String {
+        let mut text = self.title.clone();
+        for tag in &self.tags {
+            text.push_str(" #");
+            text.push_str(tag);
+        }
+        text
+    }
+
+    // 未完成且截止日期早于今天
+    pub fn is_overdue(&self, today: NaiveDate) -> bool {
+        !self.completed && self.due_date.is_some_and(|due| due < today)
+    }
+
+    // 丢弃正在进行的计时，不留下记录
+    pub fn discard_session(&mut self) {
+        if self.is_working() {
+            self.start_time = None;
+        }
+    }
+
+    // 切换工作状态
+    pub fn toggle_work(&mut self) {
+        if self.start_time.is_some() && self.end_time.is_none() {
+            // 正在工作，结束工作
+            self.end_work();
+        } else {
+            // 没有工作或已结束，开始新的工作
+            self.start_work();
+        }
+    }
+
+    // 检查是否正在工作
+    pub fn is_working(&self) -> bool {
+        self.start_time.is_some() && self.end_time.is_none()
+    }
+
+    // 本次计时已进行的秒数，未在计时时为 0
+    pub fn session_elapsed(&self, now: u64) -> u64 {
+        match self.start_time {
+            Some(start) if self.is_working() => now.saturating_sub(start),
+            _ => 0,
+        }
+    }
+
+    // 包括正在进行的计时在内的耗时
+    pub fn elapsed(&self, now: u64) -> u64 {
+        self.total_duration() + self.session_elapsed(now)
+    }
+
+    // 耗时是否已超过预计耗时
+    pub fn over_budget(&self, now: u64) -> bool {
+        self.estimate
+            .is_some_and(|estimate| self.elapsed(now) > estimate)
+    }
+
+    // 格式化时间显示
+    pub fn format_duration(&self, largest: DurationUnit) -> String {
+        format_duration(self.total_duration(), largest)
+    }
+}
+
+// Todo 列表的排序方式，只影响显示顺序，不改变保存的顺序
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortMode {
+    #[default]
+    Original, // 添加顺序
+    Title,      // 按标题字母顺序
+    Completion, // 未完成的在前
+    Created,    // 新创建的在前
+    DueDate,    // 截止日期早的在前，没有截止日期的在最后
+    Priority,   // 优先级高的在前，没有优先级的在最后
+    Duration,   // 耗时从多到少
+}
+
+impl SortMode {
+    // 循环切换到下一种排序方式
+    pub fn next(self) -> SortMode {
+        match self {
+            SortMode::Original => SortMode::Title,
+            SortMode::Title => SortMode::Completion,
+            SortMode::Completion => SortMode::Created,
+            SortMode::Created => SortMode::DueDate,
+            SortMode::DueDate => SortMode::Priority,
+            SortMode::Priority => SortMode::Duration,
+            SortMode::Duration => SortMode::Original,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortMode::Original => "默认顺序",
+            SortMode::Title => "按标题",
+            SortMode::Completion => "未完成优先",
+            SortMode::Created => "最新创建",
+            SortMode::DueDate => "按截止日期",
+            SortMode::Priority => "按优先级",
+            SortMode::Duration => "按耗时",
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Project {
+    pub name: String,
+    pub todos: Vec<Todo>,
+    #[serde(default)]
+    pub sort_mode: SortMode,
+    #[serde(default)]
+    pub created_at: u64, // 创建时间（时间戳），旧数据为 0
+    #[serde(default)]
+    pub archived: Vec<Todo>, // 归档的已完成 todo，不在列表中显示但保留耗时，从旧到新排列
+    #[serde(skip)]
+    pub collapsed: bool, // 在项目列表中折叠显示，只在界面中使用
+    #[serde(default)]
+    pub parent: Option<String>, // 上级项目的名称，顶层项目为 None
+    #[serde(default)]
+    pub folded: bool, // 在项目树中收起子项目
+}
+
+impl Project {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            todos: vec![],
+            sort_mode: SortMode::default(),
+            created_at: now_secs(),
+            archived: vec![],
+            collapsed: false,
+            parent: None,
+            folded: false,
+        }
+    }
+
+    // 复制为新的项目，todo 按 Todo::duplicate 复制，不包括归档的 todo 和子项目
+    pub fn duplicate(&self, name: String) -> Project {
+        Project {
+            todos: self.todos.iter().map(Todo::duplicate).collect(),
+            sort_mode: self.sort_mode,
+            parent: self.parent.clone(),
+            ..Project::new(name)
+        }
+    }
+
+    // 是否是名为 name 的项目的子项目，项目名不区分大小写
+    pub fn is_child_of(&self, name: &str) -> bool {
+        self.parent
+            .as_ref()
+            .is_some_and(|parent| parent.to_lowercase() == name.to_lowercase())
+    }
+
+    // 把已完成的 todo 移到归档，返回归档的数量
+    pub fn archive_completed(&mut self) -> usize {
+        let (done, pending): (Vec<Todo>, Vec<Todo>) = std::mem::take(&mut self.todos)
+            .into_iter()
+            .partition(|t| t.completed);
+        self.todos = pending;
+        let count = done.len();
+        self.archived.extend(done);
+        count
+    }
+
+    // 项目中 todo 的耗时合计，包括正在进行的计时，不包括归档的 todo
+    pub fn tracked_time(&self, now: u64, include_completed: bool) -> u64 {
+        self.todos
+            .iter()
+            .filter(|todo| include_completed || !todo.completed)
+            .map(|todo| todo.elapsed(now))
+            .sum()
+    }
+
+    // 设置了预计耗时的 todo 的耗时之和与预计耗时之和，都没有设置时返回 None
+    pub fn estimate_progress(&self, now: u64) -> Option<(u64, u64)> {
+        self.todos
+            .iter()
+            .filter_map(|todo| Some((todo.elapsed(now), todo.estimate?)))
+            .reduce(|(elapsed, estimate), (e, est)| (elapsed + e, estimate + est))
+    }
+
+    // 按排序方式返回 todos 的下标，排序是稳定的，相同时保持添加顺序
+    pub fn sorted_indices(&self) -> Vec<usize> {
+        let mut indices: Vec<usize> = (0..self.todos.len()).collect();
+        let todos = &self.todos;
+        match self.sort_mode {
+            SortMode::Original => {}
+            SortMode::Title => indices.sort_by_cached_key(|&i| todos[i].title.to_lowercase()),
+            SortMode::Completion => indices.sort_by_key(|&i| todos[i].completed),
+            SortMode::Created => indices.sort_by_key(|&i| std::cmp::Reverse(todos[i].created_at)),
+            SortMode::DueDate => {
+                indices.sort_by_key(|&i| (todos[i].due_date.is_none(), todos[i].due_date))
+            }
+            SortMode::Priority => indices.sort_by_key(|&i| std::cmp::Reverse(todos[i].priority)),
+            SortMode::Duration => {
+                indices.sort_by_key(|&i| std::cmp::Reverse(todos[i].total_duration()))
+            }
+        }
+        indices
+    }
+}
+
+// 按上级关系把项目排成树：每个项目后面紧跟它的子项目，同级的项目保持原来的顺序
+// 上级项目不存在或上级关系形成环时放到顶层
+pub fn arrange_tree(projects: &mut Vec<Project>) {
+    let parent_of = |projects: &[Project], i: usize| {
+        projects
+            .iter()
+            .position(|p| projects[i].is_child_of(&p.name))
+    };
+    for i in 0..projects.len() {
+        // 沿上级一直向上，走不到顶说明有环
+        let mut current = parent_of(projects, i);
+        let mut steps = 0;
+        while let Some(p) = current.filter(|&p| p != i && steps < projects.len()) {
+            current = parent_of(projects, p);
+            steps += 1;
+        }
+        if current.is_some() || parent_of(projects, i).is_none() {
+            projects[i].parent = None;
+        }
+    }
+
+    let parents: Vec<Option<usize>> = (0..projects.len())
+        .map(|i| parent_of(projects, i))
+        .collect();
+    let mut order = Vec::with_capacity(projects.len());
+    let mut stack: Vec<usize> = (0..projects.len())
+        .rev()
+        .filter(|&i| parents[i].is_none())
+        .collect();
+    while let Some(i) = stack.pop() {
+        order.push(i);
+        stack.extend((0..projects.len()).rev().filter(|&c| parents[c] == Some(i)));
+    }
+
+    let mut slots: Vec<Option<Project>> = std::mem::take(projects).into_iter().map(Some).collect();
+    *projects = order.into_iter().filter_map(|i| slots[i].take()).collect();
+}
+
+// 项目在树中的层级，顶层为 0
+pub fn project_depth(projects: &[Project], idx: usize) -> usize {
+    let mut depth = 0;
+    let mut current = &projects[idx];
+    while let Some(parent) = projects.iter().find(|p| current.is_child_of(&p.name)) {
+        depth += 1;
+        current = parent;
+        if depth >= projects.len() {
+            break;
+        }
+    }
+    depth
+}
+
+// 项目和它的所有子项目在排好的树中占据的下标范围
+pub fn project_subtree(projects: &[Project], idx: usize) -> std::ops::Range<usize> {
+    let depth = project_depth(projects, idx);
+    let end = (idx + 1..projects.len())
+        .find(|&i| project_depth(projects, i) <= depth)
+        .unwrap_or(projects.len());
+    idx..end
+}
+
+// 数据文件的升级步骤，第 n 项把 vn 的 JSON 升级为 v(n+1)
+// 数据文件增加字段或结构发生变化时都要在末尾追加一项（只增加字段时为空步骤），当前版本随之递增，
+// 否则旧版本读取新文件时会丢掉不认识的字段并写回，版本号更新后旧版本会拒绝保存
+const MIGRATIONS: &[fn(&mut serde_json::Value)] = &[
+    // v0 -> v1：旧文件没有 version 字段，其余结构不变
+    |_| {},
+    // v1 -> v2：增加最后保存时间 saved_at
+    |_| {},
+    // v2 -> v3：项目增加排序方式 sort_mode
+    |_| {},
+    // v3 -> v4：项目和 todo 增加创建时间 created_at
+    |_| {},
+    // v4 -> v5：todo 增加计时记录 sessions
+    |_| {},
+    // v5 -> v6：todo 增加截止日期 due_date
+    |_| {},
+    // v6 -> v7：todo 增加标签 tags
+    |_| {},
+    // v7 -> v8：todo 增加子任务 subtasks
+    |_| {},
+    // v8 -> v9：todo 增加重复规则 recurrence
+    |_| {},
+    // v9 -> v10：增加回收站 trash
+    |_| {},
+    // v10 -> v11：todo 增加进行中状态 doing
+    |_| {},
+    // v11 -> v12：项目增加归档的 todo archived
+    |_| {},
+    // v12 -> v13：去掉累加的 total_duration，耗时改为由计时记录计算
+    migrate_durations,
+    // v13 -> v14：todo 增加番茄钟数量 pomodoros
+    |_| {},
+    // v14 -> v15：todo 增加 CalDAV 同步信息 caldav
+    |_| {},
+    // v15 -> v16：todo 增加优先级 priority 和预计耗时 estimate
+    |_| {},
+    // v16 -> v17：排序方式增加按创建时间、截止日期和优先级排序
+    |_| {},
+    // v17 -> v18：todo 增加完成时间 completed_at
+    |_| {},
+    // v18 -> v19：项目增加上级项目 parent 和收起状态 folded
+    |_| {},
+    // v19 -> v20：增加归档的项目 archived_projects
+    |_| {},
+    // v20 -> v21：增加模板 templates
+    |_| {},
+];
+
+// 数据文件格式的当前版本
+pub const DATA_VERSION: u32 = MIGRATIONS.len() as u32;
+
+#[derive(Serialize, Deserialize)]
+pub struct AppData {
+    #[serde(default)]
+    pub version: u32,
+    #[serde(default)]
+    pub saved_at: u64, // 最后一次保存的时间戳
+    pub projects: Vec<Project>,
+    #[serde(default)]
+    pub trash: Vec<TrashItem>, // 回收站，从旧到新排列
+    #[serde(default)]
+    pub archived_projects: Vec<Project>, // 归档的项目，不在项目列表中显示，从旧到新排列
+    #[serde(default)]
+    pub templates: Vec<Template>,
+}
+
+// 保存的模板，用于反复使用的清单，如发布流程
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Template {
+    pub name: String,
+    pub item: Templated,
+}
+
+// 模板的内容，保存时和创建时都会去掉耗时和完成状态
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Templated {
+    Project(Project),
+    Todo(Todo), // 带子任务的 todo，添加到当前项目
+}
+
+impl Template {
+    // 把项目保存为模板，不带上级项目
+    pub fn from_project(name: String, project: &Project) -> Self {
+        let project = Project {
+            parent: None,
+            ..project.duplicate(project.name.clone())
+        };
+        Self {
+            name,
+            item: Templated::Project(project),
+        }
+    }
+
+    pub fn from_todo(name: String, todo: &Todo) -> Self {
+        Self {
+            name,
+            item: Templated::Todo(todo.duplicate()),
+        }
+    }
+}
+
+// 回收站中的一项
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TrashItem {
+    pub deleted_at: u64, // 删除时间（时间戳）
+    pub item: Trashed,
+}
+
+// 清除回收站中超过保留天数的内容，返回清除的数量
+pub fn purge_trash(trash: &mut Vec<TrashItem>, now: u64, retention_days: u64) -> usize {
+    let before = trash.len();
+    let cutoff = now.saturating_sub(retention_days * 86400);
+    trash.retain(|item| item.deleted_at >= cutoff);
+    before - trash.len()
+}
+
+// 被删除的项目或 todo
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Trashed {
+    Project(Project),
+    Todo { project: String, todo: Todo }, // project 为删除时所在项目的名称
+}
+
+impl TrashItem {
+    pub fn new(item: Trashed, deleted_at: u64) -> Self {
+        Self { deleted_at, item }
+    }
+
+    // 在回收站中显示的名称
+    pub fn label(&self) -> String {
+        match &self.item {
+            Trashed::Project(project) => {
+                format!("📁 {} ({})", project.name, project.todos.len())
+            }
+            Trashed::Todo { project, todo } => format!("{} / {}", project, todo.title),
+        }
+    }
+}
+
+// 把 JSON 中所有 todo（包括归档和回收站中的）的 total_duration 换成 untracked 和 counted_from
+fn migrate_durations(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            if let Some(total) = map.remove("total_duration") {
+                let total = total.as_u64().unwrap_or(0);
+                let sessions: Vec<Session> = map
+                    .get("sessions")
+                    .and_then(|s| serde_json::from_value(s.clone()).ok())
+                    .unwrap_or_default();
+                let (counted_from, counted) = counted_sessions(&sessions, total);
+                map.insert("untracked".into(), (total - counted).into());
+                map.insert("counted_from".into(), counted_from.into());
+            }
+            for child in map.values_mut() {
+                migrate_durations(child);
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(migrate_durations),
+        _ => {}
+    }
+}
+
+// 旧版本重复的 todo 完成后 total_duration 清零但计时记录保留，
+// 从最新的记录往前找出不超过 total 的部分，返回 (统计的起始时间, 这部分的耗时)
+fn counted_sessions(sessions: &[Session], total: u64) -> (u64, u64) {
+    let mut counted: u64 = 0;
+    let mut first = None;
+    for (i, session) in sessions.iter().enumerate().rev() {
+        let duration = session.duration();
+        if counted.saturating_add(duration) > total {
+            break;
+        }
+        counted += duration;
+        first = Some(i);
+    }
+    let counted_from = match (first, sessions.last()) {
+        (Some(0), _) | (_, None) => 0,
+        (Some(i), _) => sessions[i].start,
+        (None, Some(last)) => last.end + 1,
+    };
+    (counted_from, counted)
+}
+
+impl AppData {
+    // 读取数据的版本号，旧文件没有 version 字段视为 v0
+    pub fn version_of(value: &serde_json::Value) -> u32 {
+        value
+            .get("version")
+            .and_then(|v| v.as_u64())
+            .map_or(0, |v| v.min(u32::MAX as u64) as u32)
+    }
+
+    // 把旧版本的 JSON 数据逐步升级为当前版本
+    pub fn migrate(mut value: serde_json::Value) -> serde_json::Result<AppData> {
+        let version = Self::version_of(&value) as usize;
+        // 不是对象时无法写入版本号，直接交给反序列化报错
+        if !value.is_object() {
+            return serde_json::from_value(value);
+        }
+        for (from, step) in MIGRATIONS.iter().enumerate().skip(version) {
+            step(&mut value);
+            value["version"] = serde_json::Value::from(from + 1);
+        }
+        serde_json::from_value(value)
+    }
+
+    // 结束上次异常退出时仍在计时的任务，返回处理的数量
+    // 计时只记到最后一次保存为止，避免关机多日后记录下多日的工作时间
+    pub fn finish_interrupted_sessions(&mut self) -> usize {
+        let mut finished = 0;
+        for todo in self.projects.iter_mut().flat_map(|p| p.todos.iter_mut()) {
+            if todo.is_working() {
+                todo.end_work_at(self.saved_at);
+                finished += 1;
+            }
+        }
+        finished
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duration_formatting_is_pinned() {
+        let cases = [
+            (0, ""),
+            (59, "59s"),
+            (60, "1m"),
+            (61, "1m 1s"),
+            (3600, "1h"),
+            (3601, "1h 1s"),
+            (3661, "1h 1m 1s"),
+            (86400, "1d"),
+            (90000, "1d 1h"),
+            (90060, "1d 1h 1m"),
+            // 只显示三个单位，秒被省略
+            (90061, "1d 1h 1m"),
+            (3 * 86400 + 120, "3d 2m"),
+            (2591999, "29d 23h 59m"),
+            (2592000, "1mo"),
+            (2592000 + 86400 + 3600, "1mo 1d 1h"),
+        ];
+        for (secs, expected) in cases {
+            assert_eq!(
+                format_duration(secs, DurationUnit::Month),
+                expected,
+                "{}",
+                secs
+            );
+        }
+    }
+
+    #[test]
+    fn durations_are_parsed() {
+        assert_eq!(parse_duration("45m"), Some(2700));
+        assert_eq!(parse_duration("1h30m"), Some(5400));
+        assert_eq!(parse_duration("1d2h"), Some(93600));
+        assert_eq!(parse_duration("90"), Some(5400));
+        assert_eq!(parse_duration("2H"), Some(7200));
+        assert_eq!(parse_duration("1h30"), None);
+        assert_eq!(parse_duration("0m"), None);
+        assert_eq!(parse_duration("abc"), None);
+        assert_eq!(parse_duration("400000000000000000"), None);
+        assert_eq!(parse_duration("300000000000000d"), None);
+        assert_eq!(parse_duration("18446744073709551615s1s"), None);
+    }
+
+    #[test]
+    fn largest_duration_unit_is_configurable() {
+        let secs = 45 * 86400 + 3600;
+        assert_eq!(format_duration(secs, DurationUnit::Month), "1mo 15d 1h");
+        assert_eq!(format_duration(secs, DurationUnit::Week), "6w 3d 1h");
+        assert_eq!(format_duration(secs, DurationUnit::Day), "45d 1h");
+        assert_eq!(format_duration(secs, DurationUnit::Hour), "1081h");
+        assert_eq!(format_duration(59, DurationUnit::Week), "59s");
+    }
+
+    #[test]
+    fn interrupted_session_is_capped_at_last_save() {
+        let mut todo = Todo::new("a".to_string());
+        todo.start_time = Some(1_000);
+        let mut done = Todo::new("b".to_string());
+        done.start_time = Some(1_000);
+        done.end_time = Some(1_100);
+        done.log_session(1_000, 1_100);
+
+        let mut data = AppData {
+            version: DATA_VERSION,
+            saved_at: 1_600,
+            projects: vec![Project {
+                todos: vec![todo, done],
+                ..Project::new("p".to_string())
+            }],
+            trash: vec![],
+            archived_projects: vec![],
+            templates: vec![],
+        };
+        assert_eq!(data.finish_interrupted_sessions(), 1);
+        assert_eq!(data.projects[0].todos[0].total_duration(), 600);
+        assert_eq!(data.projects[0].todos[0].end_time, Some(1_600));
+        assert_eq!(data.projects[0].todos[1].total_duration(), 100);
+    }
+
+    #[test]
+    fn interrupted_session_without_save_time_records_nothing() {
+        let mut todo = Todo::new("a".to_string());
+        todo.start_time = Some(1_000);
+        let mut data = AppData {
+            version: DATA_VERSION,
+            saved_at: 0,
+            projects: vec![Project {
+                todos: vec![todo],
+                ..Project::new("p".to_string())
+            }],
+            trash: vec![],
+            archived_projects: vec![],
+            templates: vec![],
+        };
+        data.finish_interrupted_sessions();
+        assert!(!data.projects[0].todos[0].is_working());
+        assert_eq!(data.projects[0].todos[0].total_duration(), 0);
+    }
+
+    #[test]
+    fn old_data_without_created_at_loads() {
+        let json = r#"{"projects": [{"name": "p", "todos": [{"title": "t", "description": "",
+            "completed": false, "start_time": null, "end_time": null, "total_duration": 0}]}]}"#;
+        let data = AppData::migrate(serde_json::from_str(json).unwrap()).unwrap();
+        assert_eq!(data.projects[0].created_at, 0);
+        assert_eq!(data.projects[0].todos[0].created_at, 0);
+        assert!(Todo::new("new".to_string()).created_at > 0);
+        assert_eq!(data.projects[0].todos[0].due_date, None);
+    }
+
+    #[test]
+    fn v1_total_duration_is_migrated_to_sessions() {
+        let todo = |total: u64, sessions: &str| {
+            format!(
+                r#"{{"title": "t", "description": "", "completed": false, "start_time": null,
+                "end_time": null, "total_duration": {}, "sessions": [{}]}}"#,
+                total, sessions
+            )
+        };
+        let json = format!(
+            r#"{{"version": 1, "projects": [{{"name": "p", "todos": [{}, {}, {}, {}]}}],
+            "trash": [{{"deleted_at": 0, "item": {{"todo": {{"project": "p", "todo": {}}}}}}}]}}"#,
+            // 旧数据只有总耗时
+            todo(90, ""),
+            // 总耗时多于计时记录
+            todo(150, r#"{"start": 10, "end": 110}"#),
+            // 重复的 todo 完成后总耗时只包括最后一段记录
+            todo(
+                30,
+                r#"{"start": 10, "end": 110}, {"start": 200, "end": 230}"#
+            ),
+            // 手动修改过的文件中结束时间早于开始时间的记录按 0 计
+            todo(
+                20,
+                r#"{"start": 110, "end": 10}, {"start": 200, "end": 220}"#
+            ),
+            todo(100, r#"{"start": 10, "end": 110}"#),
+        );
+        let data = AppData::migrate(serde_json::from_str(&json).unwrap()).unwrap();
+        let todos = &data.projects[0].todos;
+        assert_eq!(data.version, DATA_VERSION);
+        assert_eq!(todos[0].total_duration(), 90);
+        assert_eq!(todos[1].total_duration(), 150);
+        assert_eq!(todos[1].untracked, 50);
+        assert_eq!(todos[2].total_duration(), 30);
+        assert_eq!(todos[2].lifetime_duration(), 130);
+        assert_eq!(todos[3].total_duration(), 20);
+        assert_eq!(todos[3].lifetime_duration(), 20);
+        let Trashed::Todo { todo, .. } = &data.trash[0].item else {
+            panic!("回收站中应为 todo");
+        };
+        assert_eq!((todo.total_duration(), todo.untracked), (100, 0));
+    }
+
+    #[test]
+    fn migration_rejects_data_that_is_not_an_object() {
+        assert!(AppData::migrate(serde_json::json!([])).is_err());
+        assert!(AppData::migrate(serde_json::json!("data")).is_err());
+        let data = AppData::migrate(serde_json::json!({"projects": []})).unwrap();
+        assert_eq!(data.version, DATA_VERSION);
+    }
+
+    #[test]
+    fn completing_recurring_todo_schedules_next_occurrence() {
+        let day = |m, d| NaiveDate::from_ymd_opt(2026, m, d).unwrap();
+        let mut todo = Todo::new("t".to_string());
+        assert_eq!(todo.complete(day(3, 4)), None);
+        assert!(todo.completed);
+
+        let mut todo = Todo::new("t".to_string());
+        todo.recurrence = Some(Recurrence::Weekly);
+        todo.log_session(0, 60);
+        todo.subtasks.push(Subtask {
+            title: "s".to_string(),
+            completed: true,
+        });
+        // 没有截止日期时从今天开始算
+        assert_eq!(todo.complete(day(3, 4)), Some(day(3, 11)));
+        assert!(!todo.completed);
+        assert_eq!(todo.total_duration(), 0);
+        assert_eq!(todo.lifetime_duration(), 60);
+        assert!(!todo.subtasks[0].completed);
+
+        // 错过的次数直接跳过，保持原来的日子
+        todo.recurrence = Some(Recurrence::Daily);
+        assert_eq!(todo.complete(day(3, 20)), Some(day(3, 21)));
+
+        todo.recurrence = Some(Recurrence::Monthly);
+        todo.due_date = Some(day(1, 31));
+        assert_eq!(todo.complete(day(1, 31)), Some(day(2, 28)));
+    }
+
+    #[test]
+    fn status_follows_completion_and_doing() {
+        let today = NaiveDate::from_ymd_opt(2026, 3, 4).unwrap();
+        let mut todo = Todo::new("t".to_string());
+        assert_eq!(todo.status(), Status::Todo);
+        todo.set_status(Status::Doing, today);
+        assert_eq!(todo.status(), Status::Doing);
+        todo.set_status(Status::Done, today);
+        assert!(todo.completed);
+        assert_eq!(todo.status(), Status::Done);
+        // 重新打开的 todo 回到待办
+        todo.set_status(Status::Todo, today);
+        assert_eq!(todo.status(), Status::Todo);
+
+        todo.recurrence = Some(Recurrence::Daily);
+        todo.set_status(Status::Doing, today);
+        assert!(todo.set_status(Status::Done, today).is_some());
+        assert_eq!(todo.status(), Status::Todo);
+    }
+
+    #[test]
+    fn trash_older_than_retention_is_purged() {
+        let todo = |title: &str| Trashed::Todo {
+            project: "p".to_string(),
+            todo: Todo::new(title.to_string()),
+        };
+        let mut trash = vec![
+            TrashItem::new(todo("old"), 0),
+            TrashItem::new(todo("new"), 10 * 86400),
+        ];
+        assert_eq!(purge_trash(&mut trash, 31 * 86400, 30), 1);
+        assert_eq!(trash[0].label(), "p / new");
+    }
+
+    #[test]
+    fn tags_are_split_from_input() {
+        let (title, tags) = parse_tags("buy  milk #errand #home #Errand #");
+        assert_eq!(title, "buy milk #");
+        assert_eq!(tags, ["errand", "home"]);
+        let (title, tags) = parse_tags("#only");
+        assert!(title.is_empty());
+        assert_eq!(tags, ["only"]);
+    }
+
+    #[test]
+    fn quick_add_tokens_fill_fields_and_bad_ones_stay_in_title() {
+        // 2026-03-04 是星期三
+        let today = NaiveDate::from_ymd_opt(2026, 3, 4).unwrap();
+        let parsed = parse_quick_add("Write report !high #work due:friday est:2h", today);
+        assert_eq!(parsed.title, "Write report");
+        assert_eq!(parsed.tags, ["work"]);
+        assert_eq!(parsed.priority, Some(Priority::High));
+        assert_eq!(parsed.due_date, NaiveDate::from_ymd_opt(2026, 3, 6));
+        assert_eq!(parsed.estimate, Some(7200));
+
+        let parsed = parse_quick_add("ping !urgent due:someday est:soon", today);
+        assert_eq!(parsed.title, "ping !urgent due:someday est:soon");
+        assert_eq!(parsed.priority, None);
+        assert_eq!(parsed.due_date, None);
+        assert_eq!(parsed.estimate, None);
+    }
+
+    #[test]
+    fn fuzzy_matching_prefers_consecutive_and_word_starts() {
+        assert!(fuzzy_score("bml", "buy milk").is_some());
+        assert!(fuzzy_score("BM", "buy milk").is_some());
+        assert_eq!(fuzzy_score("mb", "buy milk"), None);
+        assert!(fuzzy_score("", "anything").is_some());
+
+        let consecutive = fuzzy_score("milk", "buy milk").unwrap();
+        let scattered = fuzzy_score("milk", "make it look").unwrap();
+        assert!(consecutive > scattered);
+        assert!(fuzzy_score("rep", "write report") > fuzzy_score("rep", "prepare"));
+    }
+
+    #[test]
+    fn due_date_round_trips_and_marks_overdue() {
+        let mut todo = Todo::new("t".to_string());
+        todo.due_date = NaiveDate::from_ymd_opt(2026, 3, 4);
+        let json = serde_json::to_string(&todo).unwrap();
+        assert!(json.contains(r#""due_date":"2026-03-04""#));
+        let todo: Todo = serde_json::from_str(&json).unwrap();
+        assert_eq!(todo.due_date, NaiveDate::from_ymd_opt(2026, 3, 4));
+
+        let day = |d| NaiveDate::from_ymd_opt(2026, 3, d).unwrap();
+        assert!(!todo.is_overdue(day(4)));
+        assert!(todo.is_overdue(day(5)));
+        let done = Todo {
+            completed: true,
+            ..todo
+        };
+        assert!(!done.is_overdue(day(5)));
+    }
+
+    #[test]
+    fn sorted_indices_follow_sort_mode() {
+        let mut project = Project::new("p".to_string());
+        for (title, completed, duration) in [("b", true, 10), ("C", false, 30), ("a", false, 10)] {
+            let mut todo = Todo::new(title.to_string());
+            todo.completed = completed;
+            todo.log_session(0, duration);
+            project.todos.push(todo);
+        }
+
+        assert_eq!(project.sorted_indices(), vec![0, 1, 2]);
+        project.sort_mode = SortMode::Title;
+        assert_eq!(project.sorted_indices(), vec![2, 0, 1]);
+        project.sort_mode = SortMode::Completion;
+        assert_eq!(project.sorted_indices(), vec![1, 2, 0]);
+        project.sort_mode = SortMode::Duration;
+        assert_eq!(project.sorted_indices(), vec![1, 0, 2]);
+
+        project.todos[0].created_at = 30;
+        project.todos[1].created_at = 10;
+        project.todos[2].created_at = 20;
+        project.sort_mode = SortMode::Created;
+        assert_eq!(project.sorted_indices(), vec![0, 2, 1]);
+
+        project.todos[1].due_date = NaiveDate::from_ymd_opt(2026, 3, 5);
+        project.todos[2].due_date = NaiveDate::from_ymd_opt(2026, 3, 4);
+        project.sort_mode = SortMode::DueDate;
+        assert_eq!(project.sorted_indices(), vec![2, 1, 0]);
+
+        project.todos[0].priority = Some(Priority::Low);
+        project.todos[2].priority = Some(Priority::High);
+        project.sort_mode = SortMode::Priority;
+        assert_eq!(project.sorted_indices(), vec![2, 0, 1]);
+        assert_eq!(SortMode::Duration.next(), SortMode::Original);
+    }
+
+    #[test]
+    fn projects_are_arranged_as_a_tree() {
+        let project = |name: &str, parent: Option<&str>| Project {
+            parent: parent.map(str::to_string),
+            ..Project::new(name.to_string())
+        };
+        let mut projects = vec![
+            project("会议", Some("工作")),
+            project("工作", None),
+            project("周会", Some("会议")),
+            project("个人", None),
+            project("报告", Some("工作")),
+            project("孤儿", Some("不存在")),
+            project("a", Some("b")),
+            project("b", Some("A")),
+        ];
+        arrange_tree(&mut projects);
+        let names: Vec<&str> = projects.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["工作", "会议", "周会", "报告", "个人", "孤儿", "a", "b"]
+        );
+        let depths: Vec<usize> = (0..projects.len())
+            .map(|i| project_depth(&projects, i))
+            .collect();
+        assert_eq!(depths, [0, 1, 2, 1, 0, 0, 0, 1]);
+        assert_eq!(projects[5].parent, None);
+        assert_eq!(project_subtree(&projects, 0), 0..4);
+        assert_eq!(project_subtree(&projects, 1), 1..3);
+        assert_eq!(project_subtree(&projects, 4), 4..5);
+        assert_eq!(project_subtree(&projects, 7), 7..8);
+    }
+
+    #[test]
+    fn estimates_roll_up_per_project() {
+        let mut project = Project::new("p".to_string());
+        assert_eq!(project.estimate_progress(0), None);
+        for (estimate, duration) in [(Some(3600), 4000), (Some(1800), 0), (None, 600)] {
+            let mut todo = Todo::new("t".to_string());
+            todo.estimate = estimate;
+            todo.log_session(0, duration);
+            project.todos.push(todo);
+        }
+        assert!(project.todos[0].over_budget(0));
+        assert!(!project.todos[1].over_budget(0));
+        assert!(!project.todos[2].over_budget(0));
+        assert_eq!(project.estimate_progress(0), Some((4000, 5400)));
+    }
+
+    #[test]
+    fn project_time_can_exclude_completed_todos() {
+        let mut project = Project::new("p".to_string());
+        for (completed, duration) in [(true, 600), (false, 300)] {
+            let mut todo = Todo::new("t".to_string());
+            todo.completed = completed;
+            todo.log_session(0, duration);
+            project.todos.push(todo);
+        }
+        project.todos[1].start_time = Some(1000);
+        assert_eq!(project.tracked_time(1060, true), 960);
+        assert_eq!(project.tracked_time(1060, false), 360);
+    }
+
+    #[test]
+    fn completion_time_is_recorded_and_cleared() {
+        let today = NaiveDate::from_ymd_opt(2026, 3, 4).unwrap();
+        let mut todo = Todo::new("t".to_string());
+        assert_eq!(todo.complete(today), None);
+        assert!(todo.completed_at.is_some());
+        todo.set_status(Status::Todo, today);
+        assert_eq!(todo.completed_at, None);
+
+        // 重复的 todo 完成后没有保持完成，不记录完成时间
+        todo.recurrence = Some(Recurrence::Daily);
+        todo.complete(today);
+        assert_eq!(todo.completed_at, None);
+    }
+}
@@ -0,0 +1,96 @@
+use crate::keys::Action;
+use crate::model::{fuzzy_score, SortMode};
+use std::cmp::Reverse;
+
+// 命令面板中的命令：所有可以绑定按键的操作，以及只能在命令面板中使用的功能
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Command {
+    Action(Action),
+    Sort(SortMode), // 把当前项目设为该排序方式
+    ExportCsv,      // 把所有计时记录导出为 CSV
+}
+
+// 排序方式和它们的英文名称
+const SORTS: [(SortMode, &str); 7] = [
+    (SortMode::Original, "sort by original order"),
+    (SortMode::Title, "sort by title"),
+    (SortMode::Completion, "sort by completion"),
+    (SortMode::Created, "sort by created"),
+    (SortMode::DueDate, "sort by due date"),
+    (SortMode::Priority, "sort by priority"),
+    (SortMode::Duration, "sort by duration"),
+];
+
+impl Command {
+    // 面板中显示的名称
+    pub fn label(self) -> String {
+        match self {
+            Command::Action(action) => action.description().to_string(),
+            Command::Sort(mode) => format!("排序：{}", mode.label()),
+            Command::ExportCsv => "把计时记录导出为 CSV".to_string(),
+        }
+    }
+
+    // 英文名称，如 "move to project"、"sort by due date"、"export csv"
+    pub fn name(self) -> String {
+        match self {
+            Command::Action(action) => action.name().replace('_', " "),
+            Command::Sort(mode) => SORTS
+                .iter()
+                .find(|(m, _)| *m == mode)
+                .map_or_else(String::new, |(_, name)| name.to_string()),
+            Command::ExportCsv => "export csv".to_string(),
+        }
+    }
+
+    // 中文名称和英文名称中较高的匹配分数
+    fn score(self, query: &str) -> Option<i64> {
+        [self.label(), self.name()]
+            .iter()
+            .filter_map(|text| fuzzy_score(query, text))
+            .max()
+    }
+}
+
+// 所有命令，打开命令面板的操作除外
+pub fn commands() -> Vec<Command> {
+    Action::ALL
+        .iter()
+        .filter(|&&action| action != Action::CommandPalette)
+        .map(|&action| Command::Action(action))
+        .chain(SORTS.iter().map(|&(mode, _)| Command::Sort(mode)))
+        .chain([Command::ExportCsv])
+        .collect()
+}
+
+// 按输入模糊匹配命令的中文或英文名称，按分数从高到低排列，输入为空时列出所有命令
+pub fn search(query: &str) -> Vec<Command> {
+    if query.trim().is_empty() {
+        return commands();
+    }
+    let mut results: Vec<(i64, Command)> = commands()
+        .into_iter()
+        .filter_map(|command| command.score(query).map(|score| (score, command)))
+        .collect();
+    results.sort_by_key(|&(score, _)| Reverse(score));
+    results.into_iter().map(|(_, command)| command).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_match_english_or_chinese_names() {
+        assert_eq!(
+            search("move to project")[0],
+            Command::Action(Action::MoveToProject)
+        );
+        assert_eq!(search("sort by due")[0], Command::Sort(SortMode::DueDate));
+        assert_eq!(search("export csv")[0], Command::ExportCsv);
+        assert_eq!(search("回收站")[0], Command::Action(Action::ShowTrash));
+        assert!(search("qqqzzz").is_empty());
+        assert_eq!(search("").len(), commands().len());
+        assert!(!commands().contains(&Command::Action(Action::CommandPalette)));
+    }
+}
@@ -0,0 +1,182 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+// 命令行 --data-file 指定的数据文件
+static DATA_FILE: OnceLock<String> = OnceLock::new();
+
+// 命令行 --workspace 指定或界面中切换到的工作区，None 为默认工作区
+static WORKSPACE: Mutex<Option<String>> = Mutex::new(None);
+
+// 使用指定的数据文件，只在启动时设置一次
+pub fn set_data_file(path: String) {
+    let _ = DATA_FILE.set(path);
+}
+
+// 是否用 --data-file 指定了数据文件，此时不能切换工作区
+pub fn has_data_file() -> bool {
+    DATA_FILE.get().is_some()
+}
+
+// 当前工作区，默认工作区为 None
+pub fn workspace() -> Option<String> {
+    WORKSPACE.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+// 切换工作区，之后的数据、状态和工作区配置文件都使用该工作区的
+pub fn set_workspace(name: Option<String>) -> Result<(), String> {
+    if has_data_file() {
+        return Err("使用 --data-file 时不能切换工作区".to_string());
+    }
+    if let Some(name) = &name {
+        check_workspace_name(name)?;
+    }
+    *WORKSPACE.lock().unwrap_or_else(|e| e.into_inner()) = name;
+    Ok(())
+}
+
+// 工作区名称会成为文件名的一部分，只允许字母、数字、- 和 _
+pub fn check_workspace_name(name: &str) -> Result<(), String> {
+    let valid = name
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+    match name.is_empty() || !valid {
+        true => Err(format!(
+            "无效的工作区名称「{}」，只能包含字母、数字、- 和 _",
+            name
+        )),
+        false => Ok(()),
+    }
+}
+
+// 工作区中的文件名，默认工作区不变，工作区 work 中的 data.json 为 data.work.json
+pub fn workspace_file(name: &str, workspace: Option<&str>) -> String {
+    match (workspace, name.rsplit_once('.')) {
+        (Some(workspace), Some((stem, ext))) => format!("{}.{}.{}", stem, workspace, ext),
+        _ => name.to_string(),
+    }
+}
+
+// 旧版本在所有系统上都使用 ~/.config/s_todo
+fn legacy_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config").join("s_todo"))
+}
+
+// 系统目录 base 下 s_todo 目录中的文件，旧目录中已有该文件时继续使用旧位置
+// 无法确定系统目录时降级到当前目录的 s_todo_<name>
+fn resolve(base: Option<PathBuf>, legacy: Option<PathBuf>, name: &str) -> String {
+    if let Some(old) = legacy
+        .map(|dir| dir.join(name))
+        .filter(|path| path.exists())
+    {
+        return old.to_string_lossy().into_owned();
+    }
+    match base {
+        Some(dir) => dir.join("s_todo").join(name).to_string_lossy().into_owned(),
+        None => format!("./s_todo_{}", name),
+    }
+}
+
+// 数据文件：Linux 为 $XDG_DATA_HOME/s_todo，macOS 为 ~/Library/Application Support/s_todo，
+// Windows 为 %APPDATA%\s_todo
+// 其他工作区的数据文件与默认的 data.json 放在同一目录
+pub fn data_file() -> String {
+    if let Some(path) = DATA_FILE.get() {
+        return path.clone();
+    }
+    let default = resolve(dirs::data_dir(), legacy_dir(), "data.json");
+    match workspace() {
+        Some(workspace) => Path::new(&default)
+            .with_file_name(workspace_file("data.json", Some(&workspace)))
+            .to_string_lossy()
+            .into_owned(),
+        None => default,
+    }
+}
+
+// 已有数据文件的工作区，不含默认工作区，按名称排列
+pub fn workspaces() -> Vec<String> {
+    let default = resolve(dirs::data_dir(), legacy_dir(), "data.json");
+    Path::new(&default)
+        .parent()
+        .map(list_workspaces)
+        .unwrap_or_default()
+}
+
+// 目录中的 data.<名称>.json，备份和活动记录等其他文件不算
+fn list_workspaces(dir: &Path) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| {
+            let file_name = entry.ok()?.file_name().into_string().ok()?;
+            let name = file_name.strip_prefix("data.")?.strip_suffix(".json")?;
+            check_workspace_name(name).ok()?;
+            Some(name.to_string())
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+// 工作区的状态文件和配置覆盖文件，如 state.work.json
+pub fn workspace_config_file(name: &str) -> String {
+    config_file(&workspace_file(name, workspace().as_deref()))
+}
+
+// 配置、主题、按键和状态文件：Linux 为 $XDG_CONFIG_HOME/s_todo，其他系统与数据文件相同
+pub fn config_file(name: &str) -> String {
+    resolve(dirs::config_dir(), legacy_dir(), name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn existing_files_in_the_old_directory_are_kept() {
+        let legacy = std::env::temp_dir().join(format!("s_todo_paths_{}", std::process::id()));
+        std::fs::create_dir_all(&legacy).unwrap();
+        std::fs::write(legacy.join("old.json"), "{}").unwrap();
+        let base = Some(PathBuf::from("/data"));
+
+        assert_eq!(
+            resolve(base.clone(), Some(legacy.clone()), "old.json"),
+            legacy.join("old.json").to_string_lossy()
+        );
+        assert_eq!(
+            resolve(base, Some(legacy), "new.json"),
+            PathBuf::from("/data/s_todo/new.json").to_string_lossy()
+        );
+        assert_eq!(resolve(None, None, "data.json"), "./s_todo_data.json");
+    }
+
+    #[test]
+    fn workspaces_have_their_own_files() {
+        assert_eq!(workspace_file("data.json", None), "data.json");
+        assert_eq!(workspace_file("data.json", Some("work")), "data.work.json");
+        assert_eq!(
+            workspace_file("state.json", Some("私人")),
+            "state.私人.json"
+        );
+        assert!(check_workspace_name("my-work_2").is_ok());
+        assert!(check_workspace_name("").is_err());
+        assert!(check_workspace_name("../x").is_err());
+        assert!(check_workspace_name("a.b").is_err());
+
+        let dir = std::env::temp_dir().join(format!("s_todo_workspaces_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for file in [
+            "data.json",
+            "data.work.json",
+            "data.personal.json",
+            "data.work.json.bak.1",
+            "data.work.activity.jsonl",
+            "data.activity.jsonl",
+        ] {
+            std::fs::write(dir.join(file), "").unwrap();
+        }
+        assert_eq!(list_workspaces(&dir), ["personal", "work"]);
+        assert_eq!(list_workspaces(&dir.join("missing")), Vec::<String>::new());
+    }
+}
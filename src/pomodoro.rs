@@ -0,0 +1,78 @@
+// 番茄钟的阶段
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Phase {
+    Work,  // 工作，选中的 todo 同时在计时
+    Break, // 休息
+}
+
+impl Phase {
+    pub fn label(self) -> &'static str {
+        match self {
+            Phase::Work => "🍅",
+            Phase::Break => "☕",
+        }
+    }
+}
+
+// 正在进行的番茄钟，只在界面运行期间有效，不保存
+pub struct Pomodoro {
+    pub phase: Phase,
+    pub started: u64, // 当前阶段的开始时间（时间戳）
+}
+
+impl Pomodoro {
+    pub fn new(now: u64) -> Self {
+        Self {
+            phase: Phase::Work,
+            started: now,
+        }
+    }
+
+    // 当前阶段的时长（秒）
+    fn length(&self, work_secs: u64, break_secs: u64) -> u64 {
+        match self.phase {
+            Phase::Work => work_secs,
+            Phase::Break => break_secs,
+        }
+    }
+
+    // 当前阶段剩余的秒数
+    pub fn remaining(&self, now: u64, work_secs: u64, break_secs: u64) -> u64 {
+        (self.started + self.length(work_secs, break_secs)).saturating_sub(now)
+    }
+
+    // 当前阶段到时间时返回结束的阶段，工作结束后进入休息
+    pub fn advance(&mut self, now: u64, work_secs: u64, break_secs: u64) -> Option<Phase> {
+        if self.remaining(now, work_secs, break_secs) > 0 {
+            return None;
+        }
+        let finished = self.phase;
+        if finished == Phase::Work {
+            self.phase = Phase::Break;
+            self.started = now;
+        }
+        Some(finished)
+    }
+}
+
+// 倒计时显示，如 "24:59"
+pub fn format_countdown(secs: u64) -> String {
+    format!("{:02}:{:02}", secs / 60, secs % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn work_is_followed_by_break() {
+        let mut pomodoro = Pomodoro::new(1_000);
+        assert_eq!(pomodoro.remaining(1_060, 1500, 300), 1440);
+        assert_eq!(pomodoro.advance(2_499, 1500, 300), None);
+        assert_eq!(pomodoro.advance(2_500, 1500, 300), Some(Phase::Work));
+        assert_eq!(pomodoro.phase, Phase::Break);
+        assert_eq!(pomodoro.remaining(2_500, 1500, 300), 300);
+        assert_eq!(pomodoro.advance(2_800, 1500, 300), Some(Phase::Break));
+        assert_eq!(format_countdown(1499), "24:59");
+    }
+}
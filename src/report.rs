@@ -0,0 +1,972 @@
+use crate::model::{format_duration, parse_duration, DurationUnit, Project, Status, Todo};
+use chrono::{Datelike, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Weekday};
+
+// 报告的时间范围
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ReportRange {
+    Day,
+    Week,
+    All,
+}
+
+impl ReportRange {
+    // 循环切换到下一个范围
+    pub fn next(self) -> ReportRange {
+        match self {
+            ReportRange::Day => ReportRange::Week,
+            ReportRange::Week => ReportRange::All,
+            ReportRange::All => ReportRange::Day,
+        }
+    }
+
+    // 往前数 offset 个范围的起止日期，包括两端，不超过今天
+    pub fn period(self, offset: u64, today: NaiveDate) -> (NaiveDate, NaiveDate) {
+        match self {
+            ReportRange::Day => {
+                let date = today - chrono::Days::new(offset);
+                (date, date)
+            }
+            ReportRange::Week => {
+                let monday =
+                    today - chrono::Days::new(today.weekday().num_days_from_monday() as u64);
+                let first = monday - chrono::Days::new(offset * 7);
+                (first, (first + chrono::Days::new(6)).min(today))
+            }
+            ReportRange::All => (NaiveDate::MIN, today),
+        }
+    }
+
+    // 往前数 offset 个范围的名称，如 "今天"、"上周"、"2026-03-02 起的一周"
+    pub fn label(self, offset: u64, today: NaiveDate) -> String {
+        let (first, _) = self.period(offset, today);
+        match (self, offset) {
+            (ReportRange::Day, 0) => "今天".to_string(),
+            (ReportRange::Day, 1) => "昨天".to_string(),
+            (ReportRange::Day, _) => format_day(first),
+            (ReportRange::Week, 0) => "本周".to_string(),
+            (ReportRange::Week, 1) => "上周".to_string(),
+            (ReportRange::Week, _) => format!("{} 起的一周", first.format("%Y-%m-%d")),
+            (ReportRange::All, _) => "全部".to_string(),
+        }
+    }
+}
+
+// 某一天中某个 todo 的耗时
+pub struct DayEntry {
+    pub project: String,
+    pub todo: String,
+    pub secs: u64,
+}
+
+// 某一天的耗时汇总
+pub struct DayReport {
+    pub date: NaiveDate,
+    pub total: u64,
+    pub entries: Vec<DayEntry>,
+}
+
+pub struct Report {
+    pub total: u64,
+    pub projects: Vec<(String, u64)>, // 各项目的耗时，从多到少排列
+    pub days: Vec<DayReport>,         // 从新到旧排列
+    pub undated: u64,                 // 旧数据中没有计时记录的耗时，只在全部范围中统计
+}
+
+// 时间戳对应的本地日期
+pub fn local_date(ts: u64) -> NaiveDate {
+    Local
+        .timestamp_opt(ts as i64, 0)
+        .earliest()
+        .map_or(NaiveDate::MIN, |dt| dt.date_naive())
+}
+
+// 本地日期零点的时间戳
+fn day_start(date: NaiveDate) -> Option<u64> {
+    date.and_hms_opt(0, 0, 0)?
+        .and_local_timezone(Local)
+        .earliest()
+        .map(|dt| dt.timestamp().max(0) as u64)
+}
+
+// 时间戳对应的本地时间，如 "2026-03-04 09:30"
+pub fn format_time(ts: u64) -> String {
+    Local
+        .timestamp_opt(ts as i64, 0)
+        .earliest()
+        .map_or_else(String::new, |dt| dt.format("%Y-%m-%d %H:%M").to_string())
+}
+
+// 计时记录的显示和编辑格式，如 "2026-03-04 09:30 - 2026-03-04 10:15"
+pub fn format_session(start: u64, end: u64) -> String {
+    format!("{} - {}", format_time(start), format_time(end))
+}
+
+// 解析 format_session 格式的时间段，结束时间可以只写 HH:MM，表示和开始是同一天
+pub fn parse_session(input: &str) -> Result<(u64, u64), String> {
+    let error = || "格式应为 YYYY-MM-DD HH:MM - YYYY-MM-DD HH:MM".to_string();
+    let (start, end) = input.split_once(" - ").ok_or_else(error)?;
+    let start =
+        NaiveDateTime::parse_from_str(start.trim(), "%Y-%m-%d %H:%M").map_err(|_| error())?;
+    let end = end.trim();
+    let end = NaiveDateTime::parse_from_str(end, "%Y-%m-%d %H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(end, "%H:%M").map(|t| start.date().and_time(t)))
+        .map_err(|_| error())?;
+
+    let to_ts = |dt: NaiveDateTime| {
+        dt.and_local_timezone(Local)
+            .earliest()
+            .map(|dt| dt.timestamp().max(0) as u64)
+            .ok_or_else(error)
+    };
+    let (start, end) = (to_ts(start)?, to_ts(end)?);
+    if end < start {
+        return Err("结束时间不能早于开始时间".to_string());
+    }
+    Ok((start, end))
+}
+
+// 补记到今天以外的日期时，从当天这个时间开始
+const MANUAL_ENTRY_HOUR: u32 = 9;
+
+// 解析补记的耗时，如 "45m"、"1h30m yesterday"、"2h 2026-03-02"，返回计时记录的起止时间
+// 记到今天时以 now 为结束时间，记到其他日期时从当天 9 点开始，记录不能超出当天开始到 now 的范围
+pub fn parse_time_entry(input: &str, now: u64) -> Result<(u64, u64), String> {
+    let error = || "格式应为 时长 [日期]，如 45m、1h30m 昨天、2h 2026-03-02".to_string();
+    let mut words = input.split_whitespace();
+    let secs = words.next().and_then(parse_duration).ok_or_else(error)?;
+    let today = local_date(now);
+    let date = match words.next() {
+        None | Some("today") | Some("今天") => today,
+        Some("yesterday") | Some("昨天") => today.pred_opt().ok_or_else(error)?,
+        Some(date) => NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| error())?,
+    };
+    if words.next().is_some() {
+        return Err(error());
+    }
+    let future = || "不能补记未来的时间".to_string();
+    if date > today {
+        return Err(future());
+    }
+
+    let midnight = day_start(date).ok_or_else(error)?;
+    if date == today {
+        if secs > now.saturating_sub(midnight) {
+            return Err("补记到今天的时间不能超过今天已经过去的时间".to_string());
+        }
+        return Ok((now - secs, now));
+    }
+    let start = midnight + MANUAL_ENTRY_HOUR as u64 * 3600;
+    match start.checked_add(secs) {
+        Some(end) if end <= now => Ok((start, end)),
+        _ => Err(future()),
+    }
+}
+
+// 按本地日期拆分一段时间，跨过零点的部分记到各自的日期
+pub fn split_by_day(start: u64, end: u64) -> Vec<(NaiveDate, u64)> {
+    let mut parts = Vec::new();
+    let mut cursor = start;
+    while cursor < end {
+        let date = local_date(cursor);
+        let next_day = date
+            .succ_opt()
+            .and_then(day_start)
+            .filter(|&next| next > cursor)
+            .unwrap_or(end);
+        let part_end = next_day.min(end);
+        parts.push((date, part_end - cursor));
+        cursor = part_end;
+    }
+    parts
+}
+
+// 统计往前数 offset 个范围内每天和每个项目的耗时，正在进行的计时算到 now 为止
+pub fn daily_report(projects: &[Project], range: ReportRange, offset: u64, now: u64) -> Report {
+    let (first_day, last_day) = range.period(offset, local_date(now));
+
+    let mut days: Vec<DayReport> = Vec::new();
+    let mut undated = 0;
+    for project in projects {
+        // 归档的 todo 同样计入报告
+        for todo in project.todos.iter().chain(&project.archived) {
+            let mut sessions: Vec<(u64, u64)> =
+                todo.sessions.iter().map(|s| (s.start, s.end)).collect();
+            if let (true, Some(start)) = (todo.is_working(), todo.start_time) {
+                sessions.push((start, now.max(start)));
+            }
+
+            undated += todo.untracked;
+
+            for (start, end) in sessions {
+                for (date, secs) in split_by_day(start, end) {
+                    if date < first_day || date > last_day || secs == 0 {
+                        continue;
+                    }
+                    add_entry(&mut days, date, &project.name, &todo.title, secs);
+                }
+            }
+        }
+    }
+
+    days.sort_by_key(|d| std::cmp::Reverse(d.date));
+    let mut project_totals: Vec<(String, u64)> = Vec::new();
+    for day in &mut days {
+        day.entries.sort_by_key(|e| std::cmp::Reverse(e.secs));
+        for entry in &day.entries {
+            match project_totals
+                .iter_mut()
+                .find(|(name, _)| *name == entry.project)
+            {
+                Some((_, total)) => *total += entry.secs,
+                None => project_totals.push((entry.project.clone(), entry.secs)),
+            }
+        }
+    }
+    project_totals.sort_by_key(|&(_, total)| std::cmp::Reverse(total));
+
+    Report {
+        total: days.iter().map(|d| d.total).sum(),
+        projects: project_totals,
+        days,
+        undated: if range == ReportRange::All {
+            undated
+        } else {
+            0
+        },
+    }
+}
+
+fn add_entry(days: &mut Vec<DayReport>, date: NaiveDate, project: &str, todo: &str, secs: u64) {
+    let index = match days.iter().position(|d| d.date == date) {
+        Some(index) => index,
+        None => {
+            days.push(DayReport {
+                date,
+                total: 0,
+                entries: Vec::new(),
+            });
+            days.len() - 1
+        }
+    };
+
+    let day = &mut days[index];
+    day.total += secs;
+    match day
+        .entries
+        .iter_mut()
+        .find(|e| e.project == project && e.todo == todo)
+    {
+        Some(entry) => entry.secs += secs,
+        None => day.entries.push(DayEntry {
+            project: project.to_string(),
+            todo: todo.to_string(),
+            secs,
+        }),
+    }
+}
+
+fn weekday_name(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "周一",
+        Weekday::Tue => "周二",
+        Weekday::Wed => "周三",
+        Weekday::Thu => "周四",
+        Weekday::Fri => "周五",
+        Weekday::Sat => "周六",
+        Weekday::Sun => "周日",
+    }
+}
+
+// 统计面板中显示的最近天数
+pub const RECENT_DAYS: u64 = 14;
+// 统计面板中列出的最忙的天数
+pub const BUSIEST_DAYS: usize = 5;
+
+// 一个项目的统计，包括归档的 todo
+pub struct ProjectStats {
+    pub name: String,
+    pub total: u64,
+    pub completed: usize,
+    pub todos: usize,
+}
+
+pub struct Stats {
+    pub projects: Vec<ProjectStats>,
+    pub completed_this_week: usize, // 本周（周一开始）完成的 todo 数量
+    pub average_completion: Option<u64>, // 从创建到完成的平均秒数，没有可统计的 todo 时为 None
+    pub recent_days: Vec<u64>,      // 最近 RECENT_DAYS 天每天的耗时，从旧到新，最后一项是今天
+    pub busiest_days: Vec<(NaiveDate, u64)>, // 耗时最多的几天，从多到少
+}
+
+// 统计每个项目的耗时和完成数量，以及按天的耗时，正在进行的计时算到 now 为止
+pub fn stats(projects: &[Project], now: u64) -> Stats {
+    let project_stats = projects
+        .iter()
+        .map(|project| {
+            let todos: Vec<&Todo> = project.todos.iter().chain(&project.archived).collect();
+            ProjectStats {
+                name: project.name.clone(),
+                total: todos
+                    .iter()
+                    .map(|t| t.lifetime_duration() + t.session_elapsed(now))
+                    .sum(),
+                completed: todos.iter().filter(|t| t.completed).count(),
+                todos: todos.len(),
+            }
+        })
+        .collect();
+
+    // 只统计记录了创建和完成时间的 todo
+    let today = local_date(now);
+    let (week_start, _) = ReportRange::Week.period(0, today);
+    let completed_at: Vec<(u64, u64)> = projects
+        .iter()
+        .flat_map(|project| project.todos.iter().chain(&project.archived))
+        .filter(|todo| todo.completed)
+        .filter_map(|todo| Some((todo.created_at, todo.completed_at?)))
+        .collect();
+    let completed_this_week = completed_at
+        .iter()
+        .filter(|&&(_, at)| local_date(at) >= week_start)
+        .count();
+    let spans: Vec<u64> = completed_at
+        .iter()
+        .filter(|&&(created, _)| created > 0)
+        .map(|&(created, at)| at.saturating_sub(created))
+        .collect();
+    let average_completion = match spans.len() {
+        0 => None,
+        n => Some(spans.iter().sum::<u64>() / n as u64),
+    };
+
+    let days = daily_report(projects, ReportRange::All, 0, now).days;
+    let recent_days = (0..RECENT_DAYS)
+        .rev()
+        .map(|ago| {
+            let date = today - chrono::Days::new(ago);
+            days.iter().find(|d| d.date == date).map_or(0, |d| d.total)
+        })
+        .collect();
+    let mut busiest_days: Vec<(NaiveDate, u64)> = days.iter().map(|d| (d.date, d.total)).collect();
+    busiest_days.sort_by_key(|&(_, total)| std::cmp::Reverse(total));
+    busiest_days.truncate(BUSIEST_DAYS);
+
+    Stats {
+        projects: project_stats,
+        completed_this_week,
+        average_completion,
+        recent_days,
+        busiest_days,
+    }
+}
+
+// 日期和星期，如 "2026-03-04 周三"
+pub fn format_day(date: NaiveDate) -> String {
+    format!(
+        "{} {}",
+        date.format("%Y-%m-%d"),
+        weekday_name(date.weekday())
+    )
+}
+
+// 把所有计时记录导出为 CSV，按开始时间排列，包括归档的 todo，不包括正在进行的计时
+pub fn sessions_csv(projects: &[Project]) -> String {
+    let mut rows: Vec<(u64, u64, &str, &str)> = Vec::new();
+    for project in projects {
+        for todo in project.todos.iter().chain(&project.archived) {
+            for session in &todo.sessions {
+                rows.push((session.start, session.end, &project.name, &todo.title));
+            }
+        }
+    }
+    rows.sort_by_key(|&(start, ..)| start);
+
+    let format_ts = |ts: u64| {
+        Local
+            .timestamp_opt(ts as i64, 0)
+            .earliest()
+            .map_or_else(String::new, |dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+    };
+    let mut csv = String::from("project,todo,start,end,duration\n");
+    for (start, end, project, todo) in rows {
+        let secs = end.saturating_sub(start);
+        csv.push_str(&format!(
+            "{},{},{},{},{}:{:02}:{:02}\n",
+            csv_field(project),
+            csv_field(todo),
+            format_ts(start),
+            format_ts(end),
+            secs / 3600,
+            secs % 3600 / 60,
+            secs % 60
+        ));
+    }
+    csv
+}
+
+// 包含逗号、引号或换行的字段用引号括起来，引号写两次
+pub fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+// 把项目和其中的 todo 按状态分组写成 Markdown，包括耗时、截止日期、描述和子任务
+// todo 详情：标题、状态、耗时、截止日期、创建和完成时间、标签和描述，用于详情页和复制到剪贴板
+pub fn todo_details(todo: &Todo, unit: DurationUnit) -> String {
+    let mut lines = detail_fields(todo, unit);
+    if !todo.description.is_empty() {
+        lines.push(String::new());
+        lines.push(todo.description.clone());
+    }
+    lines.join("\n")
+}
+
+// 详情面板中的全部信息：在 todo_details 之外还有优先级、预计耗时、重复、子任务和计时记录（从新到旧）
+pub fn todo_full_details(todo: &Todo, unit: DurationUnit, now: u64) -> String {
+    let mut lines = detail_fields(todo, unit);
+    if let Some(priority) = todo.priority {
+        lines.push(format!("优先级：{}", priority.label()));
+    }
+    if let Some(estimate) = todo.estimate {
+        lines.push(format!("预计：{}", format_duration(estimate, unit)));
+    }
+    if let Some(recurrence) = todo.recurrence {
+        lines.push(format!("重复：{}", recurrence.label()));
+    }
+    if todo.pomodoros > 0 {
+        lines.push(format!("番茄钟：{}", todo.pomodoros));
+    }
+    if !todo.description.is_empty() {
+        lines.push(String::new());
+        lines.push(todo.description.clone());
+    }
+    if !todo.subtasks.is_empty() {
+        lines.push(String::new());
+        lines.push(format!(
+            "子任务 ({}/{})",
+            todo.subtasks.iter().filter(|s| s.completed).count(),
+            todo.subtasks.len()
+        ));
+        for subtask in &todo.subtasks {
+            let checkbox = if subtask.completed { "[x]" } else { "[ ]" };
+            lines.push(format!("{} {}", checkbox, subtask.title));
+        }
+    }
+    if todo.start_time.is_some() || !todo.sessions.is_empty() {
+        lines.push(String::new());
+        lines.push(format!("计时记录 ({})", todo.sessions.len()));
+        if let Some(start) = todo.start_time {
+            lines.push(format!(
+                "{} - 进行中  {}",
+                format_time(start),
+                format_duration(now.saturating_sub(start), unit)
+            ));
+        }
+        for session in todo.sessions.iter().rev() {
+            lines.push(format!(
+                "{}  {}",
+                format_session(session.start, session.end),
+                format_duration(session.duration(), unit)
+            ));
+        }
+    }
+    lines.join("\n")
+}
+
+// 详情的基本信息：标题、状态、耗时、截止日期、创建和完成时间、标签
+fn detail_fields(todo: &Todo, unit: DurationUnit) -> Vec<String> {
+    let mut lines = vec![
+        todo.title.clone(),
+        format!("状态：{}", todo.status().label()),
+    ];
+    if todo.total_duration() > 0 {
+        lines.push(format!("耗时：{}", todo.format_duration(unit)));
+    }
+    if let Some(due) = todo.due_date {
+        lines.push(format!("截止：{}", due.format("%Y-%m-%d")));
+    }
+    if todo.created_at > 0 {
+        lines.push(format!("创建：{}", format_time(todo.created_at)));
+    }
+    if let Some(completed_at) = todo.completed_at {
+        lines.push(format!("完成：{}", format_time(completed_at)));
+    }
+    if !todo.tags.is_empty() {
+        let tags: Vec<String> = todo.tags.iter().map(|tag| format!("#{}", tag)).collect();
+        lines.push(format!("标签：{}", tags.join(" ")));
+    }
+    lines
+}
+
+pub fn project_markdown(project: &Project, unit: DurationUnit) -> String {
+    let total: u64 = project.todos.iter().map(|t| t.total_duration()).sum();
+    let completed = project.todos.iter().filter(|t| t.completed).count();
+    let mut lines = vec![
+        format!("# {}", project.name),
+        String::new(),
+        format!(
+            "完成 {}/{}，总耗时 {}",
+            completed,
+            project.todos.len(),
+            format_duration(total, unit)
+        ),
+    ];
+
+    for status in Status::ALL {
+        let todos: Vec<&Todo> = project
+            .todos
+            .iter()
+            .filter(|t| t.status() == status)
+            .collect();
+        if todos.is_empty() {
+            continue;
+        }
+        lines.push(String::new());
+        lines.push(format!("## {}", status.label()));
+        lines.push(String::new());
+        for todo in todos {
+            let mut line = format!(
+                "- [{}] {}",
+                if todo.completed { "x" } else { " " },
+                todo.title
+            );
+            for tag in &todo.tags {
+                line.push_str(&format!(" `#{}`", tag));
+            }
+            if todo.total_duration() > 0 {
+                line.push_str(&format!("（{}）", todo.format_duration(unit)));
+            }
+            if let Some(due) = todo.due_date {
+                line.push_str(&format!(" 截止 {}", due.format("%Y-%m-%d")));
+            }
+            lines.push(line);
+            for subtask in &todo.subtasks {
+                lines.push(format!(
+                    "  - [{}] {}",
+                    if subtask.completed { "x" } else { " " },
+                    subtask.title
+                ));
+            }
+            for description in todo.description.lines() {
+                lines.push(format!("  > {}", description).trim_end().to_string());
+            }
+        }
+    }
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+// 把报告转成文本行，用于界面显示
+pub fn report_lines(report: &Report, unit: DurationUnit) -> Vec<String> {
+    let mut lines = Vec::new();
+    // 先按项目汇总，再按天列出明细
+    if !report.days.is_empty() {
+        lines.push(format!("合计 {}", format_duration(report.total, unit)));
+        for (project, secs) in &report.projects {
+            lines.push(format!("  {}  {}", project, format_duration(*secs, unit)));
+        }
+        lines.push(String::new());
+    }
+    for day in &report.days {
+        lines.push(format!(
+            "{}  合计 {}",
+            format_day(day.date),
+            format_duration(day.total, unit)
+        ));
+        for entry in &day.entries {
+            lines.push(format!(
+                "  {} / {}  {}",
+                entry.project,
+                entry.todo,
+                format_duration(entry.secs, unit)
+            ));
+        }
+        lines.push(String::new());
+    }
+
+    if report.undated > 0 {
+        lines.push(format!(
+            "无日期记录的耗时  {}",
+            format_duration(report.undated, unit)
+        ));
+    }
+    if lines.is_empty() {
+        lines.push("这段时间没有计时记录".to_string());
+    }
+    lines
+}
+
+// 截止日期在某天的 todo，不含归档的，按项目顺序
+pub fn due_on(projects: &[Project], date: NaiveDate) -> Vec<(&Project, &Todo)> {
+    projects
+        .iter()
+        .flat_map(|p| p.todos.iter().map(move |t| (p, t)))
+        .filter(|(_, t)| t.due_date == Some(date))
+        .collect()
+}
+
+// 日程中的分组，按先后排列
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum AgendaGroup {
+    Overdue,
+    Today,
+    ThisWeek, // 明天到本周日
+    Later,
+}
+
+impl AgendaGroup {
+    pub fn label(self) -> &'static str {
+        match self {
+            AgendaGroup::Overdue => "已过期",
+            AgendaGroup::Today => "今天",
+            AgendaGroup::ThisWeek => "本周",
+            AgendaGroup::Later => "以后",
+        }
+    }
+
+    fn of(due: NaiveDate, today: NaiveDate) -> Self {
+        let sunday = today + chrono::Days::new(6 - today.weekday().num_days_from_monday() as u64);
+        match due {
+            _ if due < today => AgendaGroup::Overdue,
+            _ if due == today => AgendaGroup::Today,
+            _ if due <= sunday => AgendaGroup::ThisWeek,
+            _ => AgendaGroup::Later,
+        }
+    }
+}
+
+// 所有项目中未完成且有截止日期的 todo（项目下标, todo 下标），按截止日期排列，同一天的按项目顺序
+pub fn agenda(projects: &[Project], today: NaiveDate) -> Vec<(AgendaGroup, usize, usize)> {
+    let mut items: Vec<(NaiveDate, usize, usize)> = projects
+        .iter()
+        .enumerate()
+        .flat_map(|(p, project)| {
+            project
+                .todos
+                .iter()
+                .enumerate()
+                .map(move |(t, todo)| (p, t, todo))
+        })
+        .filter(|(_, _, todo)| !todo.completed)
+        .filter_map(|(p, t, todo)| Some((todo.due_date?, p, t)))
+        .collect();
+    items.sort();
+    items
+        .into_iter()
+        .map(|(due, p, t)| (AgendaGroup::of(due, today), p, t))
+        .collect()
+}
+
+// 今日回顾：今天完成的 todo、今天每个项目的耗时，以及所有项目中今天到期和已过期的 todo
+pub fn today_lines(projects: &[Project], now: u64, unit: DurationUnit) -> Vec<String> {
+    let today = local_date(now);
+    let mut lines = Vec::new();
+
+    let completed: Vec<String> = projects
+        .iter()
+        .flat_map(|p| p.todos.iter().chain(&p.archived).map(move |t| (p, t)))
+        .filter(|(_, t)| t.completed && t.completed_at.is_some_and(|at| local_date(at) == today))
+        .map(|(p, t)| format!("  {} / {}", p.name, t.title))
+        .collect();
+    lines.push(format!("今天完成 {} 个", completed.len()));
+    lines.extend(completed);
+
+    let report = daily_report(projects, ReportRange::Day, 0, now);
+    lines.push(String::new());
+    match report.total {
+        0 => lines.push("今天还没有计时".to_string()),
+        total => {
+            lines.push(format!("今天的耗时 {}", format_duration(total, unit)));
+            for (project, secs) in &report.projects {
+                lines.push(format!("  {}  {}", project, format_duration(*secs, unit)));
+            }
+        }
+    }
+
+    let mut due: Vec<(NaiveDate, String)> = projects
+        .iter()
+        .flat_map(|p| p.todos.iter().map(move |t| (p, t)))
+        .filter(|(_, t)| !t.completed)
+        .filter_map(|(p, t)| {
+            let date = t.due_date.filter(|&date| date <= today)?;
+            Some((date, format!("{} / {}", p.name, t.title)))
+        })
+        .collect();
+    due.sort_by_key(|(date, _)| *date);
+    lines.push(String::new());
+    lines.push(format!("今天到期和已过期 {} 个", due.len()));
+    for (date, todo) in due {
+        match (today - date).num_days() {
+            0 => lines.push(format!("  {}  今天到期", todo)),
+            days => lines.push(format!("  {}  已过期 {} 天", todo, days)),
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 本地时间的时间戳
+    fn ts(y: i32, m: u32, d: u32, h: u32) -> u64 {
+        day_start(NaiveDate::from_ymd_opt(y, m, d).unwrap()).unwrap() + h as u64 * 3600
+    }
+
+    fn project_with_sessions(sessions: &[(u64, u64)], total_extra: u64) -> Project {
+        let mut todo = Todo::new("t".to_string());
+        for &(start, end) in sessions {
+            todo.log_session(start, end);
+        }
+        todo.untracked = total_extra;
+        Project {
+            todos: vec![todo],
+            ..Project::new("p".to_string())
+        }
+    }
+
+    #[test]
+    fn session_range_round_trips() {
+        let (start, end) = (ts(2026, 3, 2, 23), ts(2026, 3, 3, 1));
+        assert_eq!(parse_session(&format_session(start, end)), Ok((start, end)));
+        assert_eq!(
+            parse_session("2026-03-02 9:00 - 11:30"),
+            Ok((ts(2026, 3, 2, 9), ts(2026, 3, 2, 11) + 1800))
+        );
+        assert!(parse_session("2026-03-02 09:00 - 08:00").is_err());
+        assert!(parse_session("yesterday").is_err());
+    }
+
+    #[test]
+    fn manual_time_entries_are_parsed() {
+        let now = ts(2026, 3, 4, 12);
+        assert_eq!(parse_time_entry("45m", now), Ok((now - 2700, now)));
+        assert_eq!(
+            parse_time_entry("1h30m yesterday", now),
+            Ok((ts(2026, 3, 3, 9), ts(2026, 3, 3, 9) + 5400))
+        );
+        assert_eq!(
+            parse_time_entry("2h 2026-03-01", now),
+            Ok((ts(2026, 3, 1, 9), ts(2026, 3, 1, 11)))
+        );
+        assert!(parse_time_entry("2h 2026-03-05", now).is_err());
+        // 记录不能延伸到 now 之后，也不能早于今天零点
+        assert!(parse_time_entry("100d 2026-01-01", now).is_err());
+        assert!(parse_time_entry("13h", now).is_err());
+        assert!(parse_time_entry("12h", now).is_ok());
+        assert!(parse_time_entry("27h yesterday", now).is_ok());
+        assert!(parse_time_entry("28h yesterday", now).is_err());
+        assert!(parse_time_entry("soon", now).is_err());
+        assert!(parse_time_entry("1h today extra", now).is_err());
+    }
+
+    #[test]
+    fn session_across_midnight_is_split() {
+        let parts = split_by_day(ts(2026, 3, 2, 23), ts(2026, 3, 3, 1));
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].1, 3600);
+        assert_eq!(
+            parts[1],
+            (NaiveDate::from_ymd_opt(2026, 3, 3).unwrap(), 3600)
+        );
+    }
+
+    #[test]
+    fn report_buckets_by_day_and_range() {
+        // 2026-03-04 是周三
+        let now = ts(2026, 3, 4, 12);
+        let projects = vec![project_with_sessions(
+            &[
+                (ts(2026, 3, 4, 9), ts(2026, 3, 4, 10)),
+                (ts(2026, 3, 4, 10), ts(2026, 3, 4, 11)),
+                (ts(2026, 3, 2, 9), ts(2026, 3, 2, 10)),
+                (ts(2026, 2, 27, 9), ts(2026, 2, 27, 10)),
+            ],
+            600,
+        )];
+
+        let today = daily_report(&projects, ReportRange::Day, 0, now);
+        assert_eq!(today.days.len(), 1);
+        assert_eq!(today.days[0].total, 7200);
+        assert_eq!(today.days[0].entries.len(), 1);
+        assert_eq!(today.undated, 0);
+        assert_eq!(today.projects, vec![("p".to_string(), 7200)]);
+
+        let week = daily_report(&projects, ReportRange::Week, 0, now);
+        assert_eq!(week.days.len(), 2);
+        assert_eq!(week.total, 10800);
+        assert_eq!(
+            week.days[1].date,
+            NaiveDate::from_ymd_opt(2026, 3, 2).unwrap()
+        );
+
+        // 往前翻到上周和前天
+        let last_week = daily_report(&projects, ReportRange::Week, 1, now);
+        assert_eq!(last_week.days.len(), 1);
+        assert_eq!(
+            last_week.days[0].date,
+            NaiveDate::from_ymd_opt(2026, 2, 27).unwrap()
+        );
+        assert!(daily_report(&projects, ReportRange::Day, 1, now)
+            .days
+            .is_empty());
+        assert_eq!(
+            daily_report(&projects, ReportRange::Day, 2, now).total,
+            3600
+        );
+        assert_eq!(ReportRange::Week.label(1, local_date(now)), "上周");
+        assert_eq!(
+            ReportRange::Day.label(2, local_date(now)),
+            "2026-03-02 周一"
+        );
+
+        let all = daily_report(&projects, ReportRange::All, 0, now);
+        assert_eq!(all.days.len(), 3);
+        assert_eq!(all.undated, 600);
+        assert!(report_lines(&all, DurationUnit::Month)
+            .last()
+            .unwrap()
+            .contains("10m"));
+    }
+
+    #[test]
+    fn sessions_are_exported_as_csv() {
+        let mut project = project_with_sessions(&[(ts(2026, 3, 4, 9), ts(2026, 3, 4, 10))], 0);
+        project.name = "a, \"b\"".to_string();
+        let mut archived = Todo::new("old".to_string());
+        archived.log_session(ts(2026, 3, 3, 9), ts(2026, 3, 3, 9) + 5_430);
+        project.archived.push(archived);
+
+        assert_eq!(
+            sessions_csv(&[project]),
+            "project,todo,start,end,duration\n\
+             \"a, \"\"b\"\"\",old,2026-03-03 09:00:00,2026-03-03 10:30:30,1:30:30\n\
+             \"a, \"\"b\"\"\",t,2026-03-04 09:00:00,2026-03-04 10:00:00,1:00:00\n"
+        );
+    }
+
+    #[test]
+    fn project_is_rendered_as_markdown() {
+        let mut project = project_with_sessions(&[(0, 5_400)], 0);
+        project.todos[0].doing = true;
+        project.todos[0].tags = vec!["work".to_string()];
+        project.todos[0].description = "第一行\n第二行".to_string();
+        let mut done = Todo::new("done".to_string());
+        done.completed = true;
+        done.due_date = NaiveDate::from_ymd_opt(2026, 3, 4);
+        project.todos.push(done);
+
+        assert_eq!(
+            project_markdown(&project, DurationUnit::Month),
+            "# p\n\n完成 1/2，总耗时 1h 30m\n\n\
+             ## 进行中\n\n- [ ] t `#work`（1h 30m）\n  > 第一行\n  > 第二行\n\n\
+             ## 已完成\n\n- [x] done 截止 2026-03-04\n"
+        );
+    }
+
+    #[test]
+    fn today_review_lists_completed_tracked_and_due_todos() {
+        let now = ts(2026, 3, 4, 12);
+        let mut project = project_with_sessions(&[(ts(2026, 3, 4, 9), ts(2026, 3, 4, 10))], 0);
+        let mut done = Todo::new("done".to_string());
+        done.completed = true;
+        done.completed_at = Some(ts(2026, 3, 4, 11));
+        project.todos.push(done);
+        for (title, day) in [("later", 5), ("overdue", 2), ("today", 4)] {
+            let mut todo = Todo::new(title.to_string());
+            todo.due_date = NaiveDate::from_ymd_opt(2026, 3, day);
+            project.todos.push(todo);
+        }
+
+        let lines = today_lines(&[project], now, DurationUnit::Hour);
+        assert_eq!(
+            lines,
+            [
+                "今天完成 1 个",
+                "  p / done",
+                "",
+                "今天的耗时 1h",
+                "  p  1h",
+                "",
+                "今天到期和已过期 2 个",
+                "  p / overdue  已过期 2 天",
+                "  p / today  今天到期",
+            ]
+        );
+    }
+
+    #[test]
+    fn agenda_groups_pending_todos_by_due_date() {
+        // 2026-03-04 是周三
+        let today = NaiveDate::from_ymd_opt(2026, 3, 4).unwrap();
+        let todos = |days: &[(&str, u32)]| {
+            days.iter()
+                .map(|&(title, day)| Todo {
+                    due_date: NaiveDate::from_ymd_opt(2026, 3, day),
+                    ..Todo::new(title.to_string())
+                })
+                .collect()
+        };
+        let mut work = Project {
+            todos: todos(&[("next week", 9), ("sunday", 8), ("today", 4)]),
+            ..Project::new("work".to_string())
+        };
+        work.todos.push(Todo::new("no due".to_string()));
+        let mut home = Project {
+            todos: todos(&[("overdue", 1), ("done", 2)]),
+            ..Project::new("home".to_string())
+        };
+        home.todos[1].completed = true;
+
+        let agenda = agenda(&[work, home], today);
+        assert_eq!(
+            agenda,
+            [
+                (AgendaGroup::Overdue, 1, 0),
+                (AgendaGroup::Today, 0, 2),
+                (AgendaGroup::ThisWeek, 0, 1),
+                (AgendaGroup::Later, 0, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn stats_sum_projects_and_rank_days() {
+        let now = ts(2026, 3, 4, 12);
+        let mut project = project_with_sessions(
+            &[
+                (ts(2026, 3, 4, 9), ts(2026, 3, 4, 10)),
+                (ts(2026, 3, 2, 9), ts(2026, 3, 2, 12)),
+                (ts(2026, 1, 5, 9), ts(2026, 1, 5, 10)),
+            ],
+            0,
+        );
+        let mut archived = Todo::new("done".to_string());
+        archived.completed = true;
+        archived.untracked = 60;
+        archived.created_at = ts(2026, 3, 1, 12);
+        archived.completed_at = Some(ts(2026, 3, 3, 12));
+        project.archived.push(archived);
+        // 上周完成的 todo 只计入平均完成时间
+        let mut done = Todo::new("last week".to_string());
+        done.created_at = ts(2026, 2, 25, 12);
+        done.set_completed(true);
+        done.completed_at = Some(ts(2026, 2, 27, 12));
+        project.todos.push(done);
+
+        let stats = stats(&[project], now);
+        assert_eq!(stats.projects[0].total, 5 * 3600 + 60);
+        assert_eq!(stats.projects[0].completed, 2);
+        assert_eq!(stats.projects[0].todos, 3);
+        assert_eq!(stats.completed_this_week, 1);
+        assert_eq!(stats.average_completion, Some(2 * 86400));
+        assert_eq!(stats.recent_days.len(), RECENT_DAYS as usize);
+        assert_eq!(stats.recent_days[13], 3600);
+        assert_eq!(stats.recent_days[11], 3 * 3600);
+        assert_eq!(stats.busiest_days[0].1, 3 * 3600);
+        assert_eq!(stats.busiest_days.len(), 3);
+    }
+}
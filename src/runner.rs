@@ -0,0 +1,246 @@
+use crate::app::App;
+use crate::input::{self, Outcome};
+use crate::model::{now_secs, today};
+use crate::ui::ui;
+use crossterm::event::{KeyCode, KeyEvent, MouseEvent};
+use ratatui::{backend::Backend, Terminal};
+use std::collections::VecDeque;
+use std::io;
+use std::time::{Duration, Instant};
+
+// 事件轮询间隔
+const TICK_RATE: Duration = Duration::from_millis(250);
+
+// 主循环读取到的输入
+pub enum Input {
+    Key(KeyEvent),
+    Mouse(MouseEvent),
+    Paste(String), // 终端的括号粘贴，整段文本一次送达
+    Resize,        // 终端尺寸变化
+    Tick,          // 等待超时，没有输入
+    End,           // 没有更多输入，结束主循环
+}
+
+// 主循环与外部环境的交互：读取输入、响铃、桌面通知和外部编辑器
+// 终端中运行时由 main 实现，测试中用 ScriptedHost 按脚本输入按键
+pub trait Host {
+    fn next_input(&mut self, timeout: Duration) -> io::Result<Input>;
+
+    fn bell(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn notify(&mut self, _body: String) {}
+
+    // 复制到系统剪贴板
+    fn copy(&mut self, _text: &str) -> io::Result<()> {
+        Ok(())
+    }
+
+    // 用外部编辑器编辑文本，外层错误表示终端无法恢复，内层错误只作为提示显示
+    fn edit(&mut self, text: &str) -> io::Result<Result<String, String>> {
+        Ok(Ok(text.to_string()))
+    }
+}
+
+// 依次输入脚本中的按键，输入完后结束主循环，记录响铃和通知
+#[derive(Default)]
+pub struct ScriptedHost {
+    keys: VecDeque<KeyEvent>,
+    pub bells: usize,
+    pub notifications: Vec<String>,
+    pub copied: Vec<String>,
+}
+
+impl ScriptedHost {
+    pub fn new(keys: impl IntoIterator<Item = KeyCode>) -> Self {
+        Self {
+            keys: keys.into_iter().map(KeyEvent::from).collect(),
+            ..Self::default()
+        }
+    }
+
+    // 逐个字符输入文本
+    pub fn typing(text: &str) -> impl Iterator<Item = KeyCode> + '_ {
+        text.chars().map(KeyCode::Char)
+    }
+}
+
+impl Host for ScriptedHost {
+    fn next_input(&mut self, _timeout: Duration) -> io::Result<Input> {
+        Ok(self.keys.pop_front().map_or(Input::End, Input::Key))
+    }
+
+    fn bell(&mut self) -> io::Result<()> {
+        self.bells += 1;
+        Ok(())
+    }
+
+    fn notify(&mut self, body: String) {
+        self.notifications.push(body);
+    }
+
+    fn copy(&mut self, text: &str) -> io::Result<()> {
+        self.copied.push(text.to_string());
+        Ok(())
+    }
+}
+
+pub fn run_app<B: Backend, H: Host>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    host: &mut H,
+) -> io::Result<()> {
+    let mut last_heartbeat = Instant::now();
+
+    loop {
+        terminal.draw(|f| ui(f, app))?;
+
+        // 计时进行中时定期保存，记录最后的保存时间，异常退出后计时最多丢失一个间隔
+        let due = app.config.autosave_interval();
+        if due.is_some_and(|interval| last_heartbeat.elapsed() >= interval) {
+            if app.has_running_timer() {
+                app.save_heartbeat();
+            }
+            last_heartbeat = Instant::now();
+        }
+
+        // 计时超过提醒阈值时响铃，界面上的计时图标会同时闪烁
+        if app.check_timer_alerts(now_secs()) {
+            host.bell()?;
+        }
+        // 番茄钟阶段结束时响铃，工作结束时停止了计时，需要保存
+        if app.tick_pomodoro(now_secs()) {
+            host.bell()?;
+            app.save_data();
+        }
+        app.check_due_reminders(today());
+        for body in app.notifications.drain(..) {
+            host.notify(body);
+        }
+
+        // 定时轮询输入，没有输入时也会回到循环开头重绘
+        let outcome = match host.next_input(TICK_RATE)? {
+            Input::Key(key) => input::handle_key(app, key),
+            Input::Mouse(mouse) => input::handle_mouse(app, mouse),
+            Input::Paste(text) => {
+                input::handle_paste(app, &text);
+                continue;
+            }
+            // 终端尺寸变化时按新尺寸清屏重绘，避免残留旧画面
+            Input::Resize => {
+                terminal.autoresize()?;
+                terminal.clear()?;
+                continue;
+            }
+            Input::Tick => continue,
+            Input::End => return Ok(()),
+        };
+
+        match outcome {
+            Outcome::None => {}
+            Outcome::Save => {
+                app.save_data();
+            }
+            // 退出后由 main 保存
+            Outcome::Quit => return Ok(()),
+            Outcome::Copy(text) => match host.copy(&text) {
+                Ok(()) => app.info(format!("已复制 {} 个字符", text.chars().count())),
+                Err(err) => app.error(format!("复制失败：{}", err)),
+            },
+            Outcome::EditDescription => {
+                if edit_description(terminal, app, host)? {
+                    app.save_data();
+                }
+            }
+        }
+    }
+}
+
+// 用外部编辑器编辑选中 todo 的描述，返回是否有修改
+fn edit_description<B: Backend, H: Host>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    host: &mut H,
+) -> io::Result<bool> {
+    let Some(description) = app.get_current_todo().map(|t| t.description.clone()) else {
+        return Ok(false);
+    };
+    let edited = host.edit(&description)?;
+    terminal.clear()?;
+
+    match edited {
+        Ok(description) => Ok(app.set_current_description(&description)),
+        Err(err) => {
+            app.error(err);
+            Ok(false)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::Panel;
+    use crate::model::Project;
+    use ratatui::backend::TestBackend;
+
+    // 运行主循环，返回最后一次绘制的画面
+    fn run(app: &mut App, host: &mut ScriptedHost) -> String {
+        // 只读模式下不会写入数据文件
+        app.read_only = true;
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+        run_app(&mut terminal, app, host).unwrap();
+        let buffer = terminal.backend().buffer();
+        buffer
+            .content()
+            .chunks(buffer.area.width as usize)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn scripted_keys_drive_the_app_loop() {
+        let mut app = App::with_projects(vec![Project::new("inbox".to_string())]);
+        let keys = [KeyCode::Tab, KeyCode::Char('a')]
+            .into_iter()
+            .chain(ScriptedHost::typing("buy milk"))
+            .chain([KeyCode::Enter]);
+        let screen = run(&mut app, &mut ScriptedHost::new(keys));
+
+        assert_eq!(app.active_panel, Panel::Todos);
+        assert_eq!(app.projects[0].todos[0].title, "buy milk");
+        assert!(screen.contains("buy milk"));
+    }
+
+    #[test]
+    fn quit_key_ends_the_loop_early() {
+        let mut app = App::with_projects(vec![Project::new("inbox".to_string())]);
+        let mut host = ScriptedHost::new([KeyCode::Char('q'), KeyCode::Char('a')]);
+        run(&mut app, &mut host);
+        assert!(app.input_mode == crate::app::InputMode::Normal);
+    }
+
+    #[test]
+    fn yank_copies_the_title_or_details_to_the_clipboard() {
+        let mut todo = crate::model::Todo::new("buy milk".to_string());
+        todo.description = "2 bottles".to_string();
+        todo.tags = vec!["home".to_string()];
+        let created = crate::report::format_time(todo.created_at);
+        let mut app = App::with_projects(vec![Project {
+            todos: vec![todo],
+            ..Project::new("inbox".to_string())
+        }]);
+        let keys = [KeyCode::Tab, KeyCode::Char('y'), KeyCode::Char('Y')];
+        let mut host = ScriptedHost::new(keys);
+        run(&mut app, &mut host);
+
+        let details = format!(
+            "buy milk\n状态：待办\n创建：{}\n标签：#home\n\n2 bottles",
+            created
+        );
+        assert_eq!(host.copied, vec!["buy milk".to_string(), details]);
+        assert!(app.messages.iter().any(|m| m.text.starts_with("已复制")));
+    }
+}
@@ -0,0 +1,39 @@
+use crate::app::Panel;
+use crate::paths;
+use serde::{Deserialize, Serialize};
+
+// 上次退出时的界面状态，下次启动时恢复选中位置
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiState {
+    pub project: Option<usize>, // 选中的项目下标
+    pub todo: Option<usize>,    // 选中的 todo 在项目 todos 中的下标
+    pub active_panel: Panel,
+    pub hide_completed: bool,       // 列表中隐藏已完成的 todo
+    pub detail_pane: bool,          // 显示详情面板
+    pub project_split: Option<u16>, // 调整过的项目面板宽度（百分比）
+    pub single_pane: bool,          // 一次只显示一个面板
+}
+
+impl UiState {
+    // 读取状态文件，文件不存在或损坏时使用默认状态
+    pub fn load() -> UiState {
+        std::fs::read_to_string(Self::get_state_file_path())
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let state_file = Self::get_state_file_path();
+        if let Some(parent) = std::path::Path::new(&state_file).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&state_file, serde_json::to_string_pretty(self)?)
+    }
+
+    // 获取状态文件路径，放在系统配置目录中，每个工作区一个
+    pub fn get_state_file_path() -> String {
+        paths::workspace_config_file("state.json")
+    }
+}
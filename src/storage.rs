@@ -0,0 +1,249 @@
+use crate::backup;
+use crate::model::{AppData, Project, Todo, DATA_VERSION};
+use crate::paths;
+use chrono::Local;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+// 无法加载数据的原因，两种情况下都不能写回数据文件
+#[derive(Debug)]
+pub enum LoadError {
+    Corrupt(String),  // 数据文件损坏且没有可用的备份，可以选择重新开始
+    Unusable(String), // 版本过新或无法读取
+}
+
+impl LoadError {
+    pub fn into_message(self) -> String {
+        match self {
+            LoadError::Corrupt(message) | LoadError::Unusable(message) => message,
+        }
+    }
+}
+
+// 加载数据，数据文件版本高于当前程序时返回错误
+pub fn load_data() -> Result<AppData, String> {
+    let (app_data, notice) = load_from(&data_file_path()).map_err(LoadError::into_message)?;
+    // 命令行没有界面提示，从备份恢复时在 stderr 说明，避免后续写入悄悄覆盖
+    if let Some(notice) = notice {
+        eprintln!("{}", notice);
+    }
+    Ok(app_data)
+}
+
+// 加载数据，数据文件损坏时从最新的可用备份恢复，同时返回恢复的提示
+pub fn load_with_notice() -> Result<(AppData, Option<String>), LoadError> {
+    load_from(&data_file_path())
+}
+
+// 解析数据文件的内容并升级到当前版本，内容损坏时外层返回错误原因
+fn parse(content: &str) -> Result<Result<AppData, String>, String> {
+    let value = serde_json::from_str::<serde_json::Value>(content).map_err(|e| e.to_string())?;
+    // 新版本程序写出的文件可能包含无法识别的字段，拒绝加载以免保存时丢失数据
+    let version = AppData::version_of(&value);
+    if version > DATA_VERSION {
+        return Ok(Err(format!(
+            "数据文件版本 v{} 高于当前程序支持的 v{}，已禁止保存",
+            version, DATA_VERSION
+        )));
+    }
+    AppData::migrate(value)
+        .map(Ok)
+        .map_err(|e| format!("v{} 升级到 v{} 失败：{}", version, DATA_VERSION, e))
+}
+
+fn load_from(data_file: &str) -> Result<(AppData, Option<String>), LoadError> {
+    let content = match fs::read_to_string(data_file) {
+        Ok(content) => content,
+        // 第一次运行时使用示例数据，新建的工作区从空白开始
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            let app_data = match paths::workspace() {
+                Some(_) => AppData {
+                    version: DATA_VERSION,
+                    saved_at: 0,
+                    projects: vec![],
+                    trash: vec![],
+                    archived_projects: vec![],
+                    templates: vec![],
+                },
+                None => sample_data(),
+            };
+            return Ok((app_data, None));
+        }
+        Err(err) => {
+            return Err(LoadError::Unusable(format!(
+                "读取数据文件失败：{}，已禁止保存",
+                err
+            )))
+        }
+    };
+    let reason = match parse(&content) {
+        Ok(result) => {
+            return result
+                .map(|app_data| (app_data, None))
+                .map_err(LoadError::Unusable)
+        }
+        Err(reason) => reason,
+    };
+
+    // 数据文件损坏，保留一份副本后从新到旧尝试备份
+    let corrupt = keep_corrupt_copy(data_file, &content);
+    for backup in backup::list(data_file) {
+        let Ok(content) = fs::read_to_string(&backup.path) else {
+            continue;
+        };
+        if let Ok(Ok(app_data)) = parse(&content) {
+            let notice = format!(
+                "数据文件已损坏（{}），已从 {} 的备份恢复，损坏的文件保存为 {}",
+                reason,
+                backup.format_time(),
+                corrupt
+            );
+            return Ok((app_data, Some(notice)));
+        }
+    }
+    Err(LoadError::Corrupt(format!(
+        "数据文件已损坏（{}）且没有可用的备份，损坏的文件保存为 {}",
+        reason, corrupt
+    )))
+}
+
+// 按时间保留损坏文件的副本，已有内容相同的副本时直接复用，避免每次加载都多一份
+fn keep_corrupt_copy(data_file: &str, content: &str) -> String {
+    let path = Path::new(data_file);
+    let prefix = format!(
+        "{}.corrupt-",
+        path.file_name().unwrap_or_default().to_string_lossy()
+    );
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty());
+    if let Ok(entries) = fs::read_dir(dir.unwrap_or(Path::new("."))) {
+        for entry in entries.flatten() {
+            if !entry.file_name().to_string_lossy().starts_with(&prefix) {
+                continue;
+            }
+            if fs::read(entry.path()).is_ok_and(|bytes| bytes == content.as_bytes()) {
+                return entry.path().to_string_lossy().into_owned();
+            }
+        }
+    }
+    let corrupt = format!(
+        "{}.corrupt-{}",
+        data_file,
+        Local::now().format("%Y%m%d%H%M%S")
+    );
+    let _ = fs::copy(data_file, &corrupt);
+    corrupt
+}
+
+// 第一次运行时的示例数据
+pub fn sample_data() -> AppData {
+    AppData {
+        version: DATA_VERSION,
+        saved_at: 0,
+        projects: vec![
+            Project {
+                todos: vec![Todo::new("完成报告".to_string())],
+                ..Project::new("工作项目".to_string())
+            },
+            Project {
+                todos: vec![Todo::new("学习 Rust".to_string())],
+                ..Project::new("个人学习".to_string())
+            },
+        ],
+        trash: vec![],
+        archived_projects: vec![],
+        templates: vec![],
+    }
+}
+
+// 把数据写入数据文件，backup 为 true 时先备份原文件
+pub fn write_data(app_data: &AppData, backup: bool) -> io::Result<()> {
+    write_to(&data_file_path(), app_data, backup)
+}
+
+fn write_to(data_file: &str, app_data: &AppData, backup: bool) -> io::Result<()> {
+    // 确保目录存在
+    if let Some(parent) = Path::new(data_file).parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    if backup {
+        backup::rotate(data_file)?;
+    }
+
+    let json = serde_json::to_string_pretty(app_data)?;
+    write_atomic(data_file, json.as_bytes())
+}
+
+// 先写入同目录的临时文件再重命名，写入中途崩溃时原文件保持完整
+pub fn write_atomic(path: &str, content: &[u8]) -> io::Result<()> {
+    let temp = format!("{}.tmp", path);
+    let mut file = fs::File::create(&temp)?;
+    file.write_all(content)?;
+    file.sync_all()?;
+    fs::rename(&temp, path)
+}
+
+// 获取数据文件路径
+pub fn data_file_path() -> String {
+    paths::data_file()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_data_file(name: &str) -> String {
+        let dir =
+            std::env::temp_dir().join(format!("s_todo_storage_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join("data.json").to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn corrupt_data_is_recovered_from_backup() {
+        let data_file = temp_data_file("recover");
+        let data = sample_data();
+        write_to(&data_file, &data, true).unwrap();
+        write_to(&data_file, &data, true).unwrap();
+        assert!(!Path::new(&format!("{}.tmp", data_file)).exists());
+
+        // 保存中途崩溃留下的半个文件
+        fs::write(&data_file, "{\"version\": 3, \"proj").unwrap();
+        let (recovered, notice) = load_from(&data_file).unwrap();
+        assert_eq!(recovered.projects.len(), 2);
+        assert!(notice.unwrap().contains("备份恢复"));
+        let dir = Path::new(&data_file).parent().unwrap();
+        let copies = || {
+            fs::read_dir(dir)
+                .unwrap()
+                .map(|entry| entry.unwrap().path())
+                .filter(|path| {
+                    let name = path.file_name().unwrap().to_string_lossy();
+                    name.starts_with("data.json.corrupt-")
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(copies().len(), 1);
+
+        // 同样的损坏内容再次加载时复用已有的副本
+        let old = dir.join("data.json.corrupt-20000101000000");
+        fs::rename(&copies()[0], &old).unwrap();
+        let (_, notice) = load_from(&data_file).unwrap();
+        assert!(notice.unwrap().contains(&*old.to_string_lossy()));
+        assert_eq!(copies(), vec![old]);
+
+        // 备份也损坏时拒绝加载，避免用示例数据覆盖
+        for backup in backup::list(&data_file) {
+            fs::write(&backup.path, "").unwrap();
+        }
+        assert!(matches!(load_from(&data_file), Err(LoadError::Corrupt(_))));
+    }
+
+    #[test]
+    fn missing_data_file_starts_with_sample_data() {
+        let (data, notice) = load_from(&temp_data_file("missing")).unwrap();
+        assert_eq!(data.projects.len(), 2);
+        assert!(notice.is_none());
+    }
+}
@@ -0,0 +1,250 @@
+use crate::model::{stable_hash, Project, Todo};
+use chrono::{Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+// 没有 project 的任务导入到这个项目
+const DEFAULT_PROJECT: &str = "Taskwarrior";
+
+// Taskwarrior `task export` 输出中用到的字段，其余字段忽略
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Task {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uuid: Option<String>,
+    description: String,
+    status: String, // pending、completed、deleted、waiting、recurring
+    #[serde(skip_serializing_if = "Option::is_none")]
+    entry: Option<String>, // 创建时间，如 "20260304T013000Z"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start: Option<String>, // 开始时间，有这个字段表示正在进行
+    #[serde(skip_serializing_if = "Option::is_none")]
+    end: Option<String>, // 完成时间
+    #[serde(skip_serializing_if = "Option::is_none")]
+    project: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    due: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    annotations: Vec<Annotation>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Annotation {
+    entry: String,
+    description: String,
+}
+
+// 解析 Taskwarrior 的 UTC 时间
+fn parse_time(text: &str) -> Option<u64> {
+    NaiveDateTime::parse_from_str(text, "%Y%m%dT%H%M%SZ")
+        .ok()
+        .map(|dt| dt.and_utc().timestamp().max(0) as u64)
+}
+
+fn format_time(ts: u64) -> String {
+    Utc.timestamp_opt(ts as i64, 0)
+        .earliest()
+        .map_or_else(String::new, |dt| dt.format("%Y%m%dT%H%M%SZ").to_string())
+}
+
+// 截止时间转为本地日期，截止日期导出为本地零点
+fn due_date(text: &str) -> Option<NaiveDate> {
+    let ts = parse_time(text)?;
+    Local
+        .timestamp_opt(ts as i64, 0)
+        .earliest()
+        .map(|dt| dt.date_naive())
+}
+
+fn format_due(date: NaiveDate) -> Option<String> {
+    let midnight = date
+        .and_hms_opt(0, 0, 0)?
+        .and_local_timezone(Local)
+        .earliest()?;
+    Some(format_time(midnight.timestamp().max(0) as u64))
+}
+
+// 由创建时间、项目名和标题生成 UUID 形式的标识，重复导出时保持不变
+fn uuid(project: &Project, todo: &Todo) -> String {
+    let key = format!("{}\0{}\0{}", todo.created_at, project.name, todo.title);
+    let hex = format!(
+        "{:016x}{:016x}",
+        stable_hash(&key),
+        stable_hash(&format!("{}\0", key))
+    );
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+// 导入 `task export` 输出的 JSON，按 project 放入同名项目（不存在时创建），返回导入的数量
+// 已删除的任务和重复任务的模板不导入，创建时间和标题都相同的 todo 视为已导入过
+pub fn import(projects: &mut Vec<Project>, json: &str) -> Result<usize, String> {
+    let tasks: Vec<Task> =
+        serde_json::from_str(json).map_err(|e| format!("Taskwarrior 数据格式错误：{}", e))?;
+
+    let mut imported = 0;
+    for task in tasks {
+        if matches!(task.status.as_str(), "deleted" | "recurring") || task.description.is_empty() {
+            continue;
+        }
+        let completed = task.status == "completed";
+        let todo = Todo {
+            description: task
+                .annotations
+                .iter()
+                .map(|a| a.description.as_str())
+                .collect::<Vec<_>>()
+                .join("\n"),
+            completed,
+            created_at: task
+                .entry
+                .as_deref()
+                .and_then(parse_time)
+                .unwrap_or_default(),
+            completed_at: task
+                .end
+                .as_deref()
+                .and_then(parse_time)
+                .filter(|_| completed),
+            due_date: task.due.as_deref().and_then(due_date),
+            tags: task.tags,
+            doing: !completed && task.start.is_some(),
+            ..Todo::new(task.description)
+        };
+
+        let name = task.project.unwrap_or_else(|| DEFAULT_PROJECT.to_string());
+        let index = match projects.iter().position(|p| p.name == name) {
+            Some(index) => index,
+            None => {
+                projects.push(Project::new(name));
+                projects.len() - 1
+            }
+        };
+        let project = &mut projects[index];
+        let exists = project
+            .todos
+            .iter()
+            .chain(&project.archived)
+            .any(|t| t.title == todo.title && t.created_at == todo.created_at);
+        if !exists {
+            project.todos.push(todo);
+            imported += 1;
+        }
+    }
+    Ok(imported)
+}
+
+// 导出为 `task import` 可以读取的 JSON，包括归档的 todo，描述作为一条注释
+pub fn export(projects: &[Project]) -> String {
+    let tasks: Vec<Task> = projects
+        .iter()
+        .flat_map(|project| {
+            project
+                .todos
+                .iter()
+                .chain(&project.archived)
+                .map(move |todo| (project, todo))
+        })
+        .map(|(project, todo)| {
+            let entry = format_time(todo.created_at);
+            Task {
+                uuid: Some(uuid(project, todo)),
+                description: todo.title.clone(),
+                status: if todo.completed {
+                    "completed"
+                } else {
+                    "pending"
+                }
+                .to_string(),
+                entry: Some(entry.clone()),
+                start: None,
+                end: todo
+                    .completed_at
+                    .filter(|_| todo.completed)
+                    .map(format_time),
+                project: Some(project.name.clone()),
+                due: todo.due_date.and_then(format_due),
+                tags: todo.tags.clone(),
+                annotations: match todo.description.is_empty() {
+                    true => vec![],
+                    false => vec![Annotation {
+                        entry,
+                        description: todo.description.clone(),
+                    }],
+                },
+            }
+        })
+        .collect();
+    serde_json::to_string_pretty(&tasks).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = r#"[
+        {"id":1,"description":"写周报","entry":"20260304T013000Z","modified":"20260304T013000Z",
+         "project":"工作","status":"pending","tags":["work"],"uuid":"a","urgency":5.2,
+         "due":"20260310T120000Z","start":"20260304T020000Z",
+         "annotations":[{"entry":"20260304T013100Z","description":"第一条"},
+                        {"entry":"20260304T013200Z","description":"第二条"}]},
+        {"id":0,"description":"买菜","entry":"20260301T000000Z","status":"completed","uuid":"b",
+         "end":"20260302T000000Z"},
+        {"id":0,"description":"删掉的","entry":"20260301T000000Z","status":"deleted","uuid":"c"}
+    ]"#;
+
+    #[test]
+    fn taskwarrior_export_is_imported_once() {
+        let mut projects = vec![Project::new("工作".to_string())];
+        assert_eq!(import(&mut projects, EXPORT), Ok(2));
+        assert_eq!(projects.len(), 2);
+
+        let todo = &projects[0].todos[0];
+        assert_eq!(todo.title, "写周报");
+        assert_eq!(todo.description, "第一条\n第二条");
+        assert_eq!(todo.tags, vec!["work".to_string()]);
+        assert_eq!(todo.created_at, parse_time("20260304T013000Z").unwrap());
+        assert!(todo.due_date.is_some());
+        assert!(todo.doing);
+        assert_eq!(projects[1].name, DEFAULT_PROJECT);
+        assert!(projects[1].todos[0].completed);
+        assert_eq!(
+            projects[1].todos[0].completed_at,
+            parse_time("20260302T000000Z")
+        );
+
+        // 再次导入同样的数据不会重复添加
+        assert_eq!(import(&mut projects, EXPORT), Ok(0));
+        assert!(import(&mut projects, "{}").is_err());
+    }
+
+    #[test]
+    fn exported_todos_round_trip() {
+        let mut todo = Todo::new("a".to_string());
+        todo.created_at = 1_000;
+        todo.due_date = NaiveDate::from_ymd_opt(2026, 3, 4);
+        todo.description = "note".to_string();
+        let project = Project {
+            todos: vec![todo],
+            ..Project::new("p".to_string())
+        };
+
+        let json = export(std::slice::from_ref(&project));
+        assert_eq!(export(std::slice::from_ref(&project)), json);
+        let mut imported = vec![];
+        assert_eq!(import(&mut imported, &json), Ok(1));
+        let todo = &imported[0].todos[0];
+        assert_eq!(todo.created_at, 1_000);
+        assert_eq!(todo.due_date, NaiveDate::from_ymd_opt(2026, 3, 4));
+        assert_eq!(todo.description, "note");
+        assert_eq!(import(&mut vec![project], &json), Ok(0));
+    }
+}
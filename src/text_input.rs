@@ -0,0 +1,205 @@
+use std::ops::Deref;
+use unicode_segmentation::UnicodeSegmentation;
+
+// 输入框中的文本和光标，光标是字节位置，总在字素簇边界上
+// 移动和删除以字素簇为单位，组合字符和多码点的 emoji 不会被切开
+// 只读访问通过 Deref 得到 String，修改都经过这里的方法，保证光标有效
+#[derive(Clone, Default, Debug)]
+pub struct TextInput {
+    text: String,
+    cursor: usize,
+}
+
+// 光标在输入框中显示的字符
+pub const CURSOR: char = '▏';
+
+impl TextInput {
+    // 替换全部文本，光标移到末尾
+    pub fn set(&mut self, text: impl Into<String>) {
+        self.text = text.into();
+        self.cursor = self.text.len();
+    }
+
+    pub fn clear(&mut self) {
+        self.set(String::new());
+    }
+
+    // 取出全部文本并清空
+    pub fn take(&mut self) -> String {
+        self.cursor = 0;
+        std::mem::take(&mut self.text)
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    // 在光标处插入字符
+    pub fn insert(&mut self, c: char) {
+        self.text.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+    }
+
+    // 在光标处插入一段文本，如粘贴的内容
+    pub fn insert_str(&mut self, text: &str) {
+        self.text.insert_str(self.cursor, text);
+        self.cursor += text.len();
+    }
+
+    // 删除光标前的字符（Backspace）
+    pub fn delete_backward(&mut self) -> bool {
+        let cursor = self.cursor;
+        self.move_left();
+        self.text.replace_range(self.cursor..cursor, "");
+        self.cursor != cursor
+    }
+
+    // 删除光标处的字符（Delete）
+    pub fn delete_forward(&mut self) -> bool {
+        let Some(g) = self.text[self.cursor..].graphemes(true).next() else {
+            return false;
+        };
+        let end = self.cursor + g.len();
+        self.text.replace_range(self.cursor..end, "");
+        true
+    }
+
+    // 删除光标前的一个词，先跳过空白（Ctrl+W）
+    pub fn delete_word(&mut self) -> bool {
+        let before = &self.text[..self.cursor];
+        let trimmed = before.trim_end();
+        let start = trimmed
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_whitespace())
+            .map_or(0, |(i, c)| i + c.len_utf8());
+        self.delete_range(start)
+    }
+
+    // 删除从行首到光标的内容，单行输入时即清空光标前的全部内容（Ctrl+U）
+    pub fn delete_to_line_start(&mut self) -> bool {
+        self.delete_range(self.line_start())
+    }
+
+    fn delete_range(&mut self, start: usize) -> bool {
+        if start == self.cursor {
+            return false;
+        }
+        self.text.replace_range(start..self.cursor, "");
+        self.cursor = start;
+        true
+    }
+
+    pub fn move_left(&mut self) {
+        if let Some(g) = self.text[..self.cursor].graphemes(true).next_back() {
+            self.cursor -= g.len();
+        }
+    }
+
+    pub fn move_right(&mut self) {
+        if let Some(g) = self.text[self.cursor..].graphemes(true).next() {
+            self.cursor += g.len();
+        }
+    }
+
+    // 移到当前行的行首（Home）
+    pub fn move_home(&mut self) {
+        self.cursor = self.line_start();
+    }
+
+    // 移到当前行的行尾（End）
+    pub fn move_end(&mut self) {
+        self.cursor = self.text[self.cursor..]
+            .find('\n')
+            .map_or(self.text.len(), |i| self.cursor + i);
+    }
+
+    fn line_start(&self) -> usize {
+        self.text[..self.cursor].rfind('\n').map_or(0, |i| i + 1)
+    }
+
+    // 在光标处插入光标字符，用于显示
+    pub fn with_cursor(&self) -> String {
+        let mut text = self.text.clone();
+        text.insert(self.cursor, CURSOR);
+        text
+    }
+}
+
+impl Deref for TextInput {
+    type Target = String;
+
+    fn deref(&self) -> &String {
+        &self.text
+    }
+}
+
+impl PartialEq<&str> for TextInput {
+    fn eq(&self, other: &&str) -> bool {
+        self.text == *other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(text: &str) -> TextInput {
+        let mut input = TextInput::default();
+        input.set(text);
+        input
+    }
+
+    #[test]
+    fn characters_are_inserted_and_deleted_at_the_cursor() {
+        let mut input = input("买菜");
+        input.move_left();
+        input.insert('水');
+        input.insert('果');
+        assert_eq!(input, "买水果菜");
+        assert_eq!(input.with_cursor(), "买水果▏菜");
+
+        input.delete_forward();
+        input.delete_backward();
+        assert_eq!(input, "买水");
+        assert!(!input.delete_forward());
+
+        input.move_home();
+        assert!(!input.delete_backward());
+        input.move_right();
+        input.move_end();
+        assert_eq!(input.cursor(), input.len());
+    }
+
+    #[test]
+    fn wide_and_combined_characters_are_edited_as_a_whole() {
+        let family = "👨\u{200d}👩\u{200d}👧";
+        let mut input = input(&format!("a{}e\u{301}", family));
+        input.delete_backward();
+        assert_eq!(input, format!("a{}", family).as_str());
+        input.move_left();
+        assert_eq!(input.cursor(), 1);
+        input.delete_forward();
+        assert_eq!(input, "a");
+    }
+
+    #[test]
+    fn ctrl_w_deletes_the_previous_word_and_ctrl_u_the_line() {
+        let mut input = input("buy  fresh milk  ");
+        input.delete_word();
+        assert_eq!(input, "buy  fresh ");
+        input.delete_word();
+        assert_eq!(input, "buy  ");
+        input.delete_word();
+        assert_eq!(input, "");
+        assert!(!input.delete_word());
+
+        let mut input = self::input("line 1\nline 2");
+        input.move_left();
+        input.delete_to_line_start();
+        assert_eq!(input, "line 1\n2");
+        input.move_end();
+        input.move_home();
+        assert_eq!(input.cursor(), "line 1\n".len());
+    }
+}
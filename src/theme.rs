@@ -0,0 +1,230 @@
+use crate::paths;
+use ratatui::style::{Color, Modifier, Style};
+use serde::{Deserialize, Serialize};
+
+// 界面配色，从 theme.json 加载，缺省的字段使用默认值
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    pub border: Color,            // 普通边框
+    pub border_active: Color,     // 活动面板边框
+    pub highlight: Option<Color>, // 选中行背景色，未设置时反色显示
+    pub completed: Color,         // 已完成的 todo
+    pub overdue: Color,           // 已过期的 todo
+    pub alert: Color,             // 计时超时提醒时闪烁的颜色
+    pub marked: Color,            // 多选模式下选中的 todo
+    pub search_match: Color,      // 匹配搜索关键字的 todo
+    pub help: Color,              // 底部帮助信息
+    pub error: Color,             // 底部错误提示
+    pub warning: Color,           // 底部警告提示
+    pub info: Color,              // 底部确认提示
+    pub timer: Color,             // 计时图标
+    pub over_budget: Color,       // 耗时超过预计耗时
+    pub dimmed: Color,            // 不可选或已不统计的条目
+    pub completed_crossed: bool,  // 已完成的 todo 是否显示删除线
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            border: Color::Reset,
+            border_active: Color::Yellow,
+            highlight: None,
+            completed: Color::Reset,
+            overdue: Color::Red,
+            alert: Color::Red,
+            marked: Color::Cyan,
+            search_match: Color::Yellow,
+            help: Color::Gray,
+            error: Color::Red,
+            warning: Color::Yellow,
+            info: Color::Green,
+            timer: Color::Reset,
+            over_budget: Color::LightRed,
+            dimmed: Color::DarkGray,
+            completed_crossed: false,
+        }
+    }
+}
+
+// 界面中的图标，部分字体和 SSH 会话中 emoji 显示不正常时使用 ASCII
+pub struct Icons {
+    pub done: &'static str,
+    pub doing: &'static str,
+    pub todo: &'static str,
+    pub timer: &'static str,
+    pub project: &'static str,
+    pub pomodoro: &'static str,
+    pub recurrence: &'static str,
+    pub due: &'static str,
+    pub coffee: &'static str,
+}
+
+pub const EMOJI: Icons = Icons {
+    done: "✅",
+    doing: "🔶",
+    todo: "⭕",
+    timer: "⏱️",
+    project: "📁",
+    pomodoro: "🍅",
+    recurrence: "🔁",
+    due: "📅",
+    coffee: "☕",
+};
+
+pub const ASCII: Icons = Icons {
+    done: "[x]",
+    doing: "[~]",
+    todo: "[ ]",
+    timer: "*",
+    project: "+",
+    pomodoro: "P",
+    recurrence: "R:",
+    due: "D:",
+    coffee: "",
+};
+
+// 内置主题的名称，theme.json 中用 "base" 选择
+pub const THEMES: &[&str] = &["default", "light", "high_contrast"];
+
+impl Theme {
+    // 加载主题文件，文件不存在时使用默认主题
+    pub fn load() -> Result<Theme, String> {
+        match std::fs::read_to_string(Self::get_theme_file_path()) {
+            Ok(content) => Self::from_json(&content),
+            Err(_) => Ok(Theme::default()),
+        }
+    }
+
+    // 内置主题，名称不存在时返回 None
+    pub fn named(name: &str) -> Option<Theme> {
+        let theme = match name {
+            "default" => Theme::default(),
+            // 浅色背景的终端
+            "light" => Theme {
+                border: Color::Gray,
+                border_active: Color::Blue,
+                highlight: Some(Color::Rgb(0xdd, 0xdd, 0xdd)),
+                completed: Color::Gray,
+                overdue: Color::Red,
+                alert: Color::Red,
+                marked: Color::Magenta,
+                search_match: Color::Blue,
+                help: Color::DarkGray,
+                error: Color::Red,
+                warning: Color::Magenta,
+                info: Color::Green,
+                timer: Color::Blue,
+                over_budget: Color::Red,
+                dimmed: Color::Gray,
+                completed_crossed: false,
+            },
+            // 只用高亮度的颜色，已完成的 todo 加删除线，不只靠颜色区分
+            "high_contrast" => Theme {
+                border: Color::White,
+                border_active: Color::LightYellow,
+                highlight: None,
+                completed: Color::Gray,
+                overdue: Color::LightRed,
+                alert: Color::LightRed,
+                marked: Color::LightCyan,
+                search_match: Color::LightYellow,
+                help: Color::White,
+                error: Color::LightRed,
+                warning: Color::LightYellow,
+                info: Color::LightGreen,
+                timer: Color::LightGreen,
+                over_budget: Color::LightMagenta,
+                dimmed: Color::Gray,
+                completed_crossed: true,
+            },
+            _ => return None,
+        };
+        Some(theme)
+    }
+
+    // 解析主题文件，先按 "base" 选择内置主题（默认为 default），再用其余的项覆盖
+    pub fn from_json(content: &str) -> Result<Theme, String> {
+        let error = |e: serde_json::Error| format!("主题文件格式错误：{}", e);
+        let mut value: serde_json::Value = serde_json::from_str(content).map_err(error)?;
+        let base = match value.as_object_mut().and_then(|map| map.remove("base")) {
+            Some(serde_json::Value::String(name)) => name,
+            Some(_) => return Err("主题文件格式错误：base 应为主题名称".to_string()),
+            None => "default".to_string(),
+        };
+        let theme = Theme::named(&base)
+            .ok_or_else(|| format!("未知的主题：{}，可选 {}", base, THEMES.join("、")))?;
+
+        let mut merged = serde_json::to_value(theme).map_err(error)?;
+        if let (Some(merged), Some(overrides)) = (merged.as_object_mut(), value.as_object()) {
+            merged.extend(overrides.clone());
+        }
+        serde_json::from_value(merged).map_err(error)
+    }
+
+    // 获取主题文件路径，放在系统配置目录中
+    pub fn get_theme_file_path() -> String {
+        paths::config_file("theme.json")
+    }
+
+    // 面板边框样式
+    pub fn border_style(&self, active: bool) -> Style {
+        Style::default().fg(if active {
+            self.border_active
+        } else {
+            self.border
+        })
+    }
+
+    // 已完成的 todo 的样式
+    pub fn completed_style(&self) -> Style {
+        let style = Style::default().fg(self.completed);
+        match self.completed_crossed {
+            true => style.add_modifier(Modifier::CROSSED_OUT),
+            false => style,
+        }
+    }
+
+    // 列表选中行样式
+    pub fn highlight_style(&self) -> Style {
+        match self.highlight {
+            Some(color) => Style::default().bg(color),
+            None => Style::default().add_modifier(Modifier::REVERSED),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_theme_keeps_defaults() {
+        let theme =
+            Theme::from_json(r##"{"border_active": "cyan", "highlight": "#303030"}"##).unwrap();
+        assert_eq!(theme.border_active, Color::Cyan);
+        assert_eq!(theme.highlight, Some(Color::Rgb(0x30, 0x30, 0x30)));
+        assert_eq!(theme.help, Color::Gray);
+        assert_eq!(theme.overdue, Color::Red);
+    }
+
+    #[test]
+    fn base_theme_is_overridden_by_listed_fields() {
+        let theme = Theme::from_json(r#"{"base": "light", "timer": "green"}"#).unwrap();
+        assert_eq!(theme.timer, Color::Green);
+        assert_eq!(theme.border_active, Color::Blue);
+        assert!(
+            Theme::from_json(r#"{"base": "high_contrast"}"#)
+                .unwrap()
+                .completed_crossed
+        );
+        assert!(THEMES.iter().all(|name| Theme::named(name).is_some()));
+    }
+
+    #[test]
+    fn invalid_theme_is_an_error() {
+        assert!(Theme::from_json(r#"{"border": "not-a-color"}"#).is_err());
+        assert!(Theme::from_json(r#"{"base": "solarized"}"#).is_err());
+        assert!(Theme::from_json(r#"{"base": 1}"#).is_err());
+    }
+}
@@ -0,0 +1,2268 @@
+use crate::app::{App, InputMode, Panel, View};
+use crate::dates;
+use crate::keys::{Action, KeyBindings};
+use crate::message::Level;
+use crate::model::{
+    format_duration, now_secs, project_depth, project_subtree, today, DurationUnit, Priority,
+    SortMode, Status, Templated,
+};
+use crate::palette::Command;
+use crate::pomodoro::{self, Phase};
+use crate::report;
+use chrono::Datelike;
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Margin, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{
+        Bar, BarChart, BarGroup, Block, Borders, List, ListItem, ListState, Paragraph, Scrollbar,
+        ScrollbarOrientation, ScrollbarState, Sparkline, Wrap,
+    },
+    Frame,
+};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+// 确认类的提示自动隐藏前显示的秒数
+const TOAST_SECS: u64 = 5;
+
+pub fn ui(f: &mut Frame, app: &mut App) {
+    let terminal_width = f.area().width;
+    // 列表没有显示时（如专注模式）不响应点击
+    app.project_list_area = Rect::default();
+    app.todo_list_area = Rect::default();
+
+    // 终端足够高时在底部留出一行显示帮助信息，避免和列表重叠
+    let (main_area, help_area) = if f.area().height > 5 {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)])
+            .split(f.area());
+        (rows[0], Some(rows[1]))
+    } else {
+        (f.area(), None)
+    };
+
+    // 根据终端宽度动态调整布局
+    let (left_constraint, right_constraint) = if terminal_width < 80 {
+        // 窄屏幕：垂直布局
+        (Constraint::Percentage(100), Constraint::Percentage(0))
+    } else if let Some(percent) = app.project_split {
+        // 调整过宽度时按比例分配，两个面板都保留最小宽度
+        let left = (terminal_width as u32 * percent as u32 / 100) as u16;
+        (
+            Constraint::Length(left.clamp(20, terminal_width - 40)),
+            Constraint::Min(0),
+        )
+    } else if terminal_width < 120 {
+        // 中等屏幕：左侧较窄
+        (Constraint::Min(25), Constraint::Min(40))
+    } else {
+        // 宽屏幕：正常比例
+        (Constraint::Percentage(30), Constraint::Percentage(70))
+    };
+
+    // 当屏幕太窄时使用垂直布局
+    let chunks = if terminal_width < 80 {
+        let vertical_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
+            .split(main_area);
+        vertical_chunks
+    } else {
+        Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([left_constraint, right_constraint].as_ref())
+            .split(main_area)
+    };
+
+    // 专注模式下隐藏项目面板，Todo 列表占满整个区域；单面板模式下只显示当前面板
+    let (project_area, todo_panel) = if app.focus_mode {
+        (None, Some(main_area))
+    } else if app.single_pane {
+        match app.active_panel {
+            Panel::Projects => (Some(main_area), None),
+            Panel::Todos => (None, Some(main_area)),
+        }
+    } else {
+        (Some(chunks[0]), chunks.get(1).copied())
+    };
+
+    // 左侧：项目列表
+    if let Some(project_area) = project_area {
+        render_projects(f, app, project_area);
+    }
+
+    // 右侧：Todo列表（如果有空间显示）
+    let add_key = app.keys.label(Action::AddItem);
+    if let Some(todo_panel) = todo_panel.filter(|area| area.width > 10) {
+        // 打开详情面板时在列表右边（窄屏时在下方）显示选中 todo 的全部信息
+        let (todo_panel, detail_area) = match app.detail_pane {
+            true => {
+                let direction = match terminal_width < 80 {
+                    true => Direction::Vertical,
+                    false => Direction::Horizontal,
+                };
+                let parts = Layout::default()
+                    .direction(direction)
+                    .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+                    .split(todo_panel);
+                (parts[0], Some(parts[1]))
+            }
+            false => (todo_panel, None),
+        };
+        // 选中的 todo 有描述时，在列表下方显示描述，详情面板中已经显示时不再重复
+        let description = app
+            .todo_state
+            .selected()
+            .and_then(|i| {
+                app.get_current_todos()
+                    .get(i)
+                    .map(|t| t.description.clone())
+            })
+            .filter(|d| detail_area.is_none() && !d.trim().is_empty());
+        let (todo_area, description_area) = match &description {
+            Some(description) => {
+                let lines = description.lines().count() as u16;
+                let height = if app.description_expanded {
+                    (lines + 2).min(todo_panel.height / 2)
+                } else {
+                    lines.min(DESCRIPTION_PREVIEW_LINES + 1) + 2
+                };
+                let rows = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Min(3), Constraint::Length(height)])
+                    .split(todo_panel);
+                (rows[0], Some(rows[1]))
+            }
+            None => (todo_panel, None),
+        };
+
+        let now = now_secs();
+        let alert_threshold = app.config.timer_alert_secs();
+        let today = today();
+        let icons = app.icons();
+        let todos = app.get_current_todos();
+        let todos_empty = todos.is_empty();
+        let todo_count = todos.len();
+        let todo_items: Vec<ListItem> = todos
+            .iter()
+            .zip(app.visible_todo_indices())
+            .map(|(todo, todo_idx)| {
+                let marked = app.marked_todos.contains(&todo_idx);
+                let status = match todo.status() {
+                    Status::Done => icons.done,
+                    Status::Doing => icons.doing,
+                    Status::Todo => icons.todo,
+                };
+                let timer_indicator = match todo.is_working() {
+                    true => format!("{} ", icons.timer),
+                    false => String::new(),
+                };
+                // 计时超过提醒阈值时，计时图标每秒切换一次颜色
+                let indicator_style = match alert_threshold {
+                    Some(threshold) if todo.session_elapsed(now) >= threshold && now % 2 == 1 => {
+                        Style::default().bg(app.theme.alert)
+                    }
+                    _ => Style::default().fg(app.theme.timer),
+                };
+                // 有子任务时在标题后显示完成进度
+                let progress = match todo.subtask_progress() {
+                    Some((done, total)) => format!(" [{}/{}]", done, total),
+                    None => String::new(),
+                };
+                // 正在计时的 todo 显示包括本次计时在内的耗时，每次重绘时更新
+                // 设置了预计耗时时显示为 [耗时 / 预计]，超出预计时换颜色
+                let unit = app.config.duration_unit;
+                let duration = todo.elapsed(now);
+                let budget = match todo.estimate {
+                    Some(estimate) => format!(" [{}]", budget_label(duration, estimate, unit)),
+                    None if duration > 0 => format!(" [{}]", format_duration(duration, unit)),
+                    None => String::new(),
+                };
+                let budget_style = match todo.over_budget(now) {
+                    true => Style::default().fg(app.theme.over_budget),
+                    false => Style::default(),
+                };
+                let time_str = match todo.pomodoros {
+                    0 => String::new(),
+                    n => format!(" {}{}", icons.pomodoro, n),
+                };
+                let time_str = match todo.recurrence {
+                    Some(recurrence) => {
+                        format!("{} {}{}", time_str, icons.recurrence, recurrence.label())
+                    }
+                    None => time_str,
+                };
+                let time_str = match todo.due_date {
+                    Some(due) => format!(
+                        "{} {}{}",
+                        time_str,
+                        icons.due,
+                        due.format(&app.config.date_format)
+                    ),
+                    None => time_str,
+                };
+
+                // 窄屏时截断文本，截断后不再显示耗时等信息
+                let max_len = (todo_panel.width as usize).saturating_sub(12);
+                let (text, budget, time_str) =
+                    if todo_panel.width < 30 && todo.title.width() > max_len {
+                        let title = format!("{}...", truncate(&todo.title, max_len));
+                        (title, String::new(), String::new())
+                    } else {
+                        (format!("{}{}", todo.title, progress), budget, time_str)
+                    };
+                // 多选模式下在行首显示选择框
+                let checkbox = match (app.selecting, marked) {
+                    (false, _) => "",
+                    (true, false) => "[ ] ",
+                    (true, true) => "[x] ",
+                };
+                let mut spans = vec![
+                    Span::raw(checkbox),
+                    Span::raw(format!("{} ", status)),
+                    Span::styled(timer_indicator, indicator_style),
+                    priority_span(app, todo.priority),
+                    Span::raw(text),
+                    Span::styled(budget, budget_style),
+                    Span::raw(time_str),
+                ];
+                // 标签显示在标题后面，窄屏时省略
+                if todo_panel.width >= 30 {
+                    for tag in &todo.tags {
+                        spans.push(Span::styled(
+                            format!(" #{}", tag),
+                            Style::default().fg(app.theme.help),
+                        ));
+                    }
+                }
+                let title = Line::from(spans);
+                let is_match = app.search.as_ref().is_some_and(|q| todo.matches(q));
+                if marked {
+                    ListItem::new(title).style(Style::default().fg(app.theme.marked))
+                } else if is_match {
+                    ListItem::new(title).style(Style::default().fg(app.theme.search_match))
+                } else if todo.is_overdue(today) {
+                    ListItem::new(title).style(Style::default().fg(app.theme.overdue))
+                } else if todo.completed {
+                    ListItem::new(title).style(app.theme.completed_style())
+                } else {
+                    ListItem::new(title)
+                }
+            })
+            .collect();
+
+        let sort_label = app
+            .get_current_project()
+            .map_or(SortMode::Original, |p| p.sort_mode)
+            .label();
+        // 多选模式下在标题中显示已选数量，筛选时显示筛选的标签
+        let mut status_label = if app.selecting {
+            format!(" [多选 {}]", app.marked_todos.len())
+        } else {
+            String::new()
+        };
+        if let Some(tag) = &app.tag_filter {
+            status_label.push_str(&format!(" [#{}]", tag));
+        }
+        if let Some(query) = &app.search {
+            status_label.push_str(&format!(" [/{}]", query));
+        }
+        let hidden = app.hidden_completed_count();
+        if hidden > 0 {
+            status_label.push_str(&format!(" [隐藏 {} 个已完成]", hidden));
+        }
+        // 项目中设置了预计耗时的 todo 合计的耗时和预计耗时
+        let estimate_progress = app
+            .get_current_project()
+            .and_then(|p| p.estimate_progress(now));
+        if let Some((elapsed, estimate)) = estimate_progress {
+            let label = budget_label(elapsed, estimate, app.config.duration_unit);
+            status_label.push_str(&format!(" [预计 {}]", label));
+        }
+        let todos_title = if terminal_width < 80 {
+            format!(
+                "Todo [{}] [{}]{}",
+                if app.active_panel == Panel::Todos {
+                    "选中"
+                } else {
+                    "未选中"
+                },
+                sort_label,
+                status_label
+            )
+        } else {
+            format!(
+                "Todo - {} [{}]{}",
+                app.get_current_project().map_or("无项目", |p| &p.name),
+                sort_label,
+                status_label
+            )
+        };
+
+        let todos_list = List::new(todo_items)
+            .block(
+                Block::default()
+                    .title(todos_title)
+                    .borders(Borders::ALL)
+                    .border_style(app.theme.border_style(app.active_panel == Panel::Todos)),
+            )
+            .highlight_style(app.theme.highlight_style())
+            .highlight_symbol(">> ")
+            .scroll_padding(1);
+
+        f.render_stateful_widget(todos_list, todo_area, &mut app.todo_state);
+        app.todo_list_area = todo_area;
+        render_scrollbar(
+            f,
+            todo_area,
+            todo_count,
+            app.todo_state.offset(),
+            app.theme.border_style(app.active_panel == Panel::Todos),
+        );
+
+        // 列表为空时显示引导文字
+        if todos_empty {
+            let hint = if app.projects.is_empty() {
+                "先在项目面板添加一个项目".to_string()
+            } else if let Some(tag) = &app.tag_filter {
+                format!(
+                    "没有带 #{} 的任务 — 按 {} 修改筛选",
+                    tag,
+                    app.keys.label(Action::FilterTag)
+                )
+            } else if hidden > 0 {
+                format!(
+                    "任务都已完成 — 按 {} 显示已完成的任务",
+                    app.keys.label(Action::HideCompleted)
+                )
+            } else if app.active_panel == Panel::Todos {
+                format!("没有任务 — 按 {} 添加任务", add_key)
+            } else {
+                format!(
+                    "没有任务 — 按 {} 切换到此面板后按 {} 添加",
+                    app.keys.label(Action::SwitchPanel),
+                    add_key
+                )
+            };
+            render_placeholder(f, todo_area, &hint, app.theme.help);
+        }
+
+        if let (Some(description), Some(area)) = (description, description_area) {
+            render_description(f, app, &description, area);
+        }
+        if let Some(area) = detail_area {
+            render_detail_pane(f, app, area);
+        }
+    }
+
+    match app.view {
+        View::Report => render_report(f, app),
+        View::Subtasks => render_subtasks(f, app),
+        View::ConfirmDeleteProject => render_delete_confirmation(f, app),
+        View::ConfirmDeleteTodos => render_delete_marked_confirmation(f, app),
+        View::Trash => render_trash(f, app),
+        View::ProjectPicker => render_project_picker(f, app),
+        View::Board => render_board(f, app),
+        View::Archive => render_archive(f, app),
+        View::Stats => render_stats(f, app),
+        View::Sessions => render_sessions(f, app),
+        View::PomodoroNotice => render_pomodoro_notice(f, app),
+        View::ResumeTimers => render_resume_timers(f, app),
+        View::IdlePrompt => render_idle_prompt(f, app),
+        View::CorruptData => render_corrupt_data(f, app),
+        View::Help => render_help(f, app),
+        View::Messages => render_messages(f, app),
+        View::Details => render_details(f, app),
+        View::Activity => render_activity(f, app),
+        View::Today => render_today(f, app),
+        View::Calendar => render_calendar(f, app),
+        View::Agenda => render_agenda(f, app),
+        View::Zen => render_zen(f, app),
+        View::Workspaces => render_workspaces(f, app),
+        View::ArchivedProjects => render_archived_projects(f, app),
+        View::Templates => render_templates(f, app),
+        View::Main => {}
+    }
+
+    // 输入框 - 调整弹窗大小
+    if app.input_mode == InputMode::EditingDescription {
+        render_description_editor(f, app);
+    } else if app.input_mode == InputMode::Finding {
+        render_finder(f, app);
+    } else if app.input_mode == InputMode::Commanding {
+        render_palette(f, app);
+    } else if app.input_mode != InputMode::Normal {
+        let input_title = match app.input_mode {
+            InputMode::AddingProject => "添加新项目",
+            InputMode::AddingTodo => "添加新Todo",
+            InputMode::RenamingProject => "重命名项目",
+            InputMode::RenamingTodo => "重命名Todo",
+            InputMode::MovingTodos => "移动到项目",
+            InputMode::SettingDueDate => "截止日期，如 2026-03-04、tomorrow、next monday，留空清除",
+            InputMode::FilteringTag => "按标签筛选，留空显示全部",
+            InputMode::AddingSubtask => "添加子任务",
+            InputMode::EditingSession => "修改计时记录 YYYY-MM-DD HH:MM - YYYY-MM-DD HH:MM",
+            InputMode::LoggingTime => "补记耗时，如 45m、1h30m 昨天、2h 2026-03-02",
+            InputMode::SettingEstimate => "预计耗时，如 2h、1h30m、45m，留空清除",
+            InputMode::AddingWorkspace => "新工作区的名称，只能包含字母、数字、- 和 _",
+            InputMode::SettingParent => "上级项目的名称，留空移到顶层",
+            InputMode::NamingTemplate => "模板的名称，同名的模板会被替换",
+            InputMode::TaggingTodos => "给选中的任务添加标签，多个标签用空格分隔",
+            InputMode::Searching => "搜索标题和描述，Enter 确认后按 n/N 跳转",
+            _ => "",
+        };
+
+        // 标题中显示实时字数
+        let input_title = format!("{} ({}字)", input_title, app.input.graphemes(true).count());
+
+        // 根据终端大小调整弹窗
+        let (popup_width, popup_height) = if terminal_width < 60 {
+            (90, 3) // 窄屏时占更多比例
+        } else {
+            (60, 3) // 正常大小
+        };
+
+        let popup_area = centered_rect(popup_width, popup_height, f.area());
+        // 输入超出弹窗宽度时横向滚动，保持光标可见
+        let cursor_column = app.input[..app.input.cursor()].width() as u16;
+        let offset = (cursor_column + 1).saturating_sub(popup_area.width.saturating_sub(2));
+        let input = Paragraph::new(app.input.with_cursor())
+            .scroll((0, offset))
+            .block(Block::default().title(input_title).borders(Borders::ALL));
+        f.render_widget(ratatui::widgets::Clear, popup_area);
+        f.render_widget(input, popup_area);
+    }
+
+    // 在底部显示帮助信息，有提示信息时优先显示提示
+    if let Some(help_area) = help_area {
+        let help_text = help_text(&app.keys);
+
+        // 有计时进行时，在左侧显示正在计时的任务和本次已计时的时间，番茄钟进行中时显示倒计时
+        let now = now_secs();
+        let (work_secs, break_secs) = app.config.pomodoro_secs();
+        let countdown = app.pomodoro.as_ref().map(|p| {
+            format!(
+                "{} {}  ",
+                p.phase.label(),
+                pomodoro::format_countdown(p.remaining(now, work_secs, break_secs))
+            )
+        });
+        let running = app.running_todo().map(|(project, todo)| {
+            format!(
+                "{} {} / {} {}  ",
+                app.icons().timer,
+                project.name,
+                todo.title,
+                format_duration(todo.session_elapsed(now), app.config.duration_unit)
+            )
+        });
+        let status = format!(
+            "{}{}",
+            countdown.unwrap_or_default(),
+            running.unwrap_or_default()
+        );
+        let help_area = match status.is_empty() {
+            false => {
+                let status = Line::from(status);
+                let columns = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([
+                        Constraint::Length(status.width() as u16),
+                        Constraint::Min(0),
+                    ])
+                    .split(help_area);
+                f.render_widget(
+                    Paragraph::new(status).style(Style::default().fg(app.theme.border_active)),
+                    columns[0],
+                );
+                columns[1]
+            }
+            true => help_area,
+        };
+
+        // 确认类的提示过几秒后自动隐藏，警告和错误一直显示到下次按键
+        let message = app
+            .message
+            .as_ref()
+            .filter(|m| m.level != Level::Info || now_secs() < m.at + TOAST_SECS);
+        let help_paragraph = match message {
+            Some(message) => Paragraph::new(message.text.as_str())
+                .style(Style::default().fg(level_color(app, message.level))),
+            None => Paragraph::new(help_text).style(Style::default().fg(app.theme.help)),
+        };
+
+        f.render_widget(help_paragraph, help_area);
+    }
+}
+
+// 渲染项目列表，折叠的项目只显示名称，选中的项目总是展开显示
+// 子项目缩进显示在上级项目下，有子项目的项目前面是 +（已收起）或 -，数量和耗时包括子项目的
+fn render_projects(f: &mut Frame, app: &mut App, area: Rect) {
+    let selected = app.project_state.selected();
+    let now = now_secs();
+    let visible = app.visible_project_indices();
+    let project_items: Vec<ListItem> = visible
+        .iter()
+        .map(|&i| {
+            let project = &app.projects[i];
+            let subtree = &app.projects[project_subtree(&app.projects, i)];
+            let indent = "  ".repeat(project_depth(&app.projects, i));
+            let fold = match (subtree.len() > 1, project.folded) {
+                (false, _) => "",
+                (true, true) => "+ ",
+                (true, false) => "- ",
+            };
+            let prefix = format!("{}{}", indent, fold);
+            // 有正在计时的 todo 时，用计时图标代替文件夹图标
+            let running = subtree
+                .iter()
+                .any(|p| p.todos.iter().any(|todo| todo.is_working()));
+            let icon = match running {
+                true => app.icons().timer,
+                false => app.icons().project,
+            };
+            if project.collapsed && Some(i) != selected {
+                let max_len = (area.width as usize).saturating_sub(7 + prefix.width());
+                let name = truncate(&project.name, max_len);
+                let name = match running {
+                    true => format!("{}▸ {} {}", prefix, name, icon),
+                    false => format!("{}▸ {}", prefix, name),
+                };
+                return ListItem::new(name).style(Style::default().fg(app.theme.help));
+            }
+            let count: usize = subtree.iter().map(|p| p.todos.len()).sum();
+
+            let name = if area.width < 20 {
+                // 极窄时只显示项目名
+                let max_len = (area.width as usize).saturating_sub(5);
+                if project.name.width() > max_len {
+                    format!(
+                        "{}{}",
+                        icon,
+                        truncate(&project.name, (area.width as usize).saturating_sub(8))
+                    )
+                } else {
+                    format!("{}{}", icon, project.name)
+                }
+            } else {
+                // 正常显示，有耗时时在后面显示合计
+                let tracked: u64 = subtree
+                    .iter()
+                    .map(|p| p.tracked_time(now, app.config.project_time_includes_completed))
+                    .sum();
+                match tracked {
+                    0 => format!("{}{} {} ({})", prefix, icon, project.name, count),
+                    _ => format!(
+                        "{}{} {} ({}) [{}]",
+                        prefix,
+                        icon,
+                        project.name,
+                        count,
+                        format_duration(tracked, app.config.duration_unit)
+                    ),
+                }
+            };
+            ListItem::new(name)
+        })
+        .collect();
+
+    let projects_title = if f.area().width < 80 {
+        format!(
+            "项目 [{}]",
+            if app.active_panel == Panel::Projects {
+                "选中"
+            } else {
+                "未选中"
+            }
+        )
+    } else {
+        "项目".to_string()
+    };
+    let projects_title = match &app.workspace {
+        Some(workspace) => format!("{} - {}", projects_title, workspace),
+        None => projects_title,
+    };
+
+    let projects_list = List::new(project_items)
+        .block(
+            Block::default()
+                .title(projects_title)
+                .borders(Borders::ALL)
+                .border_style(app.theme.border_style(app.active_panel == Panel::Projects)),
+        )
+        .highlight_style(app.theme.highlight_style())
+        .highlight_symbol(">> ")
+        .scroll_padding(1);
+
+    // project_state 记录的是项目下标，收起的子项目不显示，按显示的行号渲染
+    let mut state = app.project_state.clone();
+    state.select(selected.and_then(|i| visible.iter().position(|&v| v == i)));
+    f.render_stateful_widget(projects_list, area, &mut state);
+    *app.project_state.offset_mut() = state.offset();
+    app.project_list_area = area;
+    render_scrollbar(
+        f,
+        area,
+        visible.len(),
+        state.offset(),
+        app.theme.border_style(app.active_panel == Panel::Projects),
+    );
+
+    // 没有项目时显示引导文字
+    if app.projects.is_empty() {
+        render_placeholder(
+            f,
+            area,
+            &format!(
+                "还没有项目 — 按 {} 添加一个",
+                app.keys.label(Action::AddItem)
+            ),
+            app.theme.help,
+        );
+    }
+}
+
+// 列表超出面板高度时在右边框上显示滚动条，滑块位置对应列表滚动的偏移
+fn render_scrollbar(f: &mut Frame, area: Rect, len: usize, offset: usize, style: Style) {
+    let visible = area.height.saturating_sub(2) as usize;
+    if len <= visible {
+        return;
+    }
+    let mut state = ScrollbarState::new(len - visible)
+        .position(offset)
+        .viewport_content_length(visible);
+    let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+        .begin_symbol(None)
+        .end_symbol(None)
+        .style(style);
+    f.render_stateful_widget(scrollbar, area.inner(Margin::new(0, 1)), &mut state);
+}
+
+// 在面板边框内居中显示一行提示文字
+fn render_placeholder(f: &mut Frame, area: Rect, text: &str, color: Color) {
+    let inner = area.inner(Margin::new(1, 1));
+    if inner.height == 0 || inner.width == 0 {
+        return;
+    }
+
+    let line_area = Rect {
+        y: inner.y + (inner.height.saturating_sub(1)) / 2,
+        height: inner.height.min(2),
+        ..inner
+    };
+    let paragraph = Paragraph::new(text)
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true })
+        .style(Style::default().fg(color));
+    f.render_widget(paragraph, line_area);
+}
+
+// 渲染多行的描述编辑框，内容超出时滚动到光标所在的行
+fn render_description_editor(f: &mut Frame, app: &App) {
+    let area = popup_rect(70, 60, f.area());
+    let text = app.input.with_cursor();
+    let visible = area.height.saturating_sub(2);
+    let cursor_line = app.input[..app.input.cursor()].matches('\n').count() as u16;
+    let scroll = (cursor_line + 1).saturating_sub(visible);
+    let editor = Paragraph::new(text).scroll((scroll, 0)).block(
+        Block::default()
+            .title("编辑描述 (Enter 换行, Ctrl+S 保存, Esc 取消)")
+            .borders(Borders::ALL)
+            .border_style(app.theme.border_style(true)),
+    );
+    f.render_widget(ratatui::widgets::Clear, area);
+    f.render_widget(editor, area);
+}
+
+// 全局查找弹窗：上方输入，下方是按匹配度排列的结果
+fn render_finder(f: &mut Frame, app: &mut App) {
+    let area = popup_rect(70, 70, f.area());
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(0)])
+        .split(area);
+
+    let input = Paragraph::new(app.input.with_cursor()).block(
+        Block::default()
+            .title("查找所有项目的任务 (↑/↓ 选择, Enter 跳转, Esc 取消)")
+            .borders(Borders::ALL)
+            .border_style(app.theme.border_style(true)),
+    );
+    let items: Vec<ListItem> = app
+        .finder_results
+        .iter()
+        .map(|&(p, t)| {
+            let project = &app.projects[p];
+            let todo = &project.todos[t];
+            let style = if todo.completed {
+                app.theme.completed_style()
+            } else {
+                Style::default()
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(
+                    format!("{}  ", project.name),
+                    Style::default().fg(app.theme.help),
+                ),
+                Span::styled(todo.title.clone(), style),
+            ]))
+        })
+        .collect();
+    let empty = items.is_empty();
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL))
+        .highlight_style(app.theme.highlight_style())
+        .highlight_symbol(">> ");
+
+    f.render_widget(ratatui::widgets::Clear, area);
+    f.render_widget(input, rows[0]);
+    f.render_stateful_widget(list, rows[1], &mut app.finder_state);
+    if empty {
+        render_placeholder(f, rows[1], "没有匹配的任务", app.theme.help);
+    }
+}
+
+// 渲染命令面板，每行显示命令的名称、英文名称和绑定的按键
+fn render_palette(f: &mut Frame, app: &mut App) {
+    let area = popup_rect(70, 70, f.area());
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(0)])
+        .split(area);
+
+    let input = Paragraph::new(app.input.with_cursor()).block(
+        Block::default()
+            .title("命令 (↑/↓ 选择, Enter 执行, Esc 取消)")
+            .borders(Borders::ALL)
+            .border_style(app.theme.border_style(true)),
+    );
+    let items: Vec<ListItem> = app
+        .palette_results
+        .iter()
+        .map(|&command| {
+            let key = match command {
+                Command::Action(action) => format!("  [{}]", app.keys.labels(action)),
+                _ => String::new(),
+            };
+            ListItem::new(Line::from(vec![
+                Span::raw(command.label()),
+                Span::styled(
+                    format!("  {}{}", command.name(), key),
+                    Style::default().fg(app.theme.help),
+                ),
+            ]))
+        })
+        .collect();
+    let empty = items.is_empty();
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL))
+        .highlight_style(app.theme.highlight_style())
+        .highlight_symbol(">> ");
+
+    f.render_widget(ratatui::widgets::Clear, area);
+    f.render_widget(input, rows[0]);
+    f.render_stateful_widget(list, rows[1], &mut app.palette_state);
+    if empty {
+        render_placeholder(f, rows[1], "没有匹配的命令", app.theme.help);
+    }
+}
+
+// 渲染回收站，从新到旧排列
+fn render_trash(f: &mut Frame, app: &mut App) {
+    let area = popup_rect(70, 70, f.area());
+    let items: Vec<ListItem> = app
+        .trash
+        .iter()
+        .rev()
+        .map(|item| {
+            let deleted_at = chrono::DateTime::from_timestamp(item.deleted_at as i64, 0)
+                .map(|t| {
+                    t.with_timezone(&chrono::Local)
+                        .format("%m-%d %H:%M")
+                        .to_string()
+                })
+                .unwrap_or_default();
+            ListItem::new(format!("{}  {}", deleted_at, item.label()))
+        })
+        .collect();
+    let empty = items.is_empty();
+
+    let retention = match app.config.trash_retention_days {
+        0 => String::new(),
+        days => format!(", {} 天后自动清除", days),
+    };
+    let title = format!(
+        "回收站 (Enter 恢复, {} 永久删除, Esc 关闭{})",
+        app.keys.label(Action::Delete),
+        retention
+    );
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_style(app.theme.border_style(true)),
+        )
+        .highlight_style(app.theme.highlight_style())
+        .highlight_symbol(">> ");
+    f.render_widget(ratatui::widgets::Clear, area);
+    f.render_stateful_widget(list, area, &mut app.trash_state);
+
+    if empty {
+        render_placeholder(f, area, "回收站是空的", app.theme.help);
+    }
+}
+
+// 渲染当前项目的归档，从新到旧排列
+fn render_archive(f: &mut Frame, app: &mut App) {
+    let Some(project) = app.get_current_project() else {
+        return;
+    };
+    let area = popup_rect(70, 70, f.area());
+    let items: Vec<ListItem> = project
+        .archived
+        .iter()
+        .rev()
+        .map(|todo| {
+            let time_str = if todo.total_duration() > 0 {
+                format!(" [{}]", todo.format_duration(app.config.duration_unit))
+            } else {
+                String::new()
+            };
+            ListItem::new(format!("{} {}{}", app.icons().done, todo.title, time_str))
+                .style(app.theme.completed_style())
+        })
+        .collect();
+    let empty = items.is_empty();
+    let title = format!("归档 - {} (Enter 放回列表, Esc 关闭)", project.name);
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_style(app.theme.border_style(true)),
+        )
+        .highlight_style(app.theme.highlight_style())
+        .highlight_symbol(">> ");
+    f.render_widget(ratatui::widgets::Clear, area);
+    f.render_stateful_widget(list, area, &mut app.archive_state);
+
+    if empty {
+        render_placeholder(f, area, "没有归档的任务", app.theme.help);
+    }
+}
+
+// 保存的模板，项目模板显示 todo 数，todo 模板显示子任务数
+fn render_templates(f: &mut Frame, app: &mut App) {
+    let area = popup_rect(60, 60, f.area());
+    let items: Vec<ListItem> = app
+        .templates
+        .iter()
+        .map(|template| {
+            ListItem::new(match &template.item {
+                Templated::Project(project) => format!(
+                    "{} {} ({} 个 Todo)",
+                    app.icons().project,
+                    template.name,
+                    project.todos.len()
+                ),
+                Templated::Todo(todo) => format!(
+                    "{} {} ({} 个子任务)",
+                    app.icons().todo,
+                    template.name,
+                    todo.subtasks.len()
+                ),
+            })
+        })
+        .collect();
+    let empty = items.is_empty();
+    let title = format!(
+        "模板 (Enter 创建, {} 删除, Esc 关闭)",
+        app.keys.label(Action::Delete)
+    );
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_style(app.theme.border_style(true)),
+        )
+        .highlight_style(app.theme.highlight_style())
+        .highlight_symbol(">> ");
+    f.render_widget(ratatui::widgets::Clear, area);
+    f.render_stateful_widget(list, area, &mut app.templates_state);
+
+    if empty {
+        let hint = format!(
+            "没有模板，按 {} 把选中的项目或 Todo 保存为模板",
+            app.keys.label(Action::SaveTemplate)
+        );
+        render_placeholder(f, area, &hint, app.theme.help);
+    }
+}
+
+// 归档的项目，从新到旧
+fn render_archived_projects(f: &mut Frame, app: &mut App) {
+    let area = popup_rect(60, 60, f.area());
+    let now = now_secs();
+    let items: Vec<ListItem> = app
+        .archived_projects
+        .iter()
+        .rev()
+        .map(|project| {
+            let tracked = project.tracked_time(now, true);
+            let time_str = match tracked {
+                0 => String::new(),
+                _ => format!(" [{}]", format_duration(tracked, app.config.duration_unit)),
+            };
+            ListItem::new(format!(
+                "{} {} ({}){}",
+                app.icons().project,
+                project.name,
+                project.todos.len(),
+                time_str
+            ))
+        })
+        .collect();
+    let empty = items.is_empty();
+    let title = format!(
+        "归档的项目 (Enter 放回列表, {} 导出 Markdown, Esc 关闭)",
+        app.keys.label(Action::ExportMarkdown)
+    );
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_style(app.theme.border_style(true)),
+        )
+        .highlight_style(app.theme.highlight_style())
+        .highlight_symbol(">> ");
+    f.render_widget(ratatui::widgets::Clear, area);
+    f.render_stateful_widget(list, area, &mut app.archived_projects_state);
+
+    if empty {
+        render_placeholder(f, area, "没有归档的项目", app.theme.help);
+    }
+}
+
+// 渲染看板，每个状态一列，选中的列边框高亮
+fn render_board(f: &mut Frame, app: &App) {
+    let Some(project) = app.get_current_project() else {
+        return;
+    };
+    let area = popup_rect(90, 80, f.area());
+    f.render_widget(ratatui::widgets::Clear, area);
+    let outer = Block::default()
+        .title(format!(
+            "看板 - {} (h/l 移动卡片, Tab 切换列, Esc 关闭)",
+            project.name
+        ))
+        .borders(Borders::ALL)
+        .border_style(app.theme.border_style(true));
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Ratio(1, 3); 3])
+        .split(outer.inner(area));
+    f.render_widget(outer, area);
+
+    let today = today();
+    for (column, (&status, &column_area)) in Status::ALL.iter().zip(columns.iter()).enumerate() {
+        let cards = app.board_cards(column);
+        let items: Vec<ListItem> = cards
+            .iter()
+            .map(|&i| {
+                let todo = &project.todos[i];
+                let item = ListItem::new(todo.title.clone());
+                if todo.is_overdue(today) {
+                    item.style(Style::default().fg(app.theme.overdue))
+                } else if todo.completed {
+                    item.style(app.theme.completed_style())
+                } else {
+                    item
+                }
+            })
+            .collect();
+        let active = column == app.board_column;
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .title(format!("{} ({})", status.label(), cards.len()))
+                    .borders(Borders::ALL)
+                    .border_style(app.theme.border_style(active)),
+            )
+            .highlight_style(app.theme.highlight_style());
+        let mut state = ListState::default();
+        if active {
+            state.select(app.board_selected());
+        }
+        f.render_stateful_widget(list, column_area, &mut state);
+    }
+}
+
+// 选择把 todo 移到哪个项目，当前项目显示为灰色
+fn render_project_picker(f: &mut Frame, app: &mut App) {
+    let area = popup_rect(50, 60, f.area());
+    let current = app.project_state.selected();
+    let items: Vec<ListItem> = app
+        .projects
+        .iter()
+        .enumerate()
+        .map(|(i, project)| {
+            let indent = "  ".repeat(project_depth(&app.projects, i));
+            let item = ListItem::new(format!(
+                "{}{} {}",
+                indent,
+                app.icons().project,
+                project.name
+            ));
+            if Some(i) == current {
+                item.style(Style::default().fg(app.theme.dimmed))
+            } else {
+                item
+            }
+        })
+        .collect();
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title(match app.merging_project {
+                    true => "合并到项目 (Enter 确认, Esc 取消)",
+                    false => "移动到项目 (Enter 确认, Esc 取消)",
+                })
+                .borders(Borders::ALL)
+                .border_style(app.theme.border_style(true)),
+        )
+        .highlight_style(app.theme.highlight_style())
+        .highlight_symbol(">> ");
+    f.render_widget(ratatui::widgets::Clear, area);
+    f.render_stateful_widget(list, area, &mut app.picker_state);
+}
+
+// 删除项目前的确认弹窗
+fn render_delete_confirmation(f: &mut Frame, app: &App) {
+    let Some(project) = app.get_current_project() else {
+        return;
+    };
+    let text = format!(
+        "把项目「{}」及其中的 {} 个任务移到回收站？\n\ny 确认 / 其他键取消",
+        project.name,
+        project.todos.len()
+    );
+    let area = centered_rect(50, 6, f.area());
+    let dialog = Paragraph::new(text)
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true })
+        .block(
+            Block::default()
+                .title("确认删除")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(app.theme.error)),
+        );
+    f.render_widget(ratatui::widgets::Clear, area);
+    f.render_widget(dialog, area);
+}
+
+fn render_delete_marked_confirmation(f: &mut Frame, app: &App) {
+    let text = format!(
+        "把选中的 {} 个任务移到回收站？\n\ny 确认 / 其他键取消",
+        app.marked_todos.len()
+    );
+    let area = centered_rect(50, 6, f.area());
+    let dialog = Paragraph::new(text)
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true })
+        .block(
+            Block::default()
+                .title("确认删除")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(app.theme.error)),
+        );
+    f.render_widget(ratatui::widgets::Clear, area);
+    f.render_widget(dialog, area);
+}
+
+// 渲染选中 todo 的子任务
+fn render_subtasks(f: &mut Frame, app: &mut App) {
+    let area = popup_rect(60, 60, f.area());
+    let Some(todo) = app.get_current_todo() else {
+        return;
+    };
+    let title = format!(
+        "子任务 - {} ({} 添加, {} 完成, {} 删除, Esc 关闭)",
+        todo.title,
+        app.keys.label(Action::AddItem),
+        app.keys.label(Action::ToggleComplete),
+        app.keys.label(Action::Delete)
+    );
+    let items: Vec<ListItem> = todo
+        .subtasks
+        .iter()
+        .map(|subtask| {
+            let checkbox = if subtask.completed { "[x]" } else { "[ ]" };
+            let item = ListItem::new(format!("{} {}", checkbox, subtask.title));
+            if subtask.completed {
+                item.style(app.theme.completed_style())
+            } else {
+                item
+            }
+        })
+        .collect();
+    let empty = items.is_empty();
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_style(app.theme.border_style(true)),
+        )
+        .highlight_style(app.theme.highlight_style())
+        .highlight_symbol(">> ");
+    f.render_widget(ratatui::widgets::Clear, area);
+    f.render_stateful_widget(list, area, &mut app.subtask_state);
+
+    if empty {
+        render_placeholder(
+            f,
+            area,
+            &format!("还没有子任务 — 按 {} 添加", app.keys.label(Action::AddItem)),
+            app.theme.help,
+        );
+    }
+}
+
+// 番茄钟阶段结束的提示，任意键关闭
+fn render_pomodoro_notice(f: &mut Frame, app: &App) {
+    let text = match &app.pomodoro {
+        Some(p) if p.phase == Phase::Break => format!(
+            "{} 番茄钟完成，计时已停止\n休息 {} 分钟吧",
+            app.icons().pomodoro,
+            app.config.pomodoro_break_minutes.max(1)
+        ),
+        _ => format!(
+            "{} 休息结束\n按 {} 在选中的任务上开始下一个番茄钟",
+            app.icons().coffee,
+            app.keys.label(Action::Pomodoro)
+        ),
+    };
+    let area = centered_rect(50, 6, f.area());
+    let dialog = Paragraph::new(text)
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true })
+        .block(
+            Block::default()
+                .title("番茄钟 (任意键关闭)")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(app.theme.alert)),
+        );
+    f.render_widget(ratatui::widgets::Clear, area);
+    f.render_widget(dialog, area);
+}
+
+// 询问如何处理上次退出时仍在进行的计时
+fn render_resume_timers(f: &mut Frame, app: &App) {
+    let now = now_secs();
+    let unit = app.config.duration_unit;
+    let mut lines: Vec<Line> = app
+        .projects
+        .iter()
+        .flat_map(|p| p.todos.iter().map(move |todo| (p, todo)))
+        .filter(|(_, todo)| todo.is_working())
+        .map(|(project, todo)| {
+            Line::from(format!(
+                "{} {} / {}  已计时 {}",
+                app.icons().timer,
+                project.name,
+                todo.title,
+                format_duration(todo.session_elapsed(now), unit)
+            ))
+        })
+        .collect();
+    lines.push(Line::from(""));
+    lines.push(Line::from(format!(
+        "上次退出于 {}，关闭了 {}",
+        report::format_time(app.interrupted_at),
+        format_duration(now.saturating_sub(app.interrupted_at), unit)
+    )));
+    lines.push(
+        Line::from("k 保留并继续计时  t 记到上次退出为止  d 丢弃这次计时")
+            .style(Style::default().fg(app.theme.help)),
+    );
+
+    let height = lines.len() as u16 + 2;
+    let area = centered_rect(60, height, f.area());
+    let dialog = Paragraph::new(lines).wrap(Wrap { trim: true }).block(
+        Block::default()
+            .title("上次的计时还没有结束")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(app.theme.alert)),
+    );
+    f.render_widget(ratatui::widgets::Clear, area);
+    f.render_widget(dialog, area);
+}
+
+// 询问是否保留空闲期间的计时
+fn render_idle_prompt(f: &mut Frame, app: &App) {
+    let idle = now_secs().saturating_sub(app.idle_since);
+    let lines = vec![
+        Line::from(format!(
+            "从 {} 起没有操作，已空闲 {}",
+            report::format_time(app.idle_since),
+            format_duration(idle, app.config.duration_unit)
+        )),
+        Line::from(""),
+        Line::from("k 保留空闲时间  d 丢弃空闲时间，从现在继续计时")
+            .style(Style::default().fg(app.theme.help)),
+    ];
+
+    let area = centered_rect(60, 5, f.area());
+    let dialog = Paragraph::new(lines).wrap(Wrap { trim: true }).block(
+        Block::default()
+            .title("计时中离开了一段时间")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(app.theme.alert)),
+    );
+    f.render_widget(ratatui::widgets::Clear, area);
+    f.render_widget(dialog, area);
+}
+
+fn render_corrupt_data(f: &mut Frame, app: &App) {
+    let lines = vec![
+        Line::from(app.message.as_ref().map_or("", |m| m.text.as_str())),
+        Line::from(""),
+        Line::from("n 使用示例数据重新开始  q 退出，不修改数据文件")
+            .style(Style::default().fg(app.theme.help)),
+    ];
+
+    let area = centered_rect(70, 8, f.area());
+    let dialog = Paragraph::new(lines).wrap(Wrap { trim: true }).block(
+        Block::default()
+            .title("数据文件已损坏")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(app.theme.alert)),
+    );
+    f.render_widget(ratatui::widgets::Clear, area);
+    f.render_widget(dialog, area);
+}
+
+// 渲染选中 todo 的计时记录，重复的 todo 之前几次的记录显示为灰色
+fn render_sessions(f: &mut Frame, app: &mut App) {
+    let area = popup_rect(60, 60, f.area());
+    let Some(todo) = app.get_current_todo() else {
+        return;
+    };
+    let unit = app.config.duration_unit;
+    let title = format!(
+        "计时记录 - {} 合计 {} ({} 补记, {} 修改, {} 删除, Esc 关闭)",
+        todo.title,
+        todo.format_duration(unit),
+        app.keys.label(Action::AddItem),
+        app.keys.label(Action::Rename),
+        app.keys.label(Action::Delete)
+    );
+    let items: Vec<ListItem> = todo
+        .sessions
+        .iter()
+        .map(|session| {
+            let item = ListItem::new(format!(
+                "{}  {}",
+                report::format_session(session.start, session.end),
+                format_duration(session.duration(), unit)
+            ));
+            if session.start < todo.counted_from {
+                item.style(Style::default().fg(app.theme.dimmed))
+            } else {
+                item
+            }
+        })
+        .collect();
+    let empty = items.is_empty();
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_style(app.theme.border_style(true)),
+        )
+        .highlight_style(app.theme.highlight_style())
+        .highlight_symbol(">> ");
+    f.render_widget(ratatui::widgets::Clear, area);
+    f.render_stateful_widget(list, area, &mut app.session_state);
+
+    if empty {
+        render_placeholder(f, area, "还没有计时记录", app.theme.help);
+    }
+}
+
+// 渲染按日期分组的时间报告
+fn render_report(f: &mut Frame, app: &mut App) {
+    let area = popup_rect(80, 80, f.area());
+    let now = now_secs();
+    let report = report::daily_report(&app.projects, app.report_range, app.report_offset, now);
+    let lines = report::report_lines(&report, app.config.duration_unit);
+
+    // 修正滚动位置，不超过最后一页
+    let visible = area.height.saturating_sub(2);
+    let max_scroll = (lines.len() as u16).saturating_sub(visible);
+    app.report_scroll = app.report_scroll.min(max_scroll);
+
+    let title = format!(
+        "时间报告 - {} ({} 切换范围, ←/→ 前后翻页, Esc 关闭)",
+        app.report_range
+            .label(app.report_offset, report::local_date(now)),
+        app.keys.label(Action::SwitchPanel)
+    );
+    let paragraph = Paragraph::new(lines.join("\n"))
+        .scroll((app.report_scroll, 0))
+        .block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_style(app.theme.border_style(true)),
+        );
+    f.render_widget(ratatui::widgets::Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+// 月历：左边是当月的日历，有截止的 todo 的日期带标记，右边是选中日期到期的 todo
+fn render_calendar(f: &mut Frame, app: &App) {
+    let area = popup_rect(80, 70, f.area());
+    let block = Block::default()
+        .title(format!(
+            "月历 - {} (←/→/↑/↓ 切换日期, PageUp/PageDown 翻月, Esc 关闭)",
+            app.calendar_day.format("%Y-%m")
+        ))
+        .borders(Borders::ALL)
+        .border_style(app.theme.border_style(true));
+    let inner = block.inner(area);
+    f.render_widget(ratatui::widgets::Clear, area);
+    f.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(30), Constraint::Min(0)])
+        .split(inner);
+
+    let today = today();
+    let mut lines = vec![
+        Line::from(" 一  二  三  四  五  六  日").style(Style::default().fg(app.theme.dimmed))
+    ];
+    for week in dates::month_weeks(app.calendar_day) {
+        let mut spans = Vec::new();
+        for day in week {
+            let Some(day) = day else {
+                spans.push(Span::raw("    "));
+                continue;
+            };
+            let due = report::due_on(&app.projects, day);
+            let pending = due.iter().any(|(_, t)| !t.completed);
+            let marker = match (pending, due.is_empty()) {
+                (true, _) => "•",
+                (false, false) => "·",
+                (false, true) => " ",
+            };
+            let mut style = match (pending, day < today) {
+                (true, true) => Style::default().fg(app.theme.overdue),
+                (true, false) => Style::default(),
+                (false, _) => Style::default().fg(app.theme.dimmed),
+            };
+            if day == today {
+                style = style.add_modifier(ratatui::style::Modifier::UNDERLINED);
+            }
+            if day == app.calendar_day {
+                style = app.theme.highlight_style();
+            }
+            spans.push(Span::styled(format!("{:>3}{}", day.day(), marker), style));
+        }
+        lines.push(Line::from(spans));
+    }
+    f.render_widget(Paragraph::new(lines), chunks[0]);
+
+    let due = report::due_on(&app.projects, app.calendar_day);
+    let items: Vec<ListItem> = match due.is_empty() {
+        true => {
+            vec![ListItem::new("这一天没有到期的 Todo").style(Style::default().fg(app.theme.dimmed))]
+        }
+        false => due
+            .iter()
+            .map(|(project, todo)| {
+                let checkbox = if todo.completed { "[x]" } else { "[ ]" };
+                let item = ListItem::new(format!("{} {} / {}", checkbox, project.name, todo.title));
+                match todo.completed {
+                    true => item.style(app.theme.completed_style()),
+                    false => item,
+                }
+            })
+            .collect(),
+    };
+    let list = List::new(items).block(
+        Block::default()
+            .title(app.calendar_day.format("%Y-%m-%d 到期").to_string())
+            .borders(Borders::LEFT)
+            .border_style(app.theme.border_style(false)),
+    );
+    f.render_widget(list, chunks[1]);
+}
+
+// 工作区弹窗，当前工作区变暗
+fn render_workspaces(f: &mut Frame, app: &mut App) {
+    let area = popup_rect(40, 50, f.area());
+    let items: Vec<ListItem> = app
+        .workspace_list
+        .iter()
+        .map(|workspace| {
+            let item = ListItem::new(workspace.as_deref().unwrap_or("默认").to_string());
+            match *workspace == app.workspace {
+                true => item.style(Style::default().fg(app.theme.dimmed)),
+                false => item,
+            }
+        })
+        .collect();
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title(format!(
+                    "工作区 (Enter 切换, {} 新建, Esc 取消)",
+                    app.keys.label(Action::AddItem)
+                ))
+                .borders(Borders::ALL)
+                .border_style(app.theme.border_style(true)),
+        )
+        .highlight_style(app.theme.highlight_style())
+        .highlight_symbol(">> ");
+    f.render_widget(ratatui::widgets::Clear, area);
+    f.render_stateful_widget(list, area, &mut app.workspace_state);
+}
+
+// 大号数字的字形，每个字符 5 行
+const BIG_GLYPHS: [(char, [&str; 5]); 11] = [
+    ('0', ["███", "█ █", "█ █", "█ █", "███"]),
+    ('1', ["  █", "  █", "  █", "  █", "  █"]),
+    ('2', ["███", "  █", "███", "█  ", "███"]),
+    ('3', ["███", "  █", "███", "  █", "███"]),
+    ('4', ["█ █", "█ █", "███", "  █", "  █"]),
+    ('5', ["███", "█  ", "███", "  █", "███"]),
+    ('6', ["███", "█  ", "███", "█ █", "███"]),
+    ('7', ["███", "  █", "  █", "  █", "  █"]),
+    ('8', ["███", "█ █", "███", "█ █", "███"]),
+    ('9', ["███", "█ █", "███", "  █", "███"]),
+    (':', [" ", "█", " ", "█", " "]),
+];
+
+// 把 "01:23:45" 这样的文本画成 5 行大号字，不认识的字符跳过
+fn big_text(text: &str) -> Vec<String> {
+    (0..5)
+        .map(|row| {
+            text.chars()
+                .filter_map(|c| BIG_GLYPHS.iter().find(|(g, _)| *g == c))
+                .map(|(_, glyph)| glyph[row])
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect()
+}
+
+// 沉浸模式：全屏显示正在计时的 todo、大号的本次计时、描述和子任务
+fn render_zen(f: &mut Frame, app: &App) {
+    let area = f.area();
+    f.render_widget(ratatui::widgets::Clear, area);
+    let block = Block::default()
+        .title(format!(
+            "沉浸模式 ({}/Esc 退出)",
+            app.keys.label(Action::ZenMode)
+        ))
+        .borders(Borders::ALL)
+        .border_style(app.theme.border_style(true));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let Some((project, todo)) = app.running_todo() else {
+        render_placeholder(f, inner, "计时已结束", app.theme.help);
+        return;
+    };
+    let now = now_secs();
+    let elapsed = todo.session_elapsed(now);
+    let clock = format!(
+        "{:02}:{:02}:{:02}",
+        elapsed / 3600,
+        elapsed / 60 % 60,
+        elapsed % 60
+    );
+
+    let mut lines = vec![
+        Line::from(format!("{} / {}", project.name, todo.title))
+            .style(Style::default().add_modifier(ratatui::style::Modifier::BOLD)),
+        Line::from(""),
+    ];
+    lines.extend(
+        big_text(&clock)
+            .into_iter()
+            .map(|row| Line::from(row).style(Style::default().fg(app.theme.timer))),
+    );
+    lines.push(Line::from(""));
+    lines.push(
+        Line::from(format!(
+            "累计 {}",
+            format_duration(todo.elapsed(now), app.config.duration_unit)
+        ))
+        .style(Style::default().fg(app.theme.dimmed)),
+    );
+    let header_height = lines.len() as u16;
+
+    let mut body: Vec<Line> = todo.description.lines().map(Line::from).collect();
+    if !todo.subtasks.is_empty() {
+        if !body.is_empty() {
+            body.push(Line::from(""));
+        }
+        for subtask in &todo.subtasks {
+            let checkbox = if subtask.completed { "[x]" } else { "[ ]" };
+            let line = Line::from(format!("{} {}", checkbox, subtask.title));
+            body.push(match subtask.completed {
+                true => line.style(app.theme.completed_style()),
+                false => line,
+            });
+        }
+    }
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Length(header_height),
+            Constraint::Length(1),
+            Constraint::Min(0),
+        ])
+        .split(inner);
+    f.render_widget(Paragraph::new(lines).alignment(Alignment::Center), rows[1]);
+    let body_area = popup_rect(60, 100, rows[3]);
+    f.render_widget(Paragraph::new(body).wrap(Wrap { trim: false }), body_area);
+}
+
+// 日程：按已过期、今天、本周、以后分组列出所有项目中有截止日期的 todo
+fn render_agenda(f: &mut Frame, app: &App) {
+    let area = popup_rect(70, 80, f.area());
+    let mut items = Vec::new();
+    let mut selected = None;
+    let mut group = None;
+    for (i, &(g, project_idx, todo_idx)) in app.agenda.iter().enumerate() {
+        // 每组前面是不可选的组名
+        if group != Some(g) {
+            group = Some(g);
+            items.push(ListItem::new(g.label()).style(Style::default().fg(app.theme.dimmed)));
+        }
+        if app.agenda_state.selected() == Some(i) {
+            selected = Some(items.len());
+        }
+        let project = &app.projects[project_idx];
+        let todo = &project.todos[todo_idx];
+        let due = todo
+            .due_date
+            .map(|due| due.format(&app.config.date_format).to_string())
+            .unwrap_or_default();
+        let item = ListItem::new(format!("{}  {} / {}", due, project.name, todo.title));
+        items.push(match g {
+            report::AgendaGroup::Overdue => item.style(Style::default().fg(app.theme.overdue)),
+            _ => item,
+        });
+    }
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title("日程 (Enter 跳转, Esc 关闭)")
+                .borders(Borders::ALL)
+                .border_style(app.theme.border_style(true)),
+        )
+        .highlight_style(app.theme.highlight_style())
+        .highlight_symbol(">> ");
+    f.render_widget(ratatui::widgets::Clear, area);
+    f.render_stateful_widget(
+        list,
+        area,
+        &mut ListState::default().with_selected(selected),
+    );
+}
+
+// 今日回顾：今天完成的、今天的耗时和今天到期的
+fn render_today(f: &mut Frame, app: &mut App) {
+    let area = popup_rect(70, 80, f.area());
+    let lines = report::today_lines(&app.projects, now_secs(), app.config.duration_unit);
+
+    // 修正滚动位置，不超过最后一页
+    let visible = area.height.saturating_sub(2);
+    let max_scroll = (lines.len() as u16).saturating_sub(visible);
+    app.today_scroll = app.today_scroll.min(max_scroll);
+
+    let paragraph = Paragraph::new(lines.join("\n"))
+        .scroll((app.today_scroll, 0))
+        .block(
+            Block::default()
+                .title(format!(
+                    "今日回顾 - {} (Esc 关闭)",
+                    report::format_day(today())
+                ))
+                .borders(Borders::ALL)
+                .border_style(app.theme.border_style(true)),
+        );
+    f.render_widget(ratatui::widgets::Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+// 统计面板：上方是各项目耗时的柱状图，中间是最近每天的耗时，下方是完成数量和最忙的几天
+fn render_stats(f: &mut Frame, app: &App) {
+    let area = popup_rect(90, 90, f.area());
+    let stats = report::stats(&app.projects, now_secs());
+    let unit = app.config.duration_unit;
+    let block = |title: String| {
+        Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(app.theme.border_style(true))
+    };
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(8),
+            Constraint::Length(5),
+            Constraint::Length(6 + report::BUSIEST_DAYS as u16),
+        ])
+        .split(area);
+    f.render_widget(ratatui::widgets::Clear, area);
+
+    // 每个项目一根柱子，柱子上显示耗时
+    let bars: Vec<Bar> = stats
+        .projects
+        .iter()
+        .map(|p| {
+            Bar::default()
+                .value(p.total)
+                .label(Line::from(truncate(&p.name, 10)))
+                .text_value(format_duration(p.total, unit))
+        })
+        .collect();
+    let chart = BarChart::default()
+        .block(block("各项目耗时 (Esc 关闭)".to_string()))
+        .data(BarGroup::default().bars(&bars))
+        .bar_width(10)
+        .bar_gap(2)
+        .bar_style(Style::default().fg(app.theme.border_active));
+    f.render_widget(chart, rows[0]);
+
+    let sparkline = Sparkline::default()
+        .block(block(format!("最近 {} 天每天的耗时", report::RECENT_DAYS)))
+        .data(&stats.recent_days)
+        .style(Style::default().fg(app.theme.border_active));
+    f.render_widget(sparkline, rows[1]);
+
+    let (completed, todos) = stats
+        .projects
+        .iter()
+        .fold((0, 0), |(c, t), p| (c + p.completed, t + p.todos));
+    let mut lines = vec![
+        format!("已完成 {} / {} 个任务", completed, todos),
+        format!("本周完成 {} 个任务", stats.completed_this_week),
+    ];
+    if let Some(average) = stats.average_completion {
+        lines.push(format!(
+            "从创建到完成平均 {}",
+            match average {
+                0 => "0m".to_string(),
+                _ => format_duration(average, unit),
+            }
+        ));
+    }
+    if stats.busiest_days.is_empty() {
+        lines.push("还没有计时记录".to_string());
+    } else {
+        lines.push("最忙的几天：".to_string());
+        for &(date, total) in &stats.busiest_days {
+            lines.push(format!(
+                "  {}  {}",
+                report::format_day(date),
+                format_duration(total, unit)
+            ));
+        }
+    }
+    let summary = Paragraph::new(lines.join("\n")).block(block("概览".to_string()));
+    f.render_widget(summary, rows[2]);
+}
+
+// 收起时描述显示的行数
+const DESCRIPTION_PREVIEW_LINES: u16 = 3;
+
+// 渲染描述：收起时只显示前几行，展开时显示全部并支持滚动
+fn render_description(f: &mut Frame, app: &mut App, description: &str, area: Rect) {
+    let toggle_key = app.keys.label(Action::ToggleDescription);
+    let lines: Vec<&str> = description.lines().collect();
+
+    let (text, title) = if app.description_expanded {
+        // 修正滚动位置，不超过最后一页
+        let visible = area.height.saturating_sub(2);
+        let max_scroll = (lines.len() as u16).saturating_sub(visible);
+        app.description_scroll = app.description_scroll.min(max_scroll);
+        (lines.join("\n"), format!("描述 (按 {} 收起)", toggle_key))
+    } else {
+        let preview = DESCRIPTION_PREVIEW_LINES as usize;
+        let mut text = lines[..lines.len().min(preview)].join("\n");
+        if lines.len() > preview {
+            text.push_str(&format!("\n… (按 {} 展开)", toggle_key));
+        }
+        (text, "描述".to_string())
+    };
+
+    let scroll = if app.description_expanded {
+        app.description_scroll
+    } else {
+        0
+    };
+    let paragraph = Paragraph::new(text)
+        .scroll((scroll, 0))
+        .block(Block::default().title(title).borders(Borders::ALL));
+    f.render_widget(paragraph, area);
+}
+
+// 根据当前按键绑定生成帮助信息
+fn help_text(keys: &KeyBindings) -> String {
+    let key = |action| keys.label(action);
+    format!(
+        "{}(切换) {}/{}(上下) {}(完成) {}(全部完成) {}(多选) {}(添加) {}(重命名) {}(计时) {}(报告) {}(专注) {}(排序) {}(删除) {}(保存) {}(退出) {}(全部按键)",
+        key(Action::SwitchPanel),
+        key(Action::MoveDown),
+        key(Action::MoveUp),
+        key(Action::ToggleComplete),
+        key(Action::ToggleAllComplete),
+        key(Action::SelectMode),
+        key(Action::AddItem),
+        key(Action::Rename),
+        key(Action::ToggleTimer),
+        key(Action::ShowReport),
+        key(Action::ToggleFocus),
+        key(Action::CycleSort),
+        key(Action::Delete),
+        key(Action::Save),
+        key(Action::Quit),
+        key(Action::ShowHelp),
+    )
+}
+
+// 渲染所有操作和当前生效的按键
+fn render_help(f: &mut Frame, app: &App) {
+    let area = popup_rect(60, 80, f.area());
+    let width = Action::ALL
+        .iter()
+        .map(|&action| Line::raw(app.keys.labels(action)).width())
+        .max()
+        .unwrap_or(0);
+    let lines: Vec<Line> = Action::ALL
+        .iter()
+        .map(|&action| {
+            let keys = app.keys.labels(action);
+            let padding = " ".repeat(width - Line::raw(keys.as_str()).width() + 2);
+            Line::from(vec![
+                Span::styled(keys, Style::default().fg(app.theme.border_active)),
+                Span::raw(padding),
+                Span::raw(action.description()),
+            ])
+        })
+        .collect();
+
+    let help = Paragraph::new(lines).scroll((app.help_scroll, 0)).block(
+        Block::default()
+            .title(format!(
+                "按键 ({}/{} 滚动, Esc 关闭)",
+                app.keys.label(Action::MoveDown),
+                app.keys.label(Action::MoveUp)
+            ))
+            .borders(Borders::ALL)
+            .border_style(app.theme.border_style(true)),
+    );
+    f.render_widget(ratatui::widgets::Clear, area);
+    f.render_widget(help, area);
+}
+
+// 耗时和预计耗时，如 "1h 20m / 2h"，还没有耗时时显示为 0m
+fn budget_label(elapsed: u64, estimate: u64, unit: DurationUnit) -> String {
+    let elapsed = match elapsed {
+        0 => "0m".to_string(),
+        _ => format_duration(elapsed, unit),
+    };
+    format!("{} / {}", elapsed, format_duration(estimate, unit))
+}
+
+// 标题前的优先级标记，高优先级用提醒色
+fn priority_span(app: &App, priority: Option<Priority>) -> Span<'static> {
+    match priority {
+        None => Span::raw(""),
+        Some(Priority::High) => Span::styled("!!! ", Style::default().fg(app.theme.alert)),
+        Some(Priority::Medium) => Span::raw("!! "),
+        Some(Priority::Low) => Span::raw("! "),
+    }
+}
+
+// 提示级别对应的颜色
+fn level_color(app: &App, level: Level) -> Color {
+    match level {
+        Level::Info => app.theme.info,
+        Level::Warning => app.theme.warning,
+        Level::Error => app.theme.error,
+    }
+}
+
+// 渲染最近的提示记录，最新的在最上面
+// 选中 todo 的详情，包括创建和完成时间
+fn render_details(f: &mut Frame, app: &App) {
+    let Some(todo) = app.get_current_todo() else {
+        return;
+    };
+    let area = popup_rect(60, 50, f.area());
+    let details = Paragraph::new(report::todo_details(todo, app.config.duration_unit))
+        .wrap(Wrap { trim: false })
+        .block(
+            Block::default()
+                .title("详情 (Esc 关闭)")
+                .borders(Borders::ALL)
+                .border_style(app.theme.border_style(true)),
+        );
+    f.render_widget(ratatui::widgets::Clear, area);
+    f.render_widget(details, area);
+}
+
+// 详情面板，内容超出时截断，完整的计时记录可以在计时记录视图中查看
+fn render_detail_pane(f: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default()
+        .title("详情")
+        .borders(Borders::ALL)
+        .border_style(app.theme.border_style(false));
+    let Some(todo) = app.get_current_todo() else {
+        f.render_widget(block, area);
+        render_placeholder(f, area, "没有选中的任务", app.theme.help);
+        return;
+    };
+    let details = report::todo_full_details(todo, app.config.duration_unit, now_secs());
+    let paragraph = Paragraph::new(details)
+        .wrap(Wrap { trim: false })
+        .block(block);
+    f.render_widget(paragraph, area);
+}
+
+// 活动记录，从新到旧
+fn render_activity(f: &mut Frame, app: &App) {
+    let area = popup_rect(80, 80, f.area());
+    let lines: Vec<Line> = match app.activity_log.is_empty() {
+        true => vec![Line::from("还没有活动记录").style(Style::default().fg(app.theme.dimmed))],
+        false => app
+            .activity_log
+            .iter()
+            .map(|event| Line::from(event.format()))
+            .collect(),
+    };
+
+    let log = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .scroll((app.activity_scroll, 0))
+        .block(
+            Block::default()
+                .title(format!(
+                    "活动记录 ({} 条, 用 std activity 导出, Esc 关闭)",
+                    app.activity_log.len()
+                ))
+                .borders(Borders::ALL)
+                .border_style(app.theme.border_style(true)),
+        );
+    f.render_widget(ratatui::widgets::Clear, area);
+    f.render_widget(log, area);
+}
+
+fn render_messages(f: &mut Frame, app: &App) {
+    let area = popup_rect(70, 60, f.area());
+    let lines: Vec<Line> = match app.messages.is_empty() {
+        true => vec![Line::from("还没有提示").style(Style::default().fg(app.theme.dimmed))],
+        false => app
+            .messages
+            .iter()
+            .map(|message| {
+                Line::from(vec![
+                    Span::styled(
+                        format!("{} ", report::format_time(message.at)),
+                        Style::default().fg(app.theme.dimmed),
+                    ),
+                    Span::styled(
+                        message.text.as_str(),
+                        Style::default().fg(level_color(app, message.level)),
+                    ),
+                ])
+            })
+            .collect(),
+    };
+
+    let log = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .scroll((app.messages_scroll, 0))
+        .block(
+            Block::default()
+                .title(format!("最近的提示 ({} 条, Esc 关闭)", app.messages.len()))
+                .borders(Borders::ALL)
+                .border_style(app.theme.border_style(true)),
+        );
+    f.render_widget(ratatui::widgets::Clear, area);
+    f.render_widget(log, area);
+}
+
+// 按显示宽度截断字符串，中文和 emoji 占两列，不会在字符或字素簇中间切开
+fn truncate(s: &str, max_width: usize) -> String {
+    let mut width = 0;
+    s.graphemes(true)
+        .take_while(|g| {
+            width += g.width();
+            width <= max_width
+        })
+        .collect()
+}
+
+// 按百分比计算居中的弹窗区域
+fn popup_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let popup_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(r);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(popup_layout[1])[1]
+}
+
+fn centered_rect(percent_x: u16, height: u16, r: ratatui::layout::Rect) -> ratatui::layout::Rect {
+    let popup_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(r.height.saturating_sub(height) / 2),
+            Constraint::Length(height),
+            Constraint::Length(r.height.saturating_sub(height) / 2),
+        ])
+        .split(r);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(popup_layout[1])[1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Project, Todo};
+    use ratatui::{backend::TestBackend, Terminal};
+
+    fn sample_app() -> App {
+        let mut todo = Todo::new("一个很长很长很长很长很长的任务标题".to_string());
+        todo.log_session(0, 3661);
+        App::with_projects(vec![Project {
+            todos: vec![todo],
+            ..Project::new("很长的中文项目名称".to_string())
+        }])
+    }
+
+    #[test]
+    fn truncation_counts_wide_characters_and_keeps_graphemes_whole() {
+        assert_eq!(truncate("中文标题", 5), "中文");
+        assert_eq!(truncate("ab中", 3), "ab");
+        // 家庭 emoji 由多个码点组成，不能切开
+        let family = "👨\u{200d}👩\u{200d}👧";
+        assert_eq!(truncate(&format!("{}x", family), 1), "");
+        assert_eq!(truncate(&format!("{}x", family), 2), family);
+        assert_eq!(truncate("e\u{301}f", 1), "e\u{301}");
+    }
+
+    #[test]
+    fn renders_at_any_size_without_panicking() {
+        let mut app = sample_app();
+        app.input_mode = InputMode::AddingTodo;
+        // 覆盖 <80 的垂直布局、<120 的中等布局和宽屏布局，以及极小的终端
+        for (width, height) in [
+            (1, 1),
+            (10, 3),
+            (15, 5),
+            (40, 6),
+            (79, 24),
+            (80, 24),
+            (119, 30),
+            (160, 40),
+        ] {
+            let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+            terminal.draw(|f| ui(f, &mut app)).unwrap();
+        }
+    }
+
+    #[test]
+    fn running_timer_is_shown_in_status_line() {
+        let mut app = App::with_projects(vec![Project {
+            todos: vec![Todo::new("write".to_string())],
+            ..Project::new("work".to_string())
+        }]);
+        app.projects[0].todos[0].start_time = Some(now_secs() - 65);
+        let mut terminal = Terminal::new(TestBackend::new(100, 10)).unwrap();
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        let text = buffer_text(&terminal);
+        let status = text.lines().last().unwrap();
+        assert!(status.contains("work / write 1m"));
+        // 列表中的耗时包括正在进行的计时
+        assert!(text.contains("write [1m"));
+    }
+
+    #[test]
+    fn stats_view_renders_at_any_size() {
+        let mut app = sample_app();
+        app.view = View::Stats;
+        for (width, height) in [(1, 1), (20, 6), (80, 24), (160, 40)] {
+            let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+            terminal.draw(|f| ui(f, &mut app)).unwrap();
+        }
+        let mut terminal = Terminal::new(TestBackend::new(100, 40)).unwrap();
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        assert!(buffer_text(&terminal)
+            .replace(' ', "")
+            .contains("已完成0/1"));
+    }
+
+    #[test]
+    fn long_lists_show_a_scrollbar_and_keep_the_selection_visible() {
+        let mut app = App::with_projects(vec![Project {
+            todos: (0..30).map(|i| Todo::new(format!("t{:02}", i))).collect(),
+            ..Project::new("work".to_string())
+        }]);
+        app.switch_panel();
+        for _ in 0..20 {
+            app.select_next();
+        }
+        let mut terminal = Terminal::new(TestBackend::new(100, 12)).unwrap();
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        let text = buffer_text(&terminal);
+        // 选中的行和它后面的一行都在可见范围内
+        assert!(text.contains("t20") && text.contains("t21"));
+        assert!(!text.contains("t00"));
+        assert!(text.contains('█'));
+
+        // 放得下时不显示滚动条
+        let mut app = sample_app();
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        assert!(!buffer_text(&terminal).contains('█'));
+    }
+
+    #[test]
+    fn detail_pane_shows_subtasks_and_sessions() {
+        let mut app = sample_app();
+        let todo = &mut app.projects[0].todos[0];
+        todo.description = "说明".to_string();
+        todo.priority = Some(Priority::High);
+        todo.subtasks = vec![crate::model::Subtask {
+            title: "子任务一".to_string(),
+            completed: true,
+        }];
+        app.switch_panel();
+        let mut terminal = Terminal::new(TestBackend::new(120, 30)).unwrap();
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        assert!(!buffer_text(&terminal).contains("计时记录"));
+
+        app.toggle_detail_pane();
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        let text = buffer_text(&terminal).replace(' ', "");
+        assert!(text.contains("优先级：高"));
+        assert!(text.contains("[x]子任务一"));
+        assert!(text.contains("计时记录(1)"));
+
+        // 窄屏时显示在列表下方
+        let mut terminal = Terminal::new(TestBackend::new(60, 40)).unwrap();
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        assert!(buffer_text(&terminal).replace(' ', "").contains("说明"));
+    }
+
+    #[test]
+    fn adjusted_split_is_used_on_wide_screens_only() {
+        let mut app = sample_app();
+        app.project_split = Some(50);
+        let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        assert_eq!(app.project_list_area.width, 50);
+
+        // 比例太小时项目面板保留最小宽度
+        app.project_split = Some(15);
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        assert_eq!(app.project_list_area.width, 20);
+
+        // 窄屏时仍然上下排列
+        let mut terminal = Terminal::new(TestBackend::new(60, 30)).unwrap();
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        assert_eq!(app.project_list_area.width, 60);
+    }
+
+    #[test]
+    fn help_line_only_shown_when_tall_enough() {
+        let mut app = sample_app();
+        let mut terminal = Terminal::new(TestBackend::new(100, 5)).unwrap();
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        let last_row: String = (0..100)
+            .map(|x| terminal.backend().buffer()[(x, 4)].symbol().to_string())
+            .collect();
+        assert!(!last_row.contains("Tab("));
+
+        terminal.backend_mut().resize(100, 6);
+        terminal.autoresize().unwrap();
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        let last_row: String = (0..100)
+            .map(|x| terminal.backend().buffer()[(x, 5)].symbol().to_string())
+            .collect();
+        assert!(last_row.starts_with("Tab("));
+    }
+
+    fn buffer_text(terminal: &Terminal<TestBackend>) -> String {
+        let buffer = terminal.backend().buffer();
+        (0..buffer.area.height)
+            .map(|y| {
+                (0..buffer.area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn long_description_is_collapsed_until_expanded() {
+        let mut app = sample_app();
+        app.projects[0].todos[0].description = "l1\nl2\nl3\nl4\nl5".to_string();
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        let text = buffer_text(&terminal);
+        assert!(text.contains("l3"));
+        assert!(!text.contains("l4"));
+        assert!(text.contains("…"));
+
+        app.toggle_description();
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        let text = buffer_text(&terminal);
+        assert!(text.contains("l5"));
+        assert!(!text.contains("…"));
+    }
+
+    #[test]
+    fn empty_panels_show_guidance() {
+        let mut app = App::with_projects(vec![Project::new("p".to_string())]);
+        let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        // 去掉空格比较，宽字符后面的占位格也是空格
+        let text = buffer_text(&terminal).replace(' ', "");
+        assert!(text.contains("没有任务—按Tab切换到此面板后按a添加"));
+
+        app.switch_panel();
+        // TestBackend 不会覆盖宽字符后面的格子，重绘前先清空
+        terminal.clear().unwrap();
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        let text = buffer_text(&terminal).replace(' ', "");
+        assert!(text.contains("没有任务—按a添加任务"));
+
+        app.switch_panel();
+        app.delete_selected_project();
+        // TestBackend 不会覆盖宽字符后面的格子，重绘前先清空
+        terminal.clear().unwrap();
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        let text = buffer_text(&terminal).replace(' ', "");
+        assert!(text.contains("还没有项目—按a添加一个"));
+        assert!(text.contains("先在项目面板添加一个项目"));
+    }
+
+    #[test]
+    fn focus_mode_hides_project_panel() {
+        let mut app = sample_app();
+        let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        assert!(buffer_text(&terminal).contains("📁"));
+
+        app.toggle_focus_mode();
+        terminal.clear().unwrap();
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        let text = buffer_text(&terminal);
+        assert!(!text.contains("📁"));
+        // 标题从最左边开始，说明 Todo 面板占满了宽度
+        assert!(text.lines().next().unwrap().starts_with("┌Todo"));
+    }
+
+    #[test]
+    fn single_pane_shows_only_the_active_panel() {
+        let mut app = sample_app();
+        app.single_pane = true;
+        let mut terminal = Terminal::new(TestBackend::new(60, 20)).unwrap();
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        assert_eq!(app.project_list_area.height, 19);
+        assert_eq!(app.todo_list_area, Rect::default());
+
+        app.switch_panel();
+        terminal.clear().unwrap();
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        assert!(!buffer_text(&terminal).contains("📁"));
+        assert_eq!(app.project_list_area, Rect::default());
+        assert_eq!(app.todo_list_area.width, 60);
+    }
+
+    #[test]
+    fn zen_mode_shows_only_the_timed_todo() {
+        let mut todo = Todo::new("write".to_string());
+        todo.description = "first draft".to_string();
+        todo.subtasks = vec![crate::model::Subtask {
+            title: "outline".to_string(),
+            completed: false,
+        }];
+        todo.start_time = Some(now_secs() - 65);
+        let mut app = App::with_projects(vec![Project {
+            todos: vec![todo, Todo::new("other".to_string())],
+            ..Project::new("work".to_string())
+        }]);
+        app.open_zen();
+        assert!(app.view == View::Zen);
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 24)).unwrap();
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        let text = buffer_text(&terminal);
+        assert!(text.contains("work / write"));
+        assert!(text.contains("first draft"));
+        assert!(text.contains("[ ] outline"));
+        assert!(!text.contains("other"));
+        assert!(!text.contains("📁"));
+    }
+
+    #[test]
+    fn big_text_draws_digits_side_by_side() {
+        assert_eq!(big_text("1:0")[0], "  █   ███");
+        assert_eq!(big_text("1:0")[1], "  █ █ █ █");
+    }
+
+    #[test]
+    fn ascii_mode_replaces_emoji() {
+        let mut app = sample_app();
+        app.toggle_ascii();
+        let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+        terminal.draw(|f| ui(f, &mut app)).unwrap();
+        let text = buffer_text(&terminal);
+        assert!(!text.contains("📁") && !text.contains("⭕"));
+        assert!(text.contains("+ "));
+        assert!(text.contains("[ ]"));
+    }
+}